@@ -6,6 +6,24 @@ mod m20220101_000001_users;
 mod m20260201_113639_settings;
 mod m20260202_143532_images;
 mod m20260206_114421_tmps;
+mod m20261019_093012_add_metadata_to_images;
+mod m20261019_101544_albums;
+mod m20261019_104210_tags;
+mod m20261019_112305_quotas;
+mod m20261019_120418_add_delete_token_to_images;
+mod m20261019_124733_add_expiry_to_images;
+mod m20261019_131206_image_stats;
+mod m20261019_135512_add_disabled_at_to_users;
+mod m20261019_142208_moderation;
+mod m20261019_151733_add_broken_at_to_images;
+mod m20261019_163025_regenerations;
+mod m20261019_171846_exports;
+mod m20261019_180522_add_deletion_to_users;
+mod m20261019_190214_imports;
+mod m20261019_195837_tus_uploads;
+mod m20261019_203114_pending_removals;
+mod m20261019_211542_add_tier_failed_at_to_images;
+mod m20261019_214420_add_original_ext_to_images;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -16,6 +34,24 @@ impl MigratorTrait for Migrator {
             Box::new(m20260201_113639_settings::Migration),
             Box::new(m20260202_143532_images::Migration),
             Box::new(m20260206_114421_tmps::Migration),
+            Box::new(m20261019_093012_add_metadata_to_images::Migration),
+            Box::new(m20261019_101544_albums::Migration),
+            Box::new(m20261019_104210_tags::Migration),
+            Box::new(m20261019_112305_quotas::Migration),
+            Box::new(m20261019_120418_add_delete_token_to_images::Migration),
+            Box::new(m20261019_124733_add_expiry_to_images::Migration),
+            Box::new(m20261019_131206_image_stats::Migration),
+            Box::new(m20261019_135512_add_disabled_at_to_users::Migration),
+            Box::new(m20261019_142208_moderation::Migration),
+            Box::new(m20261019_151733_add_broken_at_to_images::Migration),
+            Box::new(m20261019_163025_regenerations::Migration),
+            Box::new(m20261019_171846_exports::Migration),
+            Box::new(m20261019_180522_add_deletion_to_users::Migration),
+            Box::new(m20261019_190214_imports::Migration),
+            Box::new(m20261019_195837_tus_uploads::Migration),
+            Box::new(m20261019_203114_pending_removals::Migration),
+            Box::new(m20261019_211542_add_tier_failed_at_to_images::Migration),
+            Box::new(m20261019_214420_add_original_ext_to_images::Migration),
             // inject-above (do not remove this comment)
         ]
     }
@@ -0,0 +1,38 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "albums",
+            &[
+                ("id", ColType::PkAuto),
+                ("pid", ColType::Uuid),
+                ("user_pid", ColType::Uuid),
+                ("name", ColType::StringLen(255)),
+                ("description", ColType::TextNull),
+                ("public", ColType::BooleanWithDefault(false)),
+            ],
+            &[("images?", "cover_image_id")],
+        )
+        .await?;
+
+        create_join_table(
+            m,
+            "album_images",
+            &[("position", ColType::Integer)],
+            &[("albums", ""), ("images", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "album_images").await?;
+        drop_table(m, "albums").await
+    }
+}
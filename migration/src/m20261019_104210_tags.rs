@@ -0,0 +1,46 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "tags",
+            &[
+                ("id", ColType::PkAuto),
+                ("user_pid", ColType::Uuid),
+                ("name", ColType::StringLen(32)),
+            ],
+            &[],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-tags-user_pid-name")
+                .table(Alias::new("tags"))
+                .col(Alias::new("user_pid"))
+                .col(Alias::new("name"))
+                .unique()
+                .to_owned(),
+        )
+        .await?;
+
+        create_join_table(
+            m,
+            "image_tags",
+            &[],
+            &[("images", ""), ("tags", "")],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "image_tags").await?;
+        drop_table(m, "tags").await
+    }
+}
@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "images", "size", ColType::BigIntegerWithDefault(0)).await?;
+        add_column(m, "users", "storage_quota", ColType::BigIntegerNull).await?;
+        add_column(m, "users", "image_quota", ColType::IntegerNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "users", "image_quota").await?;
+        remove_column(m, "users", "storage_quota").await?;
+        remove_column(m, "images", "size").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "images", "expires_at", ColType::TimestampWithTimeZoneNull).await?;
+        add_column(m, "images", "max_views", ColType::IntegerNull).await?;
+        add_column(m, "images", "views", ColType::IntegerWithDefault(0)).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "images", "views").await?;
+        remove_column(m, "images", "max_views").await?;
+        remove_column(m, "images", "expires_at").await?;
+        Ok(())
+    }
+}
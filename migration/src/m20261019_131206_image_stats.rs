@@ -0,0 +1,41 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "image_stats",
+            &[
+                ("id", ColType::PkAuto),
+                ("day", ColType::Date),
+                // referring domain, empty for direct requests
+                ("referrer", ColType::StringLen(255)),
+                ("views", ColType::BigIntegerWithDefault(0)),
+                ("bytes", ColType::BigIntegerWithDefault(0)),
+            ],
+            &[("images", "")],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-image_stats-image_id-day-referrer")
+                .table(Alias::new("image_stats"))
+                .col(Alias::new("image_id"))
+                .col(Alias::new("day"))
+                .col(Alias::new("referrer"))
+                .unique()
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "image_stats").await
+    }
+}
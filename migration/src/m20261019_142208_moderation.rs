@@ -0,0 +1,59 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(
+            m,
+            "images",
+            "taken_down_at",
+            ColType::TimestampWithTimeZoneNull,
+        )
+        .await?;
+        add_column(m, "images", "takedown_reason", ColType::TextNull).await?;
+
+        create_table(
+            m,
+            "abuse_reports",
+            &[
+                ("id", ColType::PkAuto),
+                ("reason", ColType::Text),
+                ("contact", ColType::StringLen(255)),
+                (
+                    "status",
+                    ColType::Enum(
+                        "report_status".to_string(),
+                        vec![
+                            "open".to_string(),
+                            "dismissed".to_string(),
+                            "taken_down".to_string(),
+                        ],
+                    ),
+                ),
+                ("resolved_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[("images", "")],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-abuse_reports-status")
+                .table(Alias::new("abuse_reports"))
+                .col(Alias::new("status"))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "abuse_reports").await?;
+        remove_column(m, "images", "takedown_reason").await?;
+        remove_column(m, "images", "taken_down_at").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "regenerations",
+            &[
+                ("id", ColType::PkAuto),
+                (
+                    "status",
+                    ColType::Enum(
+                        "regeneration_status".to_string(),
+                        vec![
+                            "queued".to_string(),
+                            "running".to_string(),
+                            "finished".to_string(),
+                            "failed".to_string(),
+                        ],
+                    ),
+                ),
+                ("user_pid", ColType::UuidNull),
+                (
+                    "location",
+                    ColType::EnumNull(
+                        "location".to_string(),
+                        vec!["local".to_string(), "r2".to_string()],
+                    ),
+                ),
+                ("created_from", ColType::TimestampWithTimeZoneNull),
+                ("created_to", ColType::TimestampWithTimeZoneNull),
+                ("quality", ColType::Integer),
+                ("delay_ms", ColType::Integer),
+                ("total", ColType::Integer),
+                ("encoded", ColType::Integer),
+                ("skipped", ColType::Integer),
+                ("failed", ColType::Integer),
+                ("error", ColType::TextNull),
+                ("finished_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "regenerations").await
+    }
+}
@@ -0,0 +1,68 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "exports",
+            &[
+                ("id", ColType::PkAuto),
+                ("user_pid", ColType::Uuid),
+                (
+                    "status",
+                    ColType::Enum(
+                        "export_status".to_string(),
+                        vec![
+                            "queued".to_string(),
+                            "running".to_string(),
+                            "ready".to_string(),
+                            "failed".to_string(),
+                            "expired".to_string(),
+                        ],
+                    ),
+                ),
+                (
+                    "variant",
+                    ColType::Enum(
+                        "export_variant".to_string(),
+                        vec!["original".to_string(), "avif".to_string()],
+                    ),
+                ),
+                (
+                    "location",
+                    ColType::EnumNull(
+                        "location".to_string(),
+                        vec!["local".to_string(), "r2".to_string()],
+                    ),
+                ),
+                ("file_key", ColType::StringNull),
+                ("size", ColType::BigIntegerNull),
+                ("images", ColType::Integer),
+                ("missing", ColType::Integer),
+                ("error", ColType::TextNull),
+                ("finished_at", ColType::TimestampWithTimeZoneNull),
+                ("expires_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-exports-user_pid")
+                .table(Alias::new("exports"))
+                .col(Alias::new("user_pid"))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "exports").await
+    }
+}
@@ -0,0 +1,60 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "imports",
+            &[
+                ("id", ColType::PkAuto),
+                ("user_pid", ColType::Uuid),
+                (
+                    "status",
+                    ColType::Enum(
+                        "import_status".to_string(),
+                        vec![
+                            "queued".to_string(),
+                            "running".to_string(),
+                            "done".to_string(),
+                            "failed".to_string(),
+                        ],
+                    ),
+                ),
+                (
+                    "structure",
+                    ColType::Enum(
+                        "import_structure".to_string(),
+                        vec!["flat".to_string(), "albums".to_string(), "tags".to_string()],
+                    ),
+                ),
+                ("file_name", ColType::String),
+                ("total", ColType::Integer),
+                ("imported", ColType::Integer),
+                ("skipped", ColType::Integer),
+                ("failed", ColType::Integer),
+                ("error", ColType::TextNull),
+                ("finished_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-imports-user_pid")
+                .table(Alias::new("imports"))
+                .col(Alias::new("user_pid"))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "imports").await
+    }
+}
@@ -0,0 +1,33 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "tus_uploads",
+            &[
+                ("id", ColType::PkAuto),
+                ("uuid", ColType::UuidUniq),
+                ("user_pid", ColType::UuidNull),
+                ("raw_name", ColType::String),
+                ("upload_length", ColType::BigInteger),
+                ("upload_offset", ColType::BigInteger),
+                ("quality", ColType::SmallInteger),
+                ("public", ColType::Boolean),
+                ("expires_in", ColType::BigIntegerNull),
+                ("max_views", ColType::IntegerNull),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "tus_uploads").await
+    }
+}
@@ -0,0 +1,45 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(
+            m,
+            "pending_removals",
+            &[
+                ("id", ColType::PkAuto),
+                (
+                    "location",
+                    ColType::Enum(
+                        "location".to_string(),
+                        vec!["local".to_string(), "r2".to_string()],
+                    ),
+                ),
+                ("file_name", ColType::Text),
+                ("original_key", ColType::Text),
+                ("attempts", ColType::Integer),
+                ("retry_at", ColType::TimestampWithTimeZone),
+                ("error", ColType::TextNull),
+            ],
+            &[],
+        )
+        .await?;
+
+        m.create_index(
+            Index::create()
+                .name("idx-pending_removals-retry_at")
+                .table(Alias::new("pending_removals"))
+                .col(Alias::new("retry_at"))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "pending_removals").await
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(
+            m,
+            "images",
+            "original_ext",
+            ColType::StringWithDefault(String::new()),
+        )
+        .await?;
+
+        // the name is all existing images have to go by
+        let db = m.get_connection();
+        let backend = db.get_database_backend();
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([Alias::new("id"), Alias::new("raw_name")])
+                        .from(Alias::new("images")),
+                ),
+            )
+            .await?;
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let raw_name: String = row.try_get("", "raw_name")?;
+            let Some(ext) = Path::new(&raw_name).extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(Alias::new("images"))
+                        .value(Alias::new("original_ext"), ext)
+                        .and_where(Expr::col(Alias::new("id")).eq(id)),
+                ),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "images", "original_ext").await?;
+        Ok(())
+    }
+}
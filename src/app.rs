@@ -15,7 +15,11 @@ use migration::Migrator;
 use std::path::Path;
 
 use crate::{
-    common::client::{init_garage, init_r2},
+    common::{
+        auth::reject_disabled,
+        client::{init_garage, init_r2},
+        stats,
+    },
     models::_entities::settings,
 };
 #[allow(unused_imports)]
@@ -58,13 +62,41 @@ impl Hooks for App {
             .add_route(controllers::auth::routes())
             .add_route(controllers::settings::routes())
             .add_route(controllers::upload::routes())
+            .add_route(controllers::tus::routes())
             .add_route(controllers::view::routes())
             .add_route(controllers::profile::router())
+            .add_route(controllers::image::routes())
+            .add_route(controllers::import::routes())
+            .add_route(controllers::album::routes())
+            .add_route(controllers::tag::routes())
+            .add_route(controllers::admin::routes())
+            .add_route(controllers::stats::routes())
     }
     async fn connect_workers(ctx: &AppContext, queue: &Queue) -> Result<()> {
         queue
             .register(crate::workers::thumbnail::Worker::build(ctx))
             .await?;
+        queue
+            .register(crate::workers::remover::Worker::build(ctx))
+            .await?;
+        queue
+            .register(crate::workers::purger::Worker::build(ctx))
+            .await?;
+        queue
+            .register(crate::workers::relocator::Worker::build(ctx))
+            .await?;
+        queue
+            .register(crate::workers::regenerator::Worker::build(ctx))
+            .await?;
+        queue
+            .register(crate::workers::tiering::Worker::build(ctx))
+            .await?;
+        queue
+            .register(crate::workers::account_deleter::Worker::build(ctx))
+            .await?;
+        queue
+            .register(crate::workers::importer::Worker::build(ctx))
+            .await?;
         queue.register(DownloadWorker::build(ctx)).await?;
         Ok(())
     }
@@ -72,6 +104,12 @@ impl Hooks for App {
     #[allow(unused_variables)]
     fn register_tasks(tasks: &mut Tasks) {
         tasks.register(tasks::admin::Admin);
+        tasks.register(tasks::check_storage::CheckStorage);
+        tasks.register(tasks::purge_expired::PurgeExpired);
+        tasks.register(tasks::relocate::RelocateImages);
+        tasks.register(tasks::tier_images::TierImages);
+        tasks.register(tasks::regenerate::RegenerateDerivatives);
+        tasks.register(tasks::delete_accounts::DeleteAccounts);
         // tasks-inject (do not remove)
     }
     async fn truncate(ctx: &AppContext) -> Result<()> {
@@ -88,7 +126,15 @@ impl Hooks for App {
         .await?;
         Ok(())
     }
-    async fn after_routes(router: Router, _ctx: &AppContext) -> Result<Router> {
-        Ok(router)
+    async fn after_routes(router: Router, ctx: &AppContext) -> Result<Router> {
+        stats::spawn_flusher(ctx);
+
+        Ok(router.layer(axum::middleware::from_fn_with_state(
+            ctx.clone(),
+            reject_disabled,
+        )))
+    }
+    async fn on_shutdown(ctx: &AppContext) {
+        stats::flush(&ctx.db).await;
     }
 }
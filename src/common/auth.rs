@@ -0,0 +1,32 @@
+//! Checks applied to every authenticated request.
+
+use axum::{extract::Request, middleware::Next};
+use loco_rs::{controller::extractor::auth::extract_jwt_from_request_parts, prelude::*};
+
+use crate::models::users::users;
+
+/// Rejects requests carrying the JWT of a disabled user, or of one deleting
+/// their account. Tokens are checked here rather than in each handler so that
+/// ones issued before the account was disabled stop working everywhere. API
+/// tokens of such users are never found, see `Authenticable for users::Model`.
+pub async fn reject_disabled(
+    State(ctx): State<AppContext>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    if let Ok(jwt) = extract_jwt_from_request_parts(&parts, &ctx) {
+        match users::Model::is_locked_pid(&ctx.db, &jwt.claims.pid).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return Error::Unauthorized("Account locked".to_string()).into_response();
+            }
+            Err(e) => {
+                tracing::error!("Failed to check user state: {}", e);
+                return Error::InternalServerError.into_response();
+            }
+        }
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
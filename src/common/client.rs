@@ -1,6 +1,6 @@
 use std::{
     error::Error,
-    sync::{Arc, OnceLock},
+    sync::{Arc, PoisonError, RwLock},
     time::Duration,
 };
 
@@ -8,9 +8,12 @@ use aws_config::{Region, SdkConfig};
 use aws_sdk_s3::{
     Client,
     config::{Credentials, SharedCredentialsProvider, http::HttpResponse},
-    error::SdkError,
+    error::{DisplayErrorContext, SdkError},
     operation::{
+        delete_object::{DeleteObjectError, DeleteObjectOutput},
         get_object::{GetObjectError, GetObjectOutput},
+        head_object::{HeadObjectError, HeadObjectOutput},
+        list_objects_v2::ListObjectsV2Error,
         put_object::{PutObjectError, PutObjectOutput},
     },
     presigning::{PresignedRequest, PresigningConfig},
@@ -20,11 +23,13 @@ use loco_rs::app::AppContext;
 
 use crate::common::settings::SettingsService;
 
-static S3_GARAGE: OnceLock<Arc<S3Client>> = OnceLock::new();
-static S3_R2: OnceLock<Arc<R2Client>> = OnceLock::new();
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+static S3_GARAGE: RwLock<Option<Arc<S3Client>>> = RwLock::new(None);
+static S3_R2: RwLock<Option<Arc<R2Client>>> = RwLock::new(None);
 
 pub async fn init_garage(ctx: &AppContext) {
-    if S3_GARAGE.get().is_some() {
+    if read(&S3_GARAGE).is_some() {
         return;
     }
 
@@ -38,11 +43,11 @@ pub async fn init_garage(ctx: &AppContext) {
     let s3_client =
         Arc::new(S3Client::new(origin_bucket_name, preview_bucket_name, avif_bucket_name).await);
 
-    S3_GARAGE.set(s3_client).expect("初始化S3客户端失败");
+    replace(&S3_GARAGE, s3_client);
 }
 
 pub async fn init_r2(ctx: &AppContext) {
-    if S3_R2.get().is_some() {
+    if read(&S3_R2).is_some() {
         return;
     }
 
@@ -53,34 +58,43 @@ pub async fn init_r2(ctx: &AppContext) {
     let bucket_name = SettingsService::r2_bucket_name().await;
     let r2_client = Arc::new(R2Client::new(bucket_name).await);
 
-    S3_R2.set(r2_client).expect("初始化R2客户端失败");
+    replace(&S3_R2, r2_client);
 }
 
+/// Rebuilds both clients from the cached settings. Requests already holding a
+/// client finish with the old one.
 pub async fn reload() -> Result<(), String> {
     let origin_bucket_name = SettingsService::origin_bucket_name().await;
     let preview_bucket_name = SettingsService::preview_bucket_name().await;
     let avif_bucket_name = SettingsService::avif_bucket_name().await;
     let s3_client =
         Arc::new(S3Client::new(origin_bucket_name, preview_bucket_name, avif_bucket_name).await);
-
-    S3_GARAGE
-        .set(s3_client)
-        .map_err(|_| "Reload Garage failed")?;
+    replace(&S3_GARAGE, s3_client);
 
     let bucket_name = SettingsService::r2_bucket_name().await;
     let r2_client = Arc::new(R2Client::new(bucket_name).await);
-
-    S3_R2.set(r2_client).map_err(|_| "Reload R2 failed")?;
+    replace(&S3_R2, r2_client);
 
     Ok(())
 }
 
-pub fn get_garage() -> &'static Arc<S3Client> {
-    S3_GARAGE.get().expect("S3客户端未初始化")
+pub fn get_garage() -> Arc<S3Client> {
+    read(&S3_GARAGE).expect("S3客户端未初始化")
+}
+
+pub fn get_r2() -> Arc<R2Client> {
+    read(&S3_R2).expect("R2客户端未初始化")
+}
+
+fn read<T>(client: &RwLock<Option<Arc<T>>>) -> Option<Arc<T>> {
+    client
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
 }
 
-pub fn get_r2() -> &'static Arc<R2Client> {
-    S3_R2.get().expect("R2客户端未初始化")
+fn replace<T>(client: &RwLock<Option<Arc<T>>>, value: Arc<T>) {
+    *client.write().unwrap_or_else(PoisonError::into_inner) = Some(value);
 }
 
 #[derive(Debug)]
@@ -91,6 +105,14 @@ pub struct S3Client {
     avif_bucket: String,
 }
 
+/// An object found listing a bucket.
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum Position {
     Original,
     Preview,
@@ -145,6 +167,78 @@ impl S3Client {
             .send()
             .await
     }
+
+    pub async fn head_object(
+        &self,
+        key: &str,
+        position: Position,
+    ) -> Result<HeadObjectOutput, SdkError<HeadObjectError, HttpResponse>> {
+        self.client
+            .head_object()
+            .bucket(self.position(position))
+            .key(key)
+            .send()
+            .await
+    }
+
+    pub async fn delete_object(
+        &self,
+        key: &str,
+        position: Position,
+    ) -> Result<DeleteObjectOutput, SdkError<DeleteObjectError, HttpResponse>> {
+        self.client
+            .delete_object()
+            .bucket(self.position(position))
+            .key(key)
+            .send()
+            .await
+    }
+
+    pub async fn sign_download_url(
+        &self,
+        key: &str,
+        position: Position,
+        expires_in: u64,
+    ) -> Result<String, String> {
+        let presigned_req = self
+            .client
+            .get_object()
+            .bucket(self.position(position))
+            .key(key)
+            .presigned(
+                PresigningConfig::expires_in(Duration::from_secs(expires_in))
+                    .map_err(|e| e.to_string())?,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(presigned_req.uri().to_string())
+    }
+
+    /// Every object of the bucket at `position`.
+    pub async fn list_objects(
+        &self,
+        position: Position,
+    ) -> Result<Vec<StoredObject>, SdkError<ListObjectsV2Error, HttpResponse>> {
+        list_objects(&self.client, self.position(position)).await
+    }
+
+    pub fn bucket(&self, position: Position) -> &str {
+        self.position(position)
+    }
+
+    pub fn buckets(&self) -> [&str; 3] {
+        [&self.origin_bucket, &self.preview_bucket, &self.avif_bucket]
+    }
+
+    /// Checks that every bucket is reachable.
+    pub async fn health(&self) -> Result<(), String> {
+        for bucket in self.buckets() {
+            head_bucket(&self.client, bucket).await?;
+        }
+
+        Ok(())
+    }
 }
 
 async fn init_s3_client() -> Client {
@@ -186,6 +280,12 @@ fn build_client(
     aws_sdk_s3::Client::new(&config)
 }
 
+/// Key of an image's original in R2, next to the served object.
+#[must_use]
+pub fn r2_original_key(original_key: &str) -> String {
+    format!("originals/{}", original_key)
+}
+
 #[derive(Debug)]
 pub struct R2Client {
     client: Client,
@@ -233,4 +333,112 @@ impl R2Client {
 
         Ok(presigned_req.uri().to_string())
     }
+
+    pub async fn put_object(
+        &self,
+        key: &str,
+        body: ByteStream,
+        content_type: &str,
+    ) -> Result<PutObjectOutput, SdkError<PutObjectError, HttpResponse>> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(body)
+            .content_type(content_type)
+            .send()
+            .await
+    }
+
+    pub async fn get_object(
+        &self,
+        key: &str,
+    ) -> Result<GetObjectOutput, SdkError<GetObjectError, HttpResponse>> {
+        self.client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+    }
+
+    pub async fn head_object(
+        &self,
+        key: &str,
+    ) -> Result<HeadObjectOutput, SdkError<HeadObjectError, HttpResponse>> {
+        self.client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+    }
+
+    pub async fn delete_object(
+        &self,
+        key: &str,
+    ) -> Result<DeleteObjectOutput, SdkError<DeleteObjectError, HttpResponse>> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+    }
+
+    /// Every object of the bucket.
+    pub async fn list_objects(
+        &self,
+    ) -> Result<Vec<StoredObject>, SdkError<ListObjectsV2Error, HttpResponse>> {
+        list_objects(&self.client, &self.bucket).await
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Checks that the bucket is reachable.
+    pub async fn health(&self) -> Result<(), String> {
+        head_bucket(&self.client, &self.bucket).await
+    }
+}
+
+async fn list_objects(
+    client: &Client,
+    bucket: &str,
+) -> Result<Vec<StoredObject>, SdkError<ListObjectsV2Error, HttpResponse>> {
+    let mut objects = Vec::new();
+    let mut pages = client
+        .list_objects_v2()
+        .bucket(bucket)
+        .into_paginator()
+        .send();
+    while let Some(page) = pages.next().await {
+        for object in page?.contents() {
+            let Some(key) = object.key() else {
+                continue;
+            };
+            objects.push(StoredObject {
+                key: key.to_string(),
+                last_modified: object
+                    .last_modified()
+                    .and_then(|at| chrono::DateTime::from_timestamp(at.secs(), at.subsec_nanos())),
+            });
+        }
+    }
+
+    Ok(objects)
+}
+
+async fn head_bucket(client: &Client, bucket: &str) -> Result<(), String> {
+    if bucket.is_empty() {
+        return Err("bucket not configured".to_string());
+    }
+
+    let request = client.head_bucket().bucket(bucket).send();
+    match tokio::time::timeout(HEALTH_TIMEOUT, request).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("{}: {}", bucket, DisplayErrorContext(e))),
+        Err(_) => Err(format!("{}: timed out", bucket)),
+    }
 }
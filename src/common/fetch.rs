@@ -0,0 +1,254 @@
+//! Fetches remote files for upload by URL without reaching internal services.
+//!
+//! Every connection, including those after a redirect, resolves its host
+//! through [`GuardedResolver`], which drops private, loopback, link-local and
+//! other non-public addresses. Hosts given as IP literals skip DNS, so they
+//! are checked on the URL itself before the request and on each redirect.
+
+use std::{
+    error::Error as StdError,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::body::Bytes;
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+
+use crate::common::settings::UrlUploadPolicy;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum FetchError {
+    #[error("无效的URL")]
+    InvalidUrl,
+    #[error("不允许访问该地址")]
+    Blocked,
+    #[error("重定向次数过多")]
+    TooManyRedirects,
+    #[error("文件过大")]
+    TooLarge,
+    #[error("请求超时")]
+    Timeout,
+    #[error("下载失败: {0}")]
+    Failed(String),
+}
+
+pub struct Fetcher {
+    client: reqwest::Client,
+    allowed: Arc<Vec<IpAddr>>,
+    max_bytes: u64,
+}
+
+/// A response being read, its body capped at the fetcher's size limit.
+pub struct Download {
+    response: reqwest::Response,
+    max_bytes: u64,
+    read: u64,
+}
+
+impl Fetcher {
+    /// # Errors
+    ///
+    /// When the HTTP client can't be built
+    pub fn new(policy: &UrlUploadPolicy) -> Result<Self, FetchError> {
+        let allowed = Arc::new(policy.allowed_ips.clone());
+        let max_redirects = policy.max_redirects;
+        let redirect_allowed = allowed.clone();
+        let redirect = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > max_redirects {
+                return attempt.error(FetchError::TooManyRedirects);
+            }
+            match check_url(attempt.url(), &redirect_allowed) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        });
+
+        let client = reqwest::Client::builder()
+            // a proxy would resolve the host instead of the guard
+            .no_proxy()
+            .dns_resolver(Arc::new(GuardedResolver {
+                allowed: allowed.clone(),
+            }))
+            .redirect(redirect)
+            .connect_timeout(policy.timeout)
+            .timeout(policy.timeout)
+            .build()
+            .map_err(|e| FetchError::Failed(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            allowed,
+            max_bytes: policy.max_bytes,
+        })
+    }
+
+    /// Sends a GET to `url`, following redirects, and checks the announced
+    /// length.
+    ///
+    /// # Errors
+    ///
+    /// When the URL or an address on the way is not allowed, or the request
+    /// fails
+    pub async fn get(&self, url: &str) -> Result<Download, FetchError> {
+        let url = Url::parse(url).map_err(|_| FetchError::InvalidUrl)?;
+        check_url(&url, &self.allowed)?;
+
+        let response = self.client.get(url).send().await.map_err(from_reqwest)?;
+        if !response.status().is_success() {
+            return Err(FetchError::Failed(response.status().to_string()));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_bytes)
+        {
+            return Err(FetchError::TooLarge);
+        }
+
+        Ok(Download {
+            response,
+            max_bytes: self.max_bytes,
+            read: 0,
+        })
+    }
+}
+
+impl Download {
+    /// The URL the body came from, after redirects.
+    #[must_use]
+    pub fn url(&self) -> &Url {
+        self.response.url()
+    }
+
+    #[must_use]
+    pub fn content_type(&self) -> Option<&str> {
+        self.response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    /// The next part of the body, `None` at its end.
+    ///
+    /// # Errors
+    ///
+    /// When reading fails or the body outgrows the size limit
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, FetchError> {
+        let Some(chunk) = self.response.chunk().await.map_err(from_reqwest)? else {
+            return Ok(None);
+        };
+        self.read += chunk.len() as u64;
+        if self.read > self.max_bytes {
+            return Err(FetchError::TooLarge);
+        }
+
+        Ok(Some(chunk))
+    }
+}
+
+/// Resolves hosts, leaving out addresses that are not public.
+struct GuardedResolver {
+    allowed: Arc<Vec<IpAddr>>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed = self.allowed.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_allowed(addr.ip(), &allowed))
+                .collect();
+            if addrs.is_empty() {
+                return Err(Box::new(FetchError::Blocked) as Box<dyn StdError + Send + Sync>);
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn check_url(url: &Url, allowed: &[IpAddr]) -> Result<(), FetchError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(FetchError::InvalidUrl);
+    }
+    let Some(host) = url.host_str() else {
+        return Err(FetchError::InvalidUrl);
+    };
+    // IPv6 literals keep their brackets
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) if !is_allowed(ip, allowed) => Err(FetchError::Blocked),
+        _ => Ok(()),
+    }
+}
+
+fn is_allowed(ip: IpAddr, allowed: &[IpAddr]) -> bool {
+    allowed.contains(&ip) || !is_blocked(ip)
+}
+
+/// Whether the address is anything but a public unicast one.
+#[must_use]
+pub fn is_blocked(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_blocked_v4(ip),
+        IpAddr::V6(ip) => is_blocked_v6(ip),
+    }
+}
+
+fn is_blocked_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "this network", shared address space, benchmarking and reserved
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240
+}
+
+fn is_blocked_v6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_blocked_v4(v4);
+    }
+    let segments = ip.segments();
+    // NAT64 reaches IPv4 addresses too
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., hi, lo] = segments;
+        return is_blocked_v4(Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo)));
+    }
+
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local, link-local and the deprecated site-local
+        || (segments[0] & 0xfe00) == 0xfc00
+        || (segments[0] & 0xffc0) == 0xfe80
+        || (segments[0] & 0xffc0) == 0xfec0
+        // documentation
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+}
+
+/// Finds an error of ours behind reqwest's, as raised by the resolver or the
+/// redirect policy.
+fn from_reqwest(e: reqwest::Error) -> FetchError {
+    if e.is_timeout() {
+        return FetchError::Timeout;
+    }
+    let mut source = e.source();
+    while let Some(inner) = source {
+        if let Some(fetch) = inner.downcast_ref::<FetchError>() {
+            return fetch.clone();
+        }
+        source = inner.source();
+    }
+
+    FetchError::Failed(e.to_string())
+}
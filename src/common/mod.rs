@@ -1,2 +1,9 @@
+pub mod auth;
 pub mod client;
+pub mod fetch;
+pub mod queue;
+pub mod quota;
+pub mod rate_limit;
 pub mod settings;
+pub mod stats;
+pub mod zip;
@@ -0,0 +1,92 @@
+//! Job counts of the background queue.
+
+use loco_rs::{
+    bgworker::{JobStatus, Queue},
+    prelude::*,
+};
+use redis::AsyncCommands;
+use sea_orm::sqlx;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QueueStats {
+    pub queued: u64,
+    pub processing: u64,
+    pub failed: u64,
+}
+
+/// Counts the jobs of the queue provider by status.
+///
+/// # Errors
+///
+/// When the queue can't be read
+pub async fn stats(queue: &Queue) -> Result<QueueStats> {
+    Ok(QueueStats {
+        queued: count(queue, JobStatus::Queued).await?,
+        processing: count(queue, JobStatus::Processing).await?,
+        failed: count(queue, JobStatus::Failed).await?,
+    })
+}
+
+/// Counts in the queue store itself, without loading the jobs.
+///
+/// Redis keeps no list of failed jobs, they are only marked in their own
+/// record, so none are counted there.
+async fn count(queue: &Queue, status: JobStatus) -> Result<u64> {
+    let count: i64 = match queue {
+        Queue::Postgres(pool, ..) => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM pg_loco_queue WHERE status = $1")
+                .bind(status.to_string())
+                .fetch_one(pool)
+                .await
+                .map_err(Error::wrap)?
+        }
+        Queue::Sqlite(pool, ..) => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM sqlt_loco_queue WHERE status = $1")
+                .bind(status.to_string())
+                .fetch_one(pool)
+                .await
+                .map_err(Error::wrap)?
+        }
+        Queue::Redis(client, ..) => match status {
+            JobStatus::Queued => redis_count(client, "queue:*", false).await?,
+            JobStatus::Processing => redis_count(client, "processing:*", true).await?,
+            _ => 0,
+        },
+        Queue::None => 0,
+    };
+
+    Ok(u64::try_from(count).unwrap_or_default())
+}
+
+/// Sums the lengths of the queue lists, or the sizes of the processing sets,
+/// matching `pattern`.
+async fn redis_count(client: &redis::Client, pattern: &str, sets: bool) -> Result<i64> {
+    let mut conn = client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(Error::wrap)?;
+    let keys: Vec<String> = {
+        let mut keys = conn
+            .scan_match::<_, String>(pattern)
+            .await
+            .map_err(Error::wrap)?;
+        let mut found = Vec::new();
+        while let Some(key) = keys.next_item().await {
+            found.push(key);
+        }
+        found
+    };
+
+    let mut count = 0;
+    for key in keys {
+        let len: i64 = if sets {
+            conn.scard(&key).await
+        } else {
+            conn.llen(&key).await
+        }
+        .map_err(Error::wrap)?;
+        count += len;
+    }
+
+    Ok(count)
+}
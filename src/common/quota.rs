@@ -0,0 +1,79 @@
+use axum::http::StatusCode;
+use loco_rs::{controller::ErrorDetail, prelude::*};
+
+use crate::{
+    common::settings::SettingsService,
+    models::{images::Usage, users::users},
+};
+
+const MB: u64 = 1024 * 1024;
+
+/// Limits of a user, `None` means unlimited.
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub max_bytes: Option<u64>,
+    pub max_images: Option<u64>,
+}
+
+impl Quota {
+    /// The user's own quota, falling back to the site default for each limit.
+    pub async fn for_user(user: &users::Model) -> Self {
+        let max_bytes = match user.storage_quota {
+            Some(bytes) => Some(u64::try_from(bytes).unwrap_or(0)),
+            None => match SettingsService::default_storage_quota().await {
+                0 => None,
+                mb => Some(mb.saturating_mul(MB)),
+            },
+        };
+        let max_images = match user.image_quota {
+            Some(count) => Some(u64::try_from(count).unwrap_or(0)),
+            None => match SettingsService::default_image_quota().await {
+                0 => None,
+                count => Some(count),
+            },
+        };
+
+        Self {
+            max_bytes,
+            max_images,
+        }
+    }
+
+    /// Bytes left before the storage quota is reached.
+    #[must_use]
+    pub fn bytes_left(&self, usage: &Usage) -> Option<u64> {
+        self.max_bytes
+            .map(|max| max.saturating_sub(u64::try_from(usage.bytes).unwrap_or(0)))
+    }
+
+    /// Checks that one more image of `bytes` fits.
+    ///
+    /// # Errors
+    ///
+    /// When the image count or storage quota would be exceeded
+    pub fn check(&self, usage: &Usage, bytes: u64) -> Result<()> {
+        if let Some(max) = self.max_images
+            && u64::try_from(usage.images).unwrap_or(0) >= max
+        {
+            return Err(exceeded(format!("Image quota exceeded ({} images)", max)));
+        }
+        if let Some(left) = self.bytes_left(usage)
+            && bytes > left
+        {
+            return Err(exceeded(format!(
+                "Storage quota exceeded ({} of {} bytes left)",
+                left,
+                self.max_bytes.unwrap_or_default()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn exceeded(description: String) -> Error {
+    Error::CustomError(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        ErrorDetail::new("Quota Exceeded".to_string(), description),
+    )
+}
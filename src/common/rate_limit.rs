@@ -0,0 +1,234 @@
+//! Token bucket rate limits kept in memory.
+//!
+//! Every scope has a burst size and an hourly refill, read from the
+//! `rate_limit_{scope}_burst` and `rate_limit_{scope}_per_hour` settings. A
+//! burst of 0 turns the scope's limit off. Requests are counted against one
+//! bucket per key (client IP, user) and are rejected when any of them is empty.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+};
+use loco_rs::controller::middleware::remote_ip::RemoteIP;
+
+use crate::{
+    common::settings::SettingsService,
+    error::{AppError, AppResult},
+};
+
+/// Buckets untouched for this long are dropped once the map grows large.
+const IDLE_TTL: Duration = Duration::from_secs(3600);
+const PRUNE_THRESHOLD: usize = 10_000;
+
+static BUCKETS: LazyLock<Mutex<HashMap<(Scope, String), Bucket>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Upload,
+    Presign,
+    Login,
+    Forgot,
+    Resend,
+    Report,
+}
+
+impl Scope {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Upload => "upload",
+            Self::Presign => "presign",
+            Self::Login => "login",
+            Self::Forgot => "forgot",
+            Self::Resend => "resend",
+            Self::Report => "report",
+        }
+    }
+
+    /// Default burst size and hourly refill.
+    fn defaults(self) -> (u64, u64) {
+        match self {
+            Self::Upload | Self::Presign => (20, 300),
+            Self::Login => (10, 60),
+            Self::Forgot | Self::Resend => (3, 10),
+            Self::Report => (5, 30),
+        }
+    }
+
+    async fn limit(self) -> Limit {
+        let (burst, per_hour) = self.defaults();
+        let (burst, per_hour) = SettingsService::rate_limit(self.name(), burst, per_hour).await;
+
+        Limit { burst, per_hour }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    burst: u64,
+    per_hour: u64,
+}
+
+impl Limit {
+    fn per_sec(self) -> f64 {
+        self.per_hour as f64 / 3600.0
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: Limit, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_sec()).min(limit.burst as f64);
+        self.updated = now;
+    }
+
+    /// Seconds until a token is available, 0 if one is.
+    fn wait_secs(&self, limit: Limit) -> u64 {
+        if self.tokens >= 1.0 {
+            return 0;
+        }
+        if limit.per_hour == 0 {
+            return IDLE_TTL.as_secs();
+        }
+
+        ((1.0 - self.tokens) / limit.per_sec()).ceil().max(1.0) as u64
+    }
+}
+
+/// Takes a token from the scope's bucket of every key.
+///
+/// # Errors
+///
+/// [`AppError::TooManyRequests`] when a bucket is empty, nothing is taken then
+pub async fn check(scope: Scope, keys: &[String]) -> AppResult<()> {
+    with_buckets(scope, keys, true).await
+}
+
+/// Like [`check`] without taking a token, for requests that are only counted
+/// once their outcome is known, see [`charge`].
+///
+/// # Errors
+///
+/// [`AppError::TooManyRequests`] when a bucket is empty
+pub async fn ensure(scope: Scope, keys: &[String]) -> AppResult<()> {
+    with_buckets(scope, keys, false).await
+}
+
+/// Takes a token from the scope's bucket of every key, even when it's empty.
+pub async fn charge(scope: Scope, keys: &[String]) {
+    let limit = scope.limit().await;
+    if limit.burst == 0 {
+        return;
+    }
+
+    let now = Instant::now();
+    let mut buckets = BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+    for key in keys {
+        let bucket = bucket(&mut buckets, scope, key, limit, now);
+        bucket.tokens = (bucket.tokens - 1.0).max(0.0);
+    }
+}
+
+async fn with_buckets(scope: Scope, keys: &[String], take: bool) -> AppResult<()> {
+    let limit = scope.limit().await;
+    if limit.burst == 0 || keys.is_empty() {
+        return Ok(());
+    }
+
+    let now = Instant::now();
+    let mut buckets = BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut retry_after = 0;
+    for key in keys {
+        let bucket = bucket(&mut buckets, scope, key, limit, now);
+        retry_after = retry_after.max(bucket.wait_secs(limit));
+    }
+    if retry_after > 0 {
+        tracing::info!(scope = scope.name(), "rate limited for {}s", retry_after);
+        return Err(AppError::TooManyRequests(retry_after));
+    }
+
+    if take {
+        for key in keys {
+            if let Some(bucket) = buckets.get_mut(&(scope, key.clone())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+    }
+
+    if buckets.len() > PRUNE_THRESHOLD {
+        buckets.retain(|_, bucket| now.duration_since(bucket.updated) < IDLE_TTL);
+    }
+
+    Ok(())
+}
+
+/// The refilled bucket of `key`, a full one if it has none yet.
+fn bucket<'a>(
+    buckets: &'a mut HashMap<(Scope, String), Bucket>,
+    scope: Scope,
+    key: &str,
+    limit: Limit,
+    now: Instant,
+) -> &'a mut Bucket {
+    let bucket = buckets
+        .entry((scope, key.to_string()))
+        .or_insert_with(|| Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+        });
+    bucket.refill(limit, now);
+
+    bucket
+}
+
+/// Address of the client, taken from the remote IP middleware when it's
+/// enabled and from the socket otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl ClientIp {
+    /// Rate limit key of the address, `None` when it's unknown.
+    #[must_use]
+    pub fn key(&self) -> Option<String> {
+        self.0.map(|ip| format!("ip:{}", ip))
+    }
+}
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = ();
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ip = match parts.extensions.get::<RemoteIP>() {
+            Some(RemoteIP::Forwarded(ip) | RemoteIP::Socket(ip)) => Some(*ip),
+            _ => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|info| info.0.ip()),
+        };
+
+        Ok(Self(ip))
+    }
+}
+
+/// Keys of a request made by `ip`, on behalf of `subject` if known.
+#[must_use]
+pub fn keys(ip: ClientIp, subject: Option<String>) -> Vec<String> {
+    ip.key().into_iter().chain(subject).collect()
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::LazyLock};
+use std::{collections::HashMap, net::IpAddr, sync::LazyLock, time::Duration};
 
 use loco_rs::Result;
 use migration::OnConflict;
@@ -13,11 +13,58 @@ static SETTINGS_CACHE: LazyLock<RwLock<HashMap<String, String>>> =
 
 pub struct SettingsService;
 
+/// When images move from Garage to R2, see [`crate::workers::tiering`].
+#[derive(Debug, Clone, Copy)]
+pub struct TieringPolicy {
+    /// Minimum age of a moved image, 0 turns tiering off.
+    pub after_days: u64,
+    /// Images with at least this many views in the window stay local.
+    pub max_views: u64,
+    pub window_days: u64,
+    /// Images moved per run.
+    pub batch_size: u64,
+}
+
+/// Limits of uploads by URL, see [`crate::common::fetch`].
+#[derive(Debug, Clone)]
+pub struct UrlUploadPolicy {
+    pub max_bytes: u64,
+    pub timeout: Duration,
+    pub max_redirects: usize,
+    /// Addresses reachable even though they are not public, e.g. an internal
+    /// image host.
+    pub allowed_ips: Vec<IpAddr>,
+}
+
+/// Limits of ZIP imports, checked before anything is unpacked.
+#[derive(Debug, Clone, Copy)]
+pub struct ZipImportPolicy {
+    pub max_entries: usize,
+    /// Total uncompressed size of the image entries.
+    pub max_bytes: u64,
+    /// Size of a single image, larger ones are skipped.
+    pub max_entry_bytes: u64,
+}
+
 mod keys {
     pub const UPLOAD_MAX_SIZE: &str = "upload_max_size_mb";
     pub const ALLOW_REGISTRATION: &str = "allow_registration";
     pub const SITE_NAME: &str = "site_name";
     pub const ALLOW_EVERYONE_UPLOAD: &str = "allow_everyone_upload";
+    pub const DEFAULT_STORAGE_QUOTA: &str = "default_storage_quota_mb";
+    pub const DEFAULT_IMAGE_QUOTA: &str = "default_image_quota";
+    pub const ANONYMOUS_MAX_LIFETIME: &str = "anonymous_max_lifetime_hours";
+    pub const URL_UPLOAD_TIMEOUT: &str = "url_upload_timeout_secs";
+    pub const URL_UPLOAD_MAX_REDIRECTS: &str = "url_upload_max_redirects";
+    pub const URL_UPLOAD_ALLOWED_IPS: &str = "url_upload_allowed_ips";
+    pub const ZIP_IMPORT_MAX_ENTRIES: &str = "zip_import_max_entries";
+    pub const ZIP_IMPORT_MAX_SIZE: &str = "zip_import_max_size_mb";
+    pub const ACCOUNT_DELETION_GRACE_DAYS: &str = "account_deletion_grace_days";
+
+    pub const TIERING_AFTER_DAYS: &str = "tiering_after_days";
+    pub const TIERING_MAX_VIEWS: &str = "tiering_max_views";
+    pub const TIERING_WINDOW_DAYS: &str = "tiering_window_days";
+    pub const TIERING_BATCH_SIZE: &str = "tiering_batch_size";
 
     // secret, local garage
     pub const AWS_ACCESS_KEY_ID: &str = "aws_access_key_id";
@@ -71,6 +118,73 @@ impl SettingsService {
         Self::get_bool(keys::ALLOW_EVERYONE_UPLOAD, false).await
     }
 
+    /// Storage quota of users without their own, 0 means unlimited.
+    pub async fn default_storage_quota() -> u64 {
+        Self::get_u64(keys::DEFAULT_STORAGE_QUOTA, 0).await
+    }
+
+    /// Image count quota of users without their own, 0 means unlimited.
+    pub async fn default_image_quota() -> u64 {
+        Self::get_u64(keys::DEFAULT_IMAGE_QUOTA, 0).await
+    }
+
+    /// Forced maximum lifetime of anonymous uploads, 0 means unlimited.
+    pub async fn anonymous_max_lifetime() -> u64 {
+        Self::get_u64(keys::ANONYMOUS_MAX_LIFETIME, 0).await
+    }
+
+    /// Uploads by URL share the upload size limit. Allowed IPs are comma
+    /// separated, invalid ones are ignored.
+    pub async fn url_upload_policy() -> UrlUploadPolicy {
+        let allowed_ips = Self::get(keys::URL_UPLOAD_ALLOWED_IPS, "")
+            .await
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect();
+        UrlUploadPolicy {
+            max_bytes: Self::max_upload_size().await.saturating_mul(1024 * 1024),
+            timeout: Duration::from_secs(Self::get_u64(keys::URL_UPLOAD_TIMEOUT, 15).await),
+            max_redirects: usize::try_from(Self::get_u64(keys::URL_UPLOAD_MAX_REDIRECTS, 3).await)
+                .unwrap_or(usize::MAX),
+            allowed_ips,
+        }
+    }
+
+    /// Images share the upload size limit.
+    pub async fn zip_import_policy() -> ZipImportPolicy {
+        ZipImportPolicy {
+            max_entries: usize::try_from(Self::get_u64(keys::ZIP_IMPORT_MAX_ENTRIES, 5000).await)
+                .unwrap_or(usize::MAX),
+            max_bytes: Self::get_u64(keys::ZIP_IMPORT_MAX_SIZE, 2048)
+                .await
+                .saturating_mul(1024 * 1024),
+            max_entry_bytes: Self::max_upload_size().await.saturating_mul(1024 * 1024),
+        }
+    }
+
+    /// Days a user can undo deleting their account, 0 deletes it right away.
+    pub async fn account_deletion_grace_days() -> u64 {
+        Self::get_u64(keys::ACCOUNT_DELETION_GRACE_DAYS, 7).await
+    }
+
+    /// Burst size and hourly refill of a rate limit scope, see
+    /// [`crate::common::rate_limit`].
+    pub async fn rate_limit(scope: &str, burst: u64, per_hour: u64) -> (u64, u64) {
+        (
+            Self::get_u64(&format!("rate_limit_{}_burst", scope), burst).await,
+            Self::get_u64(&format!("rate_limit_{}_per_hour", scope), per_hour).await,
+        )
+    }
+
+    pub async fn tiering_policy() -> TieringPolicy {
+        TieringPolicy {
+            after_days: Self::get_u64(keys::TIERING_AFTER_DAYS, 0).await,
+            max_views: Self::get_u64(keys::TIERING_MAX_VIEWS, 10).await,
+            window_days: Self::get_u64(keys::TIERING_WINDOW_DAYS, 7).await,
+            batch_size: Self::get_u64(keys::TIERING_BATCH_SIZE, 100).await,
+        }
+    }
+
     pub async fn aws_access_key_id() -> String {
         Self::get(keys::AWS_ACCESS_KEY_ID, "").await
     }
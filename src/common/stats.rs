@@ -0,0 +1,76 @@
+//! View and bandwidth counters. Serving an image only bumps an in-memory
+//! counter, the counters are written to `image_stats` in batches.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use axum::http::{HeaderMap, Uri, header::REFERER};
+use loco_rs::app::AppContext;
+use sea_orm::DatabaseConnection;
+
+use crate::models::image_stats::{self, Counts, StatsKey};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// Past this many pending keys, new referrers are counted as direct requests.
+const MAX_PENDING: usize = 10_000;
+const MAX_DOMAIN_LEN: usize = 255;
+
+static PENDING: LazyLock<Mutex<HashMap<StatsKey, Counts>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Counts a view of the image, `bytes` being the size of the response body.
+pub fn record(image_id: i32, headers: &HeaderMap, bytes: u64) {
+    let day = chrono::Utc::now().date_naive();
+    let referrer = referrer_domain(headers).unwrap_or_default();
+    let bytes = i64::try_from(bytes).unwrap_or(i64::MAX);
+
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    let mut key = (image_id, day, referrer);
+    if pending.len() >= MAX_PENDING && !pending.contains_key(&key) {
+        key.2 = String::new();
+    }
+    let counts = pending.entry(key).or_default();
+    counts.views += 1;
+    counts.bytes = counts.bytes.saturating_add(bytes);
+}
+
+/// Writes the pending counters to the database. When that fails they are
+/// kept, with anything counted meanwhile, for the next flush.
+pub async fn flush(db: &DatabaseConnection) {
+    let batch = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    if batch.is_empty() {
+        return;
+    }
+
+    if let Err(e) = image_stats::Model::add_batch(db, &batch).await {
+        tracing::error!("Failed to write {} image stats: {}", batch.len(), e);
+        let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, counts) in batch {
+            let pending = pending.entry(key).or_default();
+            pending.views += counts.views;
+            pending.bytes = pending.bytes.saturating_add(counts.bytes);
+        }
+    }
+}
+
+/// Flushes the counters every `FLUSH_INTERVAL` for the life of the server.
+pub fn spawn_flusher(ctx: &AppContext) {
+    let db = ctx.db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            flush(&db).await;
+        }
+    });
+}
+
+fn referrer_domain(headers: &HeaderMap) -> Option<String> {
+    let uri: Uri = headers.get(REFERER)?.to_str().ok()?.parse().ok()?;
+    let host = uri.host()?.to_lowercase();
+
+    (host.len() <= MAX_DOMAIN_LEN).then_some(host)
+}
@@ -0,0 +1,294 @@
+//! Streaming ZIP archives on top of `async_zip`.
+//!
+//! [`ZipWriter`] is meant for already compressed files: entries are stored
+//! uncompressed and their sizes written after the data, so nothing has to be
+//! held in memory.
+//!
+//! [`ZipReader`] reads the entries of an uploaded archive. It trusts nothing
+//! the archive claims: the entry count is checked before the central
+//! directory is read, and entries inflating past their declared size or
+//! failing their CRC are errors, so limits checked on the central directory
+//! hold for the data too.
+
+use std::io::{Error, ErrorKind, SeekFrom};
+
+use async_zip::{
+    Compression, ZipDateTime, ZipEntryBuilder,
+    base::read::WithoutEntry,
+    tokio::{
+        read::{ZipEntryReader as EntryReader, seek::ZipFileReader},
+        write::{EntryStreamWriter, ZipFileWriter},
+    },
+};
+use futures_util::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::io::{
+    AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+
+const END: u32 = 0x0605_4b50;
+const ZIP64_END: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const END_SIZE: u64 = 22;
+const MAX_COMMENT: u64 = u16::MAX as u64;
+/// Uncompressed bytes read at a time, so inflating can't take much memory
+/// before the declared size is checked.
+const READ_SIZE: usize = 8 * 1024;
+
+pub struct ZipWriter<W> {
+    inner: ZipFileWriter<W>,
+}
+
+impl<W: AsyncWrite + Unpin> ZipWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: ZipFileWriter::with_tokio(inner),
+        }
+    }
+
+    /// Starts a new entry, to be closed before the next one.
+    ///
+    /// # Errors
+    ///
+    /// When writing fails
+    pub async fn start_entry(
+        &mut self,
+        name: &str,
+        modified: chrono::DateTime<chrono::Utc>,
+    ) -> std::io::Result<ZipEntryWriter<'_, W>> {
+        let entry = ZipEntryBuilder::new(name.to_string().into(), Compression::Stored)
+            .last_modification_date(ZipDateTime::from_chrono(&modified));
+        let writer = self
+            .inner
+            .write_entry_stream(entry)
+            .await
+            .map_err(Error::other)?;
+
+        Ok(ZipEntryWriter { inner: writer })
+    }
+
+    /// Writes the central directory and returns the inner writer, flushed.
+    ///
+    /// # Errors
+    ///
+    /// When writing fails
+    pub async fn finish(self) -> std::io::Result<W> {
+        let mut inner = self.inner.close().await.map_err(Error::other)?.into_inner();
+        inner.flush().await?;
+
+        Ok(inner)
+    }
+}
+
+/// The data of an entry being written.
+pub struct ZipEntryWriter<'a, W: AsyncWrite + Unpin> {
+    inner: EntryStreamWriter<'a, W>,
+}
+
+impl<W: AsyncWrite + Unpin> ZipEntryWriter<'_, W> {
+    /// Appends data to the entry.
+    ///
+    /// # Errors
+    ///
+    /// When writing fails
+    pub async fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.inner.write_all(data).await
+    }
+
+    /// Ends the entry, writing its sizes and CRC.
+    ///
+    /// # Errors
+    ///
+    /// When writing fails
+    pub async fn close(self) -> std::io::Result<()> {
+        self.inner.close().await.map_err(Error::other)
+    }
+}
+
+/// An entry listed in the central directory.
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    /// Uncompressed size as declared by the archive.
+    pub size: u64,
+    pub compressed_size: u64,
+    crc: u32,
+    index: usize,
+}
+
+impl ZipEntry {
+    #[must_use]
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+pub struct ZipReader<R> {
+    inner: ZipFileReader<BufReader<R>>,
+    entries: Vec<ZipEntry>,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> ZipReader<R> {
+    /// Reads the central directory, refusing archives of more than
+    /// `max_entries` entries before reading any of them. Archives with an
+    /// entry compressed in a way other than stored or deflated are refused
+    /// too.
+    ///
+    /// # Errors
+    ///
+    /// When reading fails, the archive is malformed or has too many entries
+    pub async fn new(inner: R, max_entries: usize) -> std::io::Result<Self> {
+        let mut inner = BufReader::new(inner);
+        if entry_count(&mut inner).await? > max_entries as u64 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("ZIP archive has more than {} entries", max_entries),
+            ));
+        }
+
+        let inner = ZipFileReader::with_tokio(inner)
+            .await
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let entries = inner
+            .file()
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let name = entry.filename();
+                ZipEntry {
+                    name: name.as_str().map_or_else(
+                        |_| String::from_utf8_lossy(name.as_bytes()).into_owned(),
+                        str::to_string,
+                    ),
+                    size: entry.uncompressed_size(),
+                    compressed_size: entry.compressed_size(),
+                    crc: entry.crc32(),
+                    index,
+                }
+            })
+            .collect();
+
+        Ok(Self { inner, entries })
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Starts reading the data of an entry.
+    ///
+    /// # Errors
+    ///
+    /// When reading fails or the entry is malformed
+    pub async fn open(&mut self, entry: &ZipEntry) -> std::io::Result<ZipEntryReader<'_, R>> {
+        let inner = self
+            .inner
+            .reader_without_entry(entry.index)
+            .await
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        Ok(ZipEntryReader {
+            inner,
+            name: entry.name.clone(),
+            size: entry.size,
+            crc: entry.crc,
+            read: 0,
+        })
+    }
+}
+
+/// The data of an entry, never more than its declared size.
+pub struct ZipEntryReader<'a, R> {
+    inner: EntryReader<'a, BufReader<R>, WithoutEntry>,
+    name: String,
+    size: u64,
+    crc: u32,
+    read: u64,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> ZipEntryReader<'_, R> {
+    /// The next part of the uncompressed data, `None` at its end.
+    ///
+    /// # Errors
+    ///
+    /// When reading or inflating fails, or the data doesn't match the entry
+    pub async fn chunk(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        let mut data = vec![0; READ_SIZE];
+        let read = self.inner.read(&mut data).await?;
+        if read == 0 {
+            if self.read != self.size || self.inner.compute_hash() != self.crc {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("ZIP entry {} is corrupted", self.name),
+                ));
+            }
+            return Ok(None);
+        }
+
+        self.read += read as u64;
+        if self.read > self.size {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("ZIP entry {} is larger than declared", self.name),
+            ));
+        }
+        data.truncate(read);
+
+        Ok(Some(data))
+    }
+}
+
+/// Entries the end of central directory claims, checked before `async_zip`
+/// reads, and allocates for, every one of them.
+async fn entry_count<R>(inner: &mut R) -> std::io::Result<u64>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let len = inner.seek(SeekFrom::End(0)).await?;
+    let tail_len = len.min(END_SIZE + MAX_COMMENT);
+    let tail = read_at(inner, len - tail_len, tail_len).await?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| tail[i..i + 4] == END.to_le_bytes())
+        .ok_or_else(|| invalid("Not a ZIP archive"))?;
+    let count = u16::from_le_bytes([tail[end + 10], tail[end + 11]]);
+    if count != u16::MAX {
+        return Ok(u64::from(count));
+    }
+
+    let locator = (len - tail_len + end as u64)
+        .checked_sub(20)
+        .ok_or_else(|| invalid("Missing ZIP64 locator"))?;
+    let locator = read_at(inner, locator, 20).await?;
+    if locator[..4] != ZIP64_LOCATOR.to_le_bytes() {
+        return Err(invalid("Missing ZIP64 locator"));
+    }
+    let zip64_end = read_at(inner, le_u64(&locator[8..]), 56).await?;
+    if zip64_end[..4] != ZIP64_END.to_le_bytes() {
+        return Err(invalid("Missing ZIP64 end of central directory"));
+    }
+
+    Ok(le_u64(&zip64_end[32..]))
+}
+
+async fn read_at<R>(inner: &mut R, offset: u64, len: u64) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    inner.seek(SeekFrom::Start(offset)).await?;
+    let mut buf = vec![0; usize::try_from(len).map_err(|_| invalid("Length out of range"))?];
+    inner.read_exact(&mut buf).await?;
+
+    Ok(buf)
+}
+
+fn le_u64(buf: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&buf[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
@@ -0,0 +1,538 @@
+use axum::http::StatusCode;
+use loco_rs::{controller::ErrorDetail, prelude::*};
+use sea_orm::{PaginatorTrait, TransactionTrait};
+use serde::Deserialize;
+
+use crate::{
+    common::{
+        client::{get_garage, get_r2},
+        queue,
+        quota::Quota,
+    },
+    controllers::{image::remove_image, upload::TempUpload},
+    models::{
+        _entities::{
+            abuse_reports::ReportStatus, albums, images::Location, imports, tags, tmps, tus_uploads,
+        },
+        abuse_reports, images,
+        regenerations::{self, RegenerateParams},
+        users::users::{self, UserRole},
+    },
+    views::admin::{
+        DayUploads, LocationOverview, ModeratedImageResponse, OverviewResponse, QuotaResponse,
+        RegenerationResponse, ReportResponse, ReportsResponse, UserResponse, UsersResponse,
+    },
+    workers::{downloader, regenerator},
+};
+
+const OVERVIEW_DAYS: i64 = 30;
+const MAX_PAGE_SIZE: u64 = 50;
+const REMOVE_BATCH_SIZE: u64 = 100;
+const RECENT_REGENERATIONS: u64 = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct ListUsersParams {
+    /// Part of the username or email.
+    pub q: Option<String>,
+    /// Zero based.
+    #[serde(default)]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListReportsParams {
+    /// Defaults to the open reports.
+    pub status: Option<ReportStatus>,
+    /// Zero based.
+    #[serde(default)]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TakedownParams {
+    #[validate(length(min = 1, max = 2000, message = "下架原因长度必须在1到2000之间"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleParams {
+    pub role: UserRole,
+}
+
+/// Own quotas of a user, `null` uses the site default.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaParams {
+    /// Bytes.
+    #[validate(range(min = 0, message = "配额不能为负数"))]
+    pub storage_quota: Option<i64>,
+    #[validate(range(min = 0, message = "配额不能为负数"))]
+    pub image_quota: Option<i32>,
+}
+
+async fn overview(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let total_users = users::Entity::find().count(&ctx.db).await?;
+    let locations: Vec<LocationOverview> = images::Model::usage_by_location(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|(location, usage)| LocationOverview {
+            location,
+            buckets: match location {
+                Location::Local => get_garage()
+                    .buckets()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                Location::R2 => vec![get_r2().bucket().to_string()],
+            },
+            images: usage.images,
+            bytes: usage.bytes,
+        })
+        .collect();
+
+    let from = chrono::Utc::now() - chrono::Duration::days(OVERVIEW_DAYS - 1);
+    let from = from
+        .date_naive()
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+        .fixed_offset();
+    let uploads = images::Model::uploads_per_day(&ctx.db, from)
+        .await?
+        .into_iter()
+        .map(|(day, uploads)| DayUploads { day, uploads })
+        .collect();
+
+    let queue = match &ctx.queue_provider {
+        Some(provider) => Some(queue::stats(provider).await?.into()),
+        None => None,
+    };
+    let (garage, r2) = (get_garage(), get_r2());
+    let (garage, r2) = tokio::join!(garage.health(), r2.health());
+
+    format::json(OverviewResponse {
+        total_users,
+        total_images: locations.iter().map(|l| l.images).sum(),
+        locations,
+        uploads,
+        queue,
+        garage: garage.into(),
+        r2: r2.into(),
+    })
+}
+
+async fn quota(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let user = find_user(&ctx, &pid).await?;
+
+    format::json(quota_response(&ctx, &user).await?)
+}
+
+async fn update_quota(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<QuotaParams>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let user = find_user(&ctx, &pid)
+        .await?
+        .into_active_model()
+        .set_quota(&ctx.db, params.storage_quota, params.image_quota)
+        .await?;
+
+    format::json(quota_response(&ctx, &user).await?)
+}
+
+async fn list_users(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let q = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let page_size = params.limit.clamp(1, MAX_PAGE_SIZE);
+    let (users, num_items_and_pages) =
+        users::Model::search(&ctx.db, q, params.page, page_size).await?;
+
+    format::json(UsersResponse::new(&users, &num_items_and_pages))
+}
+
+async fn user(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+    let user = find_user(&ctx, &pid).await?;
+
+    format::json(UserResponse::new(&user))
+}
+
+async fn disable(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    set_disabled(&ctx, &auth, &pid, true).await
+}
+
+async fn enable(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    set_disabled(&ctx, &auth, &pid, false).await
+}
+
+async fn set_disabled(
+    ctx: &AppContext,
+    auth: &auth::JWT,
+    pid: &str,
+    disabled: bool,
+) -> Result<Response> {
+    let admin = find_admin(ctx, auth).await?;
+    let user = find_other_user(ctx, &admin, pid).await?;
+    let user = if user.is_disabled() == disabled {
+        user
+    } else {
+        user.into_active_model()
+            .set_disabled(&ctx.db, disabled)
+            .await?
+    };
+
+    format::json(UserResponse::new(&user))
+}
+
+async fn update_role(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<RoleParams>,
+) -> Result<Response> {
+    let admin = find_admin(&ctx, &auth).await?;
+    let user = find_other_user(&ctx, &admin, &pid)
+        .await?
+        .into_active_model()
+        .set_role(&ctx.db, params.role)
+        .await?;
+
+    format::json(UserResponse::new(&user))
+}
+
+/// Marks the user's email as verified without the emailed link.
+async fn verify(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+    let user = find_user(&ctx, &pid).await?;
+    let user = if user.email_verified_at.is_some() {
+        user
+    } else {
+        user.into_active_model().verified(&ctx.db).await?
+    };
+
+    format::json(UserResponse::new(&user))
+}
+
+async fn reset_api_key(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+    let user = find_user(&ctx, &pid)
+        .await?
+        .into_active_model()
+        .reset_api_key(&ctx.db)
+        .await?;
+
+    format::json(UserResponse::new(&user))
+}
+
+async fn remove(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    let admin = find_admin(&ctx, &auth).await?;
+    let user = find_other_user(&ctx, &admin, &pid).await?;
+    remove_user(&ctx, user).await?;
+
+    format::json(())
+}
+
+/// Deletes the user along with their images, albums, tags and exports. Stored
+/// images are left to the remover worker, export archives go right away.
+pub async fn remove_user(ctx: &AppContext, user: users::Model) -> Result<()> {
+    loop {
+        let images =
+            images::Model::find_batch_by_user_pid(&ctx.db, user.pid, REMOVE_BATCH_SIZE).await?;
+        if images.is_empty() {
+            break;
+        }
+        for image in images {
+            remove_image(ctx, image).await?;
+        }
+    }
+
+    albums::Entity::delete_many()
+        .filter(albums::Column::UserPid.eq(user.pid))
+        .exec(&ctx.db)
+        .await?;
+    tags::Entity::delete_many()
+        .filter(tags::Column::UserPid.eq(user.pid))
+        .exec(&ctx.db)
+        .await?;
+    tmps::Entity::delete_many()
+        .filter(tmps::Column::UserPid.eq(user.pid))
+        .exec(&ctx.db)
+        .await?;
+    imports::Entity::delete_many()
+        .filter(imports::Column::UserPid.eq(user.pid))
+        .exec(&ctx.db)
+        .await?;
+    let uploads = tus_uploads::Entity::find()
+        .filter(tus_uploads::Column::UserPid.eq(user.pid))
+        .all(&ctx.db)
+        .await?;
+    for upload in uploads {
+        let _ = tokio::fs::remove_file(TempUpload::path(upload.uuid, &upload.raw_name)).await;
+        upload.delete(&ctx.db).await?;
+    }
+    downloader::remove_exports(&ctx.db, user.pid).await?;
+
+    tracing::info!(pid = %user.pid, "Deleting user {}", user.username);
+    user.delete(&ctx.db).await?;
+
+    Ok(())
+}
+
+/// The moderation queue, oldest report first.
+async fn list_reports(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Query(params): Query<ListReportsParams>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let status = params.status.unwrap_or(ReportStatus::Open);
+    let page_size = params.limit.clamp(1, MAX_PAGE_SIZE);
+    let (reports, num_items_and_pages) =
+        abuse_reports::Model::find_by_status(&ctx.db, status, params.page, page_size).await?;
+
+    format::json(ReportsResponse::new(&reports, &num_items_and_pages))
+}
+
+/// Closes a report without acting on the image.
+async fn dismiss_report(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let report = match abuse_reports::Model::find_by_id(&ctx.db, id).await {
+        Ok(report) => report,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    let report = report
+        .into_active_model()
+        .resolve(&ctx.db, ReportStatus::Dismissed)
+        .await?;
+    let image = images::Model::find_by_id(&ctx.db, report.image_id).await?;
+
+    format::json(ReportResponse::new(&report, &image))
+}
+
+/// Stops serving the image and closes its open reports. The objects are kept
+/// so the decision can be reviewed or reverted.
+async fn take_down(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    Json(params): Json<TakedownParams>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let image = find_image(&ctx, id).await?;
+    let txn = ctx.db.begin().await?;
+    let image = image
+        .into_active_model()
+        .set_taken_down(&txn, Some(params.reason.trim().to_string()))
+        .await?;
+    abuse_reports::Model::resolve_by_image_id(&txn, image.id, ReportStatus::TakenDown).await?;
+    txn.commit().await?;
+
+    format::json(ModeratedImageResponse::new(&image))
+}
+
+async fn restore(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let image = find_image(&ctx, id)
+        .await?
+        .into_active_model()
+        .set_taken_down(&ctx.db, None)
+        .await?;
+
+    format::json(ModeratedImageResponse::new(&image))
+}
+
+/// Starts encoding the derivatives of the matching images again.
+async fn regenerate(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Json(params): Json<RegenerateParams>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let regeneration = regenerations::Model::create(&ctx.db, &params).await?;
+    regenerator::Worker::perform_later(
+        &ctx,
+        regenerator::WorkerArgs {
+            regeneration_id: regeneration.id,
+        },
+    )
+    .await?;
+
+    format::json(RegenerationResponse::new(&regeneration))
+}
+
+async fn list_regenerations(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let regenerations = regenerations::Model::find_recent(&ctx.db, RECENT_REGENERATIONS).await?;
+
+    format::json(
+        regenerations
+            .iter()
+            .map(RegenerationResponse::new)
+            .collect::<Vec<_>>(),
+    )
+}
+
+async fn regeneration(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    find_admin(&ctx, &auth).await?;
+
+    let regeneration = match regenerations::Model::find_by_id(&ctx.db, id).await {
+        Ok(regeneration) => regeneration,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+
+    format::json(RegenerationResponse::new(&regeneration))
+}
+
+async fn find_image(ctx: &AppContext, id: i32) -> Result<images::Model> {
+    match images::Model::find_by_id(&ctx.db, id).await {
+        Ok(image) => Ok(image),
+        Err(ModelError::EntityNotFound) => Err(Error::NotFound),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn find_admin(ctx: &AppContext, auth: &auth::JWT) -> Result<users::Model> {
+    let admin = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    if admin.role != UserRole::Admin {
+        return Err(Error::CustomError(
+            StatusCode::FORBIDDEN,
+            ErrorDetail::new("Forbidden".to_string(), "Admin only".to_string()),
+        ));
+    }
+
+    Ok(admin)
+}
+
+async fn find_user(ctx: &AppContext, pid: &str) -> Result<users::Model> {
+    match users::Model::find_by_pid(&ctx.db, pid).await {
+        Ok(user) => Ok(user),
+        Err(ModelError::EntityNotFound) => Err(Error::NotFound),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Admins can't disable, demote or delete themselves.
+async fn find_other_user(
+    ctx: &AppContext,
+    admin: &users::Model,
+    pid: &str,
+) -> Result<users::Model> {
+    let user = find_user(ctx, pid).await?;
+    if user.id == admin.id {
+        return Err(Error::BadRequest(
+            "Cannot change your own account".to_string(),
+        ));
+    }
+
+    Ok(user)
+}
+
+async fn quota_response(ctx: &AppContext, user: &users::Model) -> Result<QuotaResponse> {
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    let quota = Quota::for_user(user).await;
+
+    Ok(QuotaResponse::new(user, &usage, &quota))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/admin")
+        .add("/overview", get(overview))
+        .add("/users", get(list_users))
+        .add("/users/{pid}", get(user).delete(remove))
+        .add("/users/{pid}/disable", post(disable))
+        .add("/users/{pid}/enable", post(enable))
+        .add("/users/{pid}/role", put(update_role))
+        .add("/users/{pid}/verify", post(verify))
+        .add("/users/{pid}/api-key", post(reset_api_key))
+        .add("/reports", get(list_reports))
+        .add("/reports/{id}/dismiss", post(dismiss_report))
+        .add("/images/{id}/takedown", post(take_down))
+        .add("/images/{id}/restore", post(restore))
+        .add("/users/{pid}/quota", get(quota).put(update_quota))
+        .add("/regenerations", get(list_regenerations).post(regenerate))
+        .add("/regenerations/{id}", get(regeneration))
+}
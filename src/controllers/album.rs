@@ -0,0 +1,205 @@
+use loco_rs::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    models::{
+        albums::{self, CreateParams, UpdateParams},
+        images,
+        users::users,
+    },
+    views::album::{AlbumDetailResponse, AlbumImage, AlbumListResponse, AlbumResponse},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageIdsParams {
+    pub image_ids: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverParams {
+    pub image_id: Option<i32>,
+}
+
+async fn list(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let albums = albums::Model::find_by_user_pid(&ctx.db, user.pid).await?;
+
+    format::json(AlbumListResponse {
+        albums: albums.iter().map(AlbumResponse::new).collect(),
+    })
+}
+
+async fn create(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Json(params): Json<CreateParams>,
+) -> Result<Response> {
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::create(&ctx.db, user.pid, &params).await?;
+
+    format::json(AlbumResponse::new(&album))
+}
+
+async fn detail(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+    let images = album.images(&ctx.db, false).await?;
+
+    format::json(detail_response(&album, &images))
+}
+
+/// Public view of an album, only lists the album's public images.
+async fn public_detail(State(ctx): State<AppContext>, Path(pid): Path<String>) -> Result<Response> {
+    let album = albums::Model::find_public_by_pid(&ctx.db, &pid).await?;
+    let images = album.images(&ctx.db, true).await?;
+
+    format::json(detail_response(&album, &images))
+}
+
+async fn update(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<UpdateParams>,
+) -> Result<Response> {
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+    let album = album.into_active_model().update_with(&ctx.db, &params).await?;
+
+    format::json(AlbumResponse::new(&album))
+}
+
+async fn remove(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+    album.delete(&ctx.db).await?;
+
+    format::json(())
+}
+
+async fn set_cover(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<CoverParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+
+    if let Some(image_id) = params.image_id
+        && !album.contains_image(&ctx.db, image_id).await?
+    {
+        return Err(Error::BadRequest("Image is not in this album".to_string()));
+    }
+
+    let album = album
+        .into_active_model()
+        .set_cover(&ctx.db, params.image_id)
+        .await?;
+
+    format::json(AlbumResponse::new(&album))
+}
+
+async fn add_images(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<ImageIdsParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+
+    // only the album owner's images can be added, whatever the caller's role
+    let owned: Vec<i32> = images::Model::find_by_ids(&ctx.db, &params.image_ids)
+        .await?
+        .into_iter()
+        .filter(|image| image.user_pid == Some(user.pid))
+        .map(|image| image.id)
+        .collect();
+    let ids: Vec<i32> = params
+        .image_ids
+        .into_iter()
+        .filter(|id| owned.contains(id))
+        .collect();
+
+    let added = album.add_images(&ctx.db, &ids).await?;
+
+    format::json(added)
+}
+
+async fn remove_images(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<ImageIdsParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+    album.remove_images(&ctx.db, &params.image_ids).await?;
+
+    format::json(())
+}
+
+async fn reorder(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(pid): Path<String>,
+    Json(params): Json<ImageIdsParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = albums::Model::find_by_pid_and_user_pid(&ctx.db, &pid, user.pid).await?;
+    album.reorder(&ctx.db, &params.image_ids).await?;
+
+    format::json(())
+}
+
+fn detail_response(album: &albums::Model, images: &[images::Model]) -> AlbumDetailResponse {
+    let cover_url = album.cover_image_id.and_then(|cover| {
+        images
+            .iter()
+            .find(|image| image.id == cover)
+            .map(|image| image.url.clone())
+    });
+
+    AlbumDetailResponse {
+        album: AlbumResponse::new(album),
+        cover_url,
+        images: images.iter().map(AlbumImage::new).collect(),
+    }
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/album")
+        .add("/list", get(list))
+        .add("/", post(create))
+        .add("/public/{pid}", get(public_detail))
+        .add("/{pid}", get(detail).patch(update).delete(remove))
+        .add("/{pid}/cover", put(set_cover))
+        .add(
+            "/{pid}/images",
+            post(add_images).delete(remove_images).put(reorder),
+        )
+}
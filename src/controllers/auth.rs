@@ -1,11 +1,16 @@
 use crate::{
+    common::{
+        rate_limit::{self, ClientIp, Scope},
+        settings::SettingsService,
+    },
     error::{AppError, AppResult},
     mailers::auth::AuthMailer,
     models::{
         _entities::users,
         users::{LoginParams, Model, RegisterParams},
     },
-    views::auth::{ApiKeyResponse, CurrentResponse, LoginResponse},
+    views::auth::{ApiKeyResponse, CurrentResponse, DeleteAccountResponse, LoginResponse},
+    workers::account_deleter,
 };
 use ::cookie::{Cookie, time::Duration};
 use axum::http::{HeaderValue, header::SET_COOKIE};
@@ -37,8 +42,20 @@ pub struct ResetPasswordParams {
     pub new_password: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteAccountParams {
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RestoreParams {
+    pub token: String,
+}
+
 const VERIFY_TOKEN_EXP_HOURS: i64 = 24;
 const RESET_TOKEN_EXP_MIN: i64 = 30;
+const MAX_DELETION_GRACE_DAYS: u64 = 365;
+const CLEAR_JWT_COOKIE: &str = "auth_token=; Path=/; HttpOnly; Max-Age=0; SameSite=Lax";
 
 /// Register function creates a new user with the given parameters
 #[debug_handler]
@@ -116,8 +133,14 @@ async fn verify(
 #[debug_handler]
 async fn forgot(
     State(ctx): State<AppContext>,
+    ip: ClientIp,
     Json(params): Json<ForgotParams>,
 ) -> Result<Response> {
+    let keys = rate_limit::keys(ip, Some(format!("email:{}", params.email.to_lowercase())));
+    if let Err(e) = rate_limit::check(Scope::Forgot, &keys).await {
+        return Ok(e.into_response());
+    }
+
     let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
         // we don't want to expose our users email. if the email is invalid we still
         // returning success to the caller
@@ -213,12 +236,99 @@ async fn reset_password(
     Ok(())
 }
 
+/// Schedules deleting the account after the grace period and logs the user
+/// out. Their tokens stop working right away, the emailed link undoes it.
+async fn delete_account(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Json(params): Json<DeleteAccountParams>,
+) -> AppResult<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    if !user.verify_password(&params.password) {
+        return Err(AppError::WrongCredentials);
+    }
+
+    let grace_days = SettingsService::account_deletion_grace_days().await;
+    let grace = chrono::Duration::days(
+        i64::try_from(grace_days.min(MAX_DELETION_GRACE_DAYS)).unwrap_or_default(),
+    );
+    let user = user
+        .into_active_model()
+        .schedule_deletion(&ctx.db, grace)
+        .await?;
+    tracing::info!(pid = user.pid.to_string(), "account deletion scheduled");
+
+    AuthMailer::send_deletion_scheduled(&ctx, &user).await?;
+    if grace_days == 0 {
+        account_deleter::Worker::perform_later(&ctx, account_deleter::WorkerArgs {}).await?;
+    }
+
+    let mut response = format::json(DeleteAccountResponse {
+        deletes_at: user.deletes_at,
+    })?;
+    response
+        .headers_mut()
+        .insert(SET_COOKIE, HeaderValue::from_static(CLEAR_JWT_COOKIE));
+
+    Ok(response)
+}
+
+/// Undoes a scheduled account deletion with the emailed token, while the
+/// grace period lasts. The user logs in again afterwards.
+#[debug_handler]
+async fn restore(
+    State(ctx): State<AppContext>,
+    Json(params): Json<RestoreParams>,
+) -> AppResult<impl IntoResponse> {
+    let Ok(user) = users::Model::find_by_deletion_token(&ctx.db, &params.token).await else {
+        return Err(AppError::InvalidToken);
+    };
+
+    if user.deletes_at.is_none_or(|at| Local::now() >= at) {
+        return Err(AppError::TokenOutdated);
+    }
+
+    let user = user.into_active_model().cancel_deletion(&ctx.db).await?;
+    tracing::info!(pid = user.pid.to_string(), "account deletion cancelled");
+
+    Ok(())
+}
+
 /// Creates a user login and returns a token
+///
+/// Only failed attempts are counted, against the username as tried from the
+/// client's address. Others can't lock an account out that way.
 #[debug_handler]
 async fn login(
     State(ctx): State<AppContext>,
+    ip: ClientIp,
     Json(params): Json<LoginParams>,
 ) -> AppResult<impl IntoResponse> {
+    let username = params.username.to_lowercase();
+    let user_key = match ip.key() {
+        Some(ip) => format!("{}:user:{}", ip, username),
+        None => format!("user:{}", username),
+    };
+    let keys = [user_key];
+    rate_limit::ensure(Scope::Login, &keys).await?;
+
+    let user = match authenticate(&ctx, &params).await {
+        Ok(user) => user,
+        Err(e @ (AppError::UsernameNotExist | AppError::WrongCredentials)) => {
+            rate_limit::charge(Scope::Login, &keys).await;
+            return Err(e);
+        }
+        Err(e) => return Err(e),
+    };
+
+    let jwt_secret = ctx.config.get_jwt_config()?;
+    let response = insert_jwt_into_cookie(jwt_secret, &user)?;
+
+    Ok(response)
+}
+
+/// The user the credentials belong to, if they may log in.
+async fn authenticate(ctx: &AppContext, params: &LoginParams) -> AppResult<users::Model> {
     let Ok(user) = users::Model::find_by_username(&ctx.db, &params.username).await else {
         tracing::debug!(
             username = params.username,
@@ -236,10 +346,15 @@ async fn login(
         return Err(AppError::WrongCredentials);
     }
 
-    let jwt_secret = ctx.config.get_jwt_config()?;
-    let response = insert_jwt_into_cookie(jwt_secret, &user)?;
+    if user.is_disabled() {
+        return Err(AppError::AccountDisabled);
+    }
 
-    Ok(response)
+    if user.is_deleting() {
+        return Err(AppError::AccountDeleting);
+    }
+
+    Ok(user)
 }
 
 #[debug_handler]
@@ -251,10 +366,9 @@ async fn current(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Respo
 #[debug_handler]
 async fn logout(_: auth::JWT, State(_): State<AppContext>) -> Result<Response> {
     let mut response = format::json(())?;
-    let cookie = "auth_token=; Path=/; HttpOnly; Max-Age=0; SameSite=Lax";
     response
         .headers_mut()
-        .insert(SET_COOKIE, HeaderValue::from_static(cookie));
+        .insert(SET_COOKIE, HeaderValue::from_static(CLEAR_JWT_COOKIE));
 
     Ok(response)
 }
@@ -262,8 +376,14 @@ async fn logout(_: auth::JWT, State(_): State<AppContext>) -> Result<Response> {
 #[debug_handler]
 async fn resend_verification_email(
     State(ctx): State<AppContext>,
+    ip: ClientIp,
     Json(params): Json<ResendVerificationParams>,
 ) -> Result<Response> {
+    let keys = rate_limit::keys(ip, Some(format!("email:{}", params.email.to_lowercase())));
+    if let Err(e) = rate_limit::check(Scope::Resend, &keys).await {
+        return Ok(e.into_response());
+    }
+
     let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
         tracing::info!(
             email = params.email,
@@ -303,6 +423,8 @@ pub fn routes() -> Routes {
         .add("/reset/password", post(reset_password))
         .add("/current", get(current))
         .add("/logout", post(logout))
+        .add("/delete", post(delete_account))
+        .add("/restore", post(restore))
         .add("/resend-verification-mail", post(resend_verification_email))
 }
 
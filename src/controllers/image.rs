@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+
+use loco_rs::prelude::*;
+use sea_orm::TransactionTrait;
+use serde::Deserialize;
+
+use crate::{
+    common::rate_limit::{self, ClientIp, Scope},
+    models::{
+        abuse_reports::{self, ReportParams},
+        albums, image_tags,
+        images::{self, MetadataParams},
+        pending_removals,
+        tags::{self, TagsParams},
+        users::users,
+    },
+    views::image::{BulkItemResult, BulkResponse, ImageResponse},
+    workers::remover,
+};
+
+const MAX_BULK_ITEMS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAction {
+    Delete,
+    MakePublic,
+    MakePrivate,
+    AddToAlbum,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteTokenParams {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkParams {
+    pub ids: Vec<i32>,
+    pub action: BulkAction,
+    /// Target album pid, required by `add_to_album`.
+    pub album: Option<String>,
+}
+
+async fn remove(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let image = images::Model::find_by_id(&ctx.db, id).await?;
+
+    if !image.can_be_managed_by(&user) {
+        return Err(Error::NotFound);
+    }
+
+    remove_image(&ctx, image).await?;
+
+    format::json(())
+}
+
+async fn update(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    Json(params): Json<MetadataParams>,
+) -> Result<Response> {
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let image = images::Model::find_by_id(&ctx.db, id).await?;
+
+    if !image.can_be_managed_by(&user) {
+        return Err(Error::NotFound);
+    }
+
+    let image = image
+        .into_active_model()
+        .update_metadata(&ctx.db, &params)
+        .await?;
+
+    format::json(ImageResponse::new(&image))
+}
+
+async fn add_tags(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    Json(params): Json<TagsParams>,
+) -> Result<Response> {
+    let names = params.normalized().map_err(Error::BadRequest)?;
+    let (image, owner) = find_taggable(&ctx, &auth, id).await?;
+
+    let txn = ctx.db.begin().await?;
+    let tags = tags::Model::find_or_create(&txn, owner, &names).await?;
+    let tag_ids: Vec<i32> = tags.iter().map(|t| t.id).collect();
+    image_tags::Model::attach(&txn, image.id, &tag_ids).await?;
+    txn.commit().await?;
+
+    image_tag_names(&ctx, image.id).await
+}
+
+async fn remove_tags(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    Json(params): Json<TagsParams>,
+) -> Result<Response> {
+    let names = params.normalized().map_err(Error::BadRequest)?;
+    let (image, owner) = find_taggable(&ctx, &auth, id).await?;
+
+    let tags = tags::Model::find_by_names(&ctx.db, owner, &names).await?;
+    let tag_ids: Vec<i32> = tags.iter().map(|t| t.id).collect();
+    image_tags::Model::detach(&ctx.db, image.id, &tag_ids).await?;
+
+    image_tag_names(&ctx, image.id).await
+}
+
+/// Tags are scoped to the image owner, so anonymous uploads can't be tagged.
+async fn find_taggable(
+    ctx: &AppContext,
+    auth: &auth::JWT,
+    id: i32,
+) -> Result<(images::Model, Uuid)> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let image = images::Model::find_by_id(&ctx.db, id).await?;
+
+    if !image.can_be_managed_by(&user) {
+        return Err(Error::NotFound);
+    }
+    let Some(owner) = image.user_pid else {
+        return Err(Error::BadRequest("Anonymous uploads can't be tagged".to_string()));
+    };
+
+    Ok((image, owner))
+}
+
+async fn image_tag_names(ctx: &AppContext, image_id: i32) -> Result<Response> {
+    let mut names = tags::Model::names_by_image_ids(&ctx.db, &[image_id]).await?;
+
+    format::json(names.remove(&image_id).unwrap_or_default())
+}
+
+/// Applies one action to a set of images inside a single transaction. Images
+/// the caller can't manage are reported per item instead of failing the batch.
+async fn bulk(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Json(params): Json<BulkParams>,
+) -> Result<Response> {
+    if params.ids.len() > MAX_BULK_ITEMS {
+        return Err(Error::BadRequest(format!(
+            "At most {} images per request",
+            MAX_BULK_ITEMS
+        )));
+    }
+
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let album = match (&params.action, &params.album) {
+        (BulkAction::AddToAlbum, Some(pid)) => {
+            Some(albums::Model::find_by_pid_and_user_pid(&ctx.db, pid, user.pid).await?)
+        }
+        (BulkAction::AddToAlbum, None) => {
+            return Err(Error::BadRequest("Missing album".to_string()));
+        }
+        _ => None,
+    };
+
+    let txn = ctx.db.begin().await?;
+    let mut found: HashMap<i32, images::Model> = images::Model::find_by_ids(&txn, &params.ids)
+        .await?
+        .into_iter()
+        .filter(|image| image.can_be_managed_by(&user))
+        .map(|image| (image.id, image))
+        .collect();
+
+    let mut results = Vec::with_capacity(params.ids.len());
+    let mut removed = Vec::new();
+    let mut album_images = Vec::new();
+    for id in params.ids {
+        let Some(image) = found.remove(&id) else {
+            results.push(BulkItemResult::err(id, "Image not found"));
+            continue;
+        };
+
+        // A failed statement aborts the whole transaction, so database errors
+        // fail the batch rather than a single item.
+        match params.action {
+            BulkAction::Delete => {
+                removed.push(pending_removals::Model::create(&txn, &image).await?);
+                image.delete(&txn).await?;
+            }
+            BulkAction::MakePublic => {
+                image.into_active_model().set_public(&txn, true).await?;
+            }
+            BulkAction::MakePrivate => {
+                image.into_active_model().set_public(&txn, false).await?;
+            }
+            BulkAction::AddToAlbum => {
+                if image.user_pid != Some(user.pid) {
+                    results.push(BulkItemResult::err(id, "Image not found"));
+                    continue;
+                }
+                album_images.push(id);
+            }
+        }
+        results.push(BulkItemResult::ok(id));
+    }
+    if let Some(album) = album {
+        album.add_images(&txn, &album_images).await?;
+    }
+    txn.commit().await?;
+
+    for removal in &removed {
+        remover::enqueue(&ctx, removal).await;
+    }
+
+    format::json(BulkResponse { results })
+}
+
+/// Deletes an anonymous upload with the token returned by the upload.
+async fn remove_with_token(
+    State(ctx): State<AppContext>,
+    Json(params): Json<DeleteTokenParams>,
+) -> Result<Response> {
+    let image = match images::Model::find_by_delete_token(&ctx.db, params.token.trim()).await {
+        Ok(image) => image,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    remove_image(&ctx, image).await?;
+
+    format::json(())
+}
+
+/// Reports a public image for review by an admin.
+async fn report(
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Json(params): Json<ReportParams>,
+) -> Result<Response> {
+    if let Err(e) = rate_limit::check(Scope::Report, &rate_limit::keys(ip, None)).await {
+        return Ok(e.into_response());
+    }
+    if let Err(e) = validator::Validate::validate(&params) {
+        tracing::info!("参数校验失败: {}", e);
+
+        return Err(Error::Validation(e.into()));
+    }
+
+    let image = match images::Model::find_by_filename(&ctx.db, params.name.trim(), None).await {
+        Ok(image) if !image.is_expired() => image,
+        Ok(_) | Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    abuse_reports::Model::create(&ctx.db, image.id, &params).await?;
+
+    format::json(())
+}
+
+/// Deletes the image row and hands its stored objects over to the remover
+/// worker. The objects are recorded as a pending removal in the same
+/// transaction, so the purger retries them when the removal fails.
+pub async fn remove_image(ctx: &AppContext, image: images::Model) -> Result<()> {
+    let txn = ctx.db.begin().await?;
+    let removal = pending_removals::Model::create(&txn, &image).await?;
+    image.delete(&txn).await?;
+    txn.commit().await?;
+
+    remover::enqueue(ctx, &removal).await;
+
+    Ok(())
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/image")
+        .add("/bulk", post(bulk))
+        .add("/delete-by-token", post(remove_with_token))
+        .add("/report", post(report))
+        .add("/{id}", patch(update).delete(remove))
+        .add("/{id}/tags", post(add_tags).delete(remove_tags))
+}
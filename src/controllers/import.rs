@@ -0,0 +1,104 @@
+use axum::extract::DefaultBodyLimit;
+use loco_rs::prelude::*;
+
+use crate::{
+    common::{
+        quota::Quota,
+        rate_limit::{self, ClientIp, Scope},
+        settings::SettingsService,
+    },
+    controllers::upload::receive_file,
+    models::{
+        images,
+        imports::{self, ImportParams},
+        users::users,
+    },
+    views::import::ImportResponse,
+    workers::importer::{self, Worker, WorkerArgs},
+};
+
+/// Starts importing the images of an uploaded ZIP, one image per entry.
+async fn create(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(params): Query<ImportParams>,
+    multipart: Multipart,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let keys = rate_limit::keys(ip, Some(format!("user:{}", user.pid)));
+    if let Err(e) = rate_limit::check(Scope::Upload, &keys).await {
+        return Ok(e.into_response());
+    }
+    // a job lost to a restart would otherwise block the user for good
+    let stale_before =
+        (chrono::Utc::now() - chrono::TimeDelta::hours(importer::STALE_HOURS)).into();
+    for import in imports::Model::find_pending_by_user_pid(&ctx.db, user.pid).await? {
+        if !import.is_stale(stale_before) {
+            return Err(Error::BadRequest("已有导入正在进行中".to_string()));
+        }
+        tracing::warn!(import_id = import.id, "Import timed out");
+        import
+            .into_active_model()
+            .fail(&ctx.db, "Timed out".to_string())
+            .await?;
+    }
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    Quota::for_user(&user).await.check(&usage, 0)?;
+
+    // the archive may be as large as the images unpacked from it
+    let policy = SettingsService::zip_import_policy().await;
+    let upload = receive_file(multipart, policy.max_bytes).await?;
+    if !upload.raw_name().to_lowercase().ends_with(".zip") {
+        return Err(Error::BadRequest("请上传ZIP文件".to_string()));
+    }
+    let file_name = upload.raw_name().to_string();
+    let tmp_file_guard = upload.keep().await?;
+
+    let import = imports::Model::create(&ctx.db, user.pid, &file_name, params.structure).await?;
+    if let Err(e) = Worker::perform_later(
+        &ctx,
+        WorkerArgs {
+            import_id: import.id,
+            tmp_file_guard,
+            quality: params.quality.min(100),
+            public: params.public.unwrap_or(true),
+        },
+    )
+    .await
+    {
+        import
+            .into_active_model()
+            .fail(&ctx.db, format!("Failed to enqueue: {e}"))
+            .await?;
+        return Err(e);
+    }
+
+    format::json(ImportResponse::from(import))
+}
+
+/// Progress of one of the user's imports.
+async fn status(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let import = match imports::Model::find_by_id_and_user_pid(&ctx.db, id, user.pid).await {
+        Ok(import) => import,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+
+    format::json(ImportResponse::from(import))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/import")
+        .add("/", post(create))
+        .add("/{id}", get(status))
+        // archives are limited by `receive_file` instead of the body limit
+        // meant for single images
+        .layer(DefaultBodyLimit::disable())
+}
@@ -1,6 +1,13 @@
+pub mod admin;
+pub mod album;
 pub mod auth;
+pub mod image;
+pub mod import;
 pub mod profile;
 pub mod settings;
+pub mod stats;
 pub mod status;
+pub mod tag;
+pub mod tus;
 pub mod upload;
 pub mod view;
@@ -1,20 +1,90 @@
 use loco_rs::prelude::*;
 
-use crate::{models::users::users, views::profile::UserProfileResponse};
+use crate::{
+    common::quota::Quota,
+    models::{
+        exports::{self, ExportParams},
+        images,
+        users::users,
+    },
+    views::profile::{ExportResponse, UsageResponse, UserProfileResponse},
+    workers::downloader::{self, DownloadWorker, DownloadWorkerArgs},
+};
 
 async fn user_profile(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
     tracing::debug!("Received request for user profile");
     let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    let quota = Quota::for_user(&user).await;
 
     format::json(UserProfileResponse {
         name: user.username,
         email: user.email,
         api_token: user.api_key,
+        usage: UsageResponse::new(&usage, &quota),
     })
 }
 
+/// Starts exporting every image of the user into a ZIP, one at a time.
+async fn create_export(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Json(params): Json<ExportParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    // a job lost to a restart would otherwise block the user for good
+    let stale_before =
+        (chrono::Utc::now() - chrono::TimeDelta::hours(downloader::STALE_HOURS)).into();
+    for export in exports::Model::find_pending_by_user_pid(&ctx.db, user.pid).await? {
+        if !export.is_stale(stale_before) {
+            return Err(Error::BadRequest("已有导出正在进行中".to_string()));
+        }
+        tracing::warn!(export_id = export.id, "Export timed out");
+        export
+            .into_active_model()
+            .fail(&ctx.db, "Timed out".to_string())
+            .await?;
+    }
+
+    let export = exports::Model::create(&ctx.db, user.pid, &params).await?;
+    if let Err(e) = DownloadWorker::perform_later(
+        &ctx,
+        DownloadWorkerArgs {
+            export_id: export.id,
+        },
+    )
+    .await
+    {
+        export
+            .into_active_model()
+            .fail(&ctx.db, format!("Failed to enqueue: {e}"))
+            .await?;
+        return Err(e);
+    }
+
+    format::json(ExportResponse::new(export, None))
+}
+
+/// The user's latest export, with a download link once it is ready.
+async fn export_status(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let export = match exports::Model::find_latest_by_user_pid(&ctx.db, user.pid).await {
+        Ok(export) => export,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    let url = downloader::sign_url(&export)
+        .await
+        .transpose()
+        .map_err(|e| Error::string(&e))?;
+
+    format::json(ExportResponse::new(export, url))
+}
+
 pub fn router() -> Routes {
     Routes::new()
         .prefix("/api/profile")
         .add("/user", get(user_profile))
+        .add("/export", get(export_status))
+        .add("/export", post(create_export))
 }
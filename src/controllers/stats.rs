@@ -0,0 +1,82 @@
+use chrono::NaiveDate;
+use loco_rs::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    models::{
+        image_stats::{self, StatsScope},
+        images,
+        users::users,
+    },
+    views::stats::StatsResponse,
+};
+
+const DEFAULT_RANGE_DAYS: i64 = 30;
+const MAX_RANGE_DAYS: i64 = 366;
+const TOP_REFERRERS: u64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct RangeParams {
+    /// First day, `YYYY-MM-DD`. Defaults to 30 days before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last day, `YYYY-MM-DD`. Defaults to today.
+    pub to: Option<NaiveDate>,
+}
+
+impl RangeParams {
+    fn range(&self) -> Result<(NaiveDate, NaiveDate)> {
+        let to = self.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+        let from = self
+            .from
+            .unwrap_or(to - chrono::Duration::days(DEFAULT_RANGE_DAYS - 1));
+
+        if !(0..MAX_RANGE_DAYS).contains(&(to - from).num_days()) {
+            return Err(Error::BadRequest("Invalid date range".to_string()));
+        }
+
+        Ok((from, to))
+    }
+}
+
+async fn image(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(id): Path<i32>,
+    Query(params): Query<RangeParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let image = images::Model::find_by_id(&ctx.db, id).await?;
+
+    if !image.can_be_managed_by(&user) {
+        return Err(Error::NotFound);
+    }
+
+    format::json(stats(&ctx, StatsScope::Image(image.id), &params).await?)
+}
+
+/// Stats summed over all of the user's images.
+async fn user(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Query(params): Query<RangeParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+
+    format::json(stats(&ctx, StatsScope::User(user.pid), &params).await?)
+}
+
+async fn stats(ctx: &AppContext, scope: StatsScope, params: &RangeParams) -> Result<StatsResponse> {
+    let (from, to) = params.range()?;
+    let days = image_stats::Model::daily(&ctx.db, scope, from, to).await?;
+    let referrers =
+        image_stats::Model::top_referrers(&ctx.db, scope, from, to, TOP_REFERRERS).await?;
+
+    Ok(StatsResponse::new(from, to, days, referrers))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/stats")
+        .add("/image/{id}", get(image))
+        .add("/user", get(user))
+}
@@ -0,0 +1,53 @@
+use axum::http::StatusCode;
+use loco_rs::{controller::ErrorDetail, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    models::{
+        tags,
+        users::users::{self, UserRole},
+    },
+    views::tag::TagListResponse,
+};
+
+const DEFAULT_TOP_LIMIT: u64 = 20;
+const MAX_TOP_LIMIT: u64 = 100;
+
+#[derive(Deserialize)]
+pub struct TopParams {
+    pub limit: Option<u64>,
+}
+
+async fn list(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let tags = tags::Model::find_by_user_pid_with_count(&ctx.db, user.pid).await?;
+
+    format::json(TagListResponse::new(tags))
+}
+
+/// Most used tags across all users, admin only.
+async fn top(
+    auth: auth::JWT,
+    State(ctx): State<AppContext>,
+    Query(params): Query<TopParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    if user.role != UserRole::Admin {
+        return Err(Error::CustomError(
+            StatusCode::FORBIDDEN,
+            ErrorDetail::new("Forbidden".to_string(), "Admin only".to_string()),
+        ));
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_TOP_LIMIT).min(MAX_TOP_LIMIT);
+    let tags = tags::Model::most_used(&ctx.db, limit).await?;
+
+    format::json(TagListResponse::new(tags))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/tag")
+        .add("/list", get(list))
+        .add("/top", get(top))
+}
@@ -0,0 +1,533 @@
+//! Resumable uploads over the [tus 1.0](https://tus.io/protocols/resumable-upload)
+//! core protocol and its creation extension.
+//!
+//! An upload is created with its length, receives its bytes in any number of
+//! `PATCH` requests and survives restarts, as its offset is kept in the
+//! database and its bytes in the temp dir. Once complete, it is handed to the
+//! thumbnail job like a multipart upload of the same caller. Each way to
+//! authenticate has its own routes, so that the `Location` of an upload leads
+//! back to routes taking the same credentials.
+use std::{
+    collections::{HashMap, HashSet},
+    io::SeekFrom,
+    sync::{LazyLock, Mutex},
+};
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, StatusCode, header, response::Builder},
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use futures_util::StreamExt;
+use loco_rs::{controller::ErrorDetail, prelude::*};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncSeekExt, AsyncWriteExt},
+};
+
+use crate::{
+    common::{
+        quota::Quota,
+        rate_limit::{self, ClientIp, Scope},
+        settings::SettingsService,
+    },
+    controllers::upload::{
+        TEMP_DIR, TempUpload, UploadParams, enqueue_thumbnail, save_within_quota, with_extension,
+    },
+    models::{
+        images,
+        tus_uploads::{self, NewTusUpload},
+        users::users,
+    },
+};
+
+const TUS_VERSION: &str = "1.0.0";
+const OFFSET_STREAM: &str = "application/offset+octet-stream";
+
+/// Uploads a `PATCH` is currently writing to.
+static LOCKED: LazyLock<Mutex<HashSet<Uuid>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Keeps other requests from writing to an upload until dropped.
+struct UploadLock(Uuid);
+
+impl UploadLock {
+    fn acquire(uuid: Uuid) -> Option<Self> {
+        let mut locked = LOCKED.lock().unwrap_or_else(|e| e.into_inner());
+        // not `then_some`, whose lock would be dropped right away, unlocking
+        // the upload of the request holding it
+        if locked.insert(uuid) {
+            Some(Self(uuid))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for UploadLock {
+    fn drop(&mut self) {
+        LOCKED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.0);
+    }
+}
+
+async fn options() -> Result<Response> {
+    let max_size = SettingsService::max_upload_size()
+        .await
+        .saturating_mul(1024 * 1024);
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("Tus-Version", TUS_VERSION)
+        .header("Tus-Extension", "creation")
+        .header("Tus-Max-Size", max_size)
+        .body(Body::empty())?)
+}
+
+async fn create(
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    if !SettingsService::allow_everyone_upload().await {
+        return Err(Error::Unauthorized("Upload is not allowed".to_string()));
+    }
+
+    create_upload(&ctx, None, ip, &upload_params, &headers, "/api/tus").await
+}
+
+async fn create_with_jwt(
+    jwt: auth::JWT,
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+
+    create_upload(
+        &ctx,
+        Some(&user),
+        ip,
+        &upload_params,
+        &headers,
+        "/api/tus/jwt",
+    )
+    .await
+}
+
+async fn create_with_token(
+    auth: auth::ApiToken<users::Model>,
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    create_upload(
+        &ctx,
+        Some(&auth.user),
+        ip,
+        &upload_params,
+        &headers,
+        "/api/tus/token",
+    )
+    .await
+}
+
+async fn head(
+    State(ctx): State<AppContext>,
+    Path(uuid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    upload_offset(&ctx, None, uuid, &headers).await
+}
+
+async fn head_with_jwt(
+    jwt: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(uuid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+
+    upload_offset(&ctx, Some(&user), uuid, &headers).await
+}
+
+async fn head_with_token(
+    auth: auth::ApiToken<users::Model>,
+    State(ctx): State<AppContext>,
+    Path(uuid): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    upload_offset(&ctx, Some(&auth.user), uuid, &headers).await
+}
+
+async fn patch(
+    State(ctx): State<AppContext>,
+    Path(uuid): Path<Uuid>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response> {
+    append(&ctx, None, uuid, &headers, body).await
+}
+
+async fn patch_with_jwt(
+    jwt: auth::JWT,
+    State(ctx): State<AppContext>,
+    Path(uuid): Path<Uuid>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+
+    append(&ctx, Some(&user), uuid, &headers, body).await
+}
+
+async fn patch_with_token(
+    auth: auth::ApiToken<users::Model>,
+    State(ctx): State<AppContext>,
+    Path(uuid): Path<Uuid>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response> {
+    append(&ctx, Some(&auth.user), uuid, &headers, body).await
+}
+
+/// Creates an empty upload of `Upload-Length` bytes, located under `base`.
+/// The image options are taken now and applied once the upload completes.
+async fn create_upload(
+    ctx: &AppContext,
+    user: Option<&users::Model>,
+    ip: ClientIp,
+    upload_params: &UploadParams,
+    headers: &HeaderMap,
+    base: &str,
+) -> Result<Response> {
+    if let Some(response) = unsupported_version(headers) {
+        return Ok(response);
+    }
+    let keys = rate_limit::keys(ip, user.map(|user| format!("user:{}", user.pid)));
+    if let Err(e) = rate_limit::check(Scope::Upload, &keys).await {
+        return Ok(e.into_response());
+    }
+    upload_params.expiry(max_lifetime(user).await)?;
+
+    let length = header_u64(headers, "upload-length")
+        .ok_or_else(|| Error::BadRequest("Upload-Length is required".to_string()))?;
+    if length == 0 {
+        return Err(Error::BadRequest("Empty file".to_string()));
+    }
+    let max_size = SettingsService::max_upload_size()
+        .await
+        .saturating_mul(1024 * 1024);
+    if length > max_size {
+        return Err(tus_error(StatusCode::PAYLOAD_TOO_LARGE, "File too large"));
+    }
+    if let Some(user) = user {
+        let quota = Quota::for_user(user).await;
+        let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+        quota.check(&usage, length)?;
+    }
+
+    let metadata = parse_metadata(headers);
+    let raw_name = metadata
+        .get("filename")
+        .or_else(|| metadata.get("name"))
+        .cloned()
+        .unwrap_or_default();
+    let raw_name = with_extension(raw_name, metadata.get("filetype").map(String::as_str));
+
+    let upload = tus_uploads::Model::create(
+        &ctx.db,
+        NewTusUpload {
+            user_pid: user.map(|user| user.pid),
+            raw_name,
+            length,
+            quality: upload_params.quality,
+            public: upload_params.public.unwrap_or(true),
+            expires_in: upload_params.expires_in,
+            max_views: upload_params.max_views,
+        },
+    )
+    .await?;
+    fs::create_dir_all(TEMP_DIR).await?;
+    File::create(TempUpload::path(upload.uuid, &upload.raw_name)).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(header::LOCATION, format!("{}/{}", base, upload.uuid))
+        .body(Body::empty())?)
+}
+
+async fn upload_offset(
+    ctx: &AppContext,
+    user: Option<&users::Model>,
+    uuid: Uuid,
+    headers: &HeaderMap,
+) -> Result<Response> {
+    if let Some(response) = unsupported_version(headers) {
+        return Ok(response);
+    }
+    let upload = find_upload(ctx, user, uuid).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Upload-Offset", upload.offset())
+        .header("Upload-Length", upload.length())
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::empty())?)
+}
+
+/// Appends the body at `Upload-Offset`, keeping what was received of it even
+/// when the request breaks off, and finishes the upload once complete.
+async fn append(
+    ctx: &AppContext,
+    user: Option<&users::Model>,
+    uuid: Uuid,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<Response> {
+    if let Some(response) = unsupported_version(headers) {
+        return Ok(response);
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(OFFSET_STREAM) {
+        return Err(tus_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Content-Type must be application/offset+octet-stream",
+        ));
+    }
+    let offset = header_u64(headers, "upload-offset")
+        .ok_or_else(|| Error::BadRequest("Upload-Offset is required".to_string()))?;
+
+    let Some(_lock) = UploadLock::acquire(uuid) else {
+        return Err(tus_error(StatusCode::LOCKED, "Upload is in progress"));
+    };
+    let upload = find_upload(ctx, user, uuid).await?;
+    if offset != upload.offset() {
+        return Err(tus_error(StatusCode::CONFLICT, "Upload-Offset mismatch"));
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(TempUpload::path(upload.uuid, &upload.raw_name))
+        .await?;
+    // drops whatever a broken off request wrote past the saved offset
+    file.set_len(offset).await?;
+    file.seek(SeekFrom::End(0)).await?;
+    let (written, result) = write_body(&mut file, body, upload.length() - offset).await;
+    file.flush().await?;
+
+    let offset = offset + written;
+    let upload = upload
+        .into_active_model()
+        .set_offset(&ctx.db, offset)
+        .await?;
+    result?;
+
+    let response = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("Upload-Offset", offset);
+    let response = if offset == upload.length() {
+        finish(ctx, user, upload, response).await?
+    } else {
+        response
+    };
+
+    Ok(response.body(Body::empty())?)
+}
+
+/// Writes the body to the file, up to `left` bytes. Returns how much got
+/// written along with the error that stopped it, if any.
+async fn write_body(file: &mut File, body: Body, left: u64) -> (u64, Result<()>) {
+    let mut stream = body.into_data_stream();
+    let mut written = 0;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => return (written, Err(Error::BadRequest(e.to_string()))),
+        };
+        if written + chunk.len() as u64 > left {
+            return (
+                written,
+                Err(Error::BadRequest(
+                    "Upload exceeds Upload-Length".to_string(),
+                )),
+            );
+        }
+        if let Err(e) = file.write_all(&chunk).await {
+            return (written, Err(e.into()));
+        }
+        written += chunk.len() as u64;
+    }
+
+    (written, Ok(()))
+}
+
+/// Hands the complete upload to the thumbnail job and tells where the image
+/// is, in headers as a `PATCH` response has no body.
+async fn finish(
+    ctx: &AppContext,
+    user: Option<&users::Model>,
+    upload: tus_uploads::Model,
+    response: Builder,
+) -> Result<Builder> {
+    let upload_params = UploadParams {
+        public: Some(upload.public),
+        quality: u8::try_from(upload.quality).unwrap_or(100),
+        expires_in: upload.expires_in.and_then(|secs| u64::try_from(secs).ok()),
+        max_views: upload.max_views,
+    };
+    let (expires_at, max_views) = upload_params.expiry(max_lifetime(user).await)?;
+
+    // the temp file goes with the upload from here on, even if it's no image
+    let temp = TempUpload::resume(upload.uuid, &upload.raw_name).await?;
+    upload.delete(&ctx.db).await?;
+
+    let (mut r, args, quota) = match user {
+        Some(user) => {
+            let quota = Quota::for_user(user).await;
+            let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+            let (mut r, args) = temp
+                .finish(ctx, upload_params.quality, Some((&quota, &usage)))
+                .await?;
+            r.is_public = upload_params.public.unwrap_or(true);
+            r.user_id = Some(user.pid);
+            (r, args, Some(quota))
+        }
+        None => {
+            let (mut r, args) = temp.finish(ctx, upload_params.quality, None).await?;
+            r.delete_token = Some(images::Model::new_delete_token(r.uuid));
+            (r, args, None)
+        }
+    };
+    r.expires_at = expires_at;
+    r.max_views = max_views;
+
+    match quota {
+        Some(quota) => save_within_quota(ctx, &r, &quota).await?,
+        None => images::Model::save_local_with_result(&ctx.db, &r).await?,
+    };
+    enqueue_thumbnail(ctx, args).await;
+
+    let mut response = response;
+    if r.is_public {
+        response = response.header("Image-Url", r.url);
+    }
+    if let Some(delete_token) = r.delete_token {
+        response = response.header("Image-Delete-Token", delete_token);
+    }
+
+    Ok(response)
+}
+
+/// The upload, if it belongs to the caller.
+async fn find_upload(
+    ctx: &AppContext,
+    user: Option<&users::Model>,
+    uuid: Uuid,
+) -> Result<tus_uploads::Model> {
+    match tus_uploads::Model::find_by_uuid(&ctx.db, uuid).await {
+        Ok(upload) if upload.user_pid == user.map(|user| user.pid) => Ok(upload),
+        Ok(_) | Err(ModelError::EntityNotFound) => Err(Error::NotFound),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn max_lifetime(user: Option<&users::Model>) -> u64 {
+    match user {
+        Some(_) => 0,
+        None => SettingsService::anonymous_max_lifetime().await,
+    }
+}
+
+/// Adds `Tus-Resumable` to every response, errors included, as the protocol
+/// requires.
+async fn with_tus_resumable(mut response: Response) -> Response {
+    response.headers_mut().insert(
+        "Tus-Resumable",
+        header::HeaderValue::from_static(TUS_VERSION),
+    );
+    response
+}
+
+/// The `412` response to a request for another protocol version.
+fn unsupported_version(headers: &HeaderMap) -> Option<Response> {
+    let version = headers
+        .get("tus-resumable")
+        .and_then(|value| value.to_str().ok());
+    if version == Some(TUS_VERSION) {
+        return None;
+    }
+
+    let mut response = StatusCode::PRECONDITION_FAILED.into_response();
+    response
+        .headers_mut()
+        .insert("Tus-Version", header::HeaderValue::from_static(TUS_VERSION));
+    Some(response)
+}
+
+fn tus_error(status: StatusCode, description: &str) -> Error {
+    Error::CustomError(
+        status,
+        ErrorDetail::new(
+            status.canonical_reason().unwrap_or_default().to_string(),
+            description.to_string(),
+        ),
+    )
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// `Upload-Metadata` as pairs of a key and a base64 value, which may be left
+/// out. Pairs that don't decode are skipped.
+fn parse_metadata(headers: &HeaderMap) -> HashMap<String, String> {
+    let Some(metadata) = headers
+        .get("upload-metadata")
+        .and_then(|value| value.to_str().ok())
+    else {
+        return HashMap::new();
+    };
+
+    metadata
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = match pair.trim().split_once(' ') {
+                Some((key, value)) => {
+                    let value = BASE64_STANDARD.decode(value.trim()).ok()?;
+                    (key, String::from_utf8(value).ok()?)
+                }
+                None => (pair.trim(), String::new()),
+            };
+            (!key.is_empty()).then(|| (key.to_string(), value))
+        })
+        .collect()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/tus")
+        .add("/", post(create).options(options))
+        .add("/jwt", post(create_with_jwt).options(options))
+        .add("/token", post(create_with_token).options(options))
+        .add("/{uuid}", axum::routing::head(head).patch(patch))
+        .add(
+            "/jwt/{uuid}",
+            axum::routing::head(head_with_jwt).patch(patch_with_jwt),
+        )
+        .add(
+            "/token/{uuid}",
+            axum::routing::head(head_with_token).patch(patch_with_token),
+        )
+        .layer(axum::middleware::map_response(with_tus_resumable))
+}
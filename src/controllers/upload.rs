@@ -1,5 +1,8 @@
-use loco_rs::prelude::*;
+use axum::http::StatusCode;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use loco_rs::{controller::ErrorDetail, prelude::*};
 use mime_guess2::mime;
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 use std::path::Path;
@@ -8,6 +11,9 @@ use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
 
 use crate::common::client::get_r2;
+use crate::common::fetch::Fetcher;
+use crate::common::quota::Quota;
+use crate::common::rate_limit::{self, ClientIp, Scope};
 use crate::common::settings::SettingsService;
 use crate::models::images;
 use crate::models::tmps;
@@ -15,12 +21,55 @@ use crate::models::users::users;
 use crate::views::upload::{PresignResponse, UploadResponse};
 use crate::workers::thumbnail::{Worker, WorkerArgs};
 
-const TEMP_DIR: &str = "tmp_upload";
+pub const TEMP_DIR: &str = "tmp_upload";
 
 #[derive(Debug, Deserialize)]
 pub struct UploadParams {
     pub public: Option<bool>,
     pub quality: u8,
+    /// Seconds until the image expires.
+    pub expires_in: Option<u64>,
+    /// Number of views after which the image expires.
+    pub max_views: Option<i32>,
+}
+
+impl UploadParams {
+    /// Expiry time and view limit of the upload. A non-zero `max_lifetime`
+    /// (hours) caps the expiry time and applies even when none was asked for.
+    pub fn expiry(&self, max_lifetime: u64) -> Result<(Option<DateTimeWithTimeZone>, Option<i32>)> {
+        if self.expires_in == Some(0) || self.max_views.is_some_and(|v| v < 1) {
+            return Err(Error::BadRequest("Invalid expiry".to_string()));
+        }
+
+        let max_secs = (max_lifetime > 0).then(|| max_lifetime.saturating_mul(3600));
+        let secs = match (self.expires_in, max_secs) {
+            (Some(secs), Some(max)) => Some(secs.min(max)),
+            (secs, max) => secs.or(max),
+        };
+        let expires_at = secs
+            .map(|secs| {
+                let secs = i64::try_from(secs).unwrap_or(i64::MAX);
+                chrono::TimeDelta::try_seconds(secs)
+                    .and_then(|delta| chrono::Utc::now().checked_add_signed(delta))
+                    .ok_or_else(|| Error::BadRequest("Invalid expiry".to_string()))
+            })
+            .transpose()?;
+
+        Ok((expires_at.map(Into::into), self.max_views))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UrlUploadParams {
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Base64UploadParams {
+    /// Base64 of the file, or a `data:` URI with it.
+    pub data: String,
+    pub file_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,24 +95,40 @@ pub struct UploadResult {
     pub user_id: Option<Uuid>,
     pub uuid: Uuid,
     pub raw_name: String,
-    // pub size: String,
+    /// Bytes of the uploaded original.
+    pub size: i64,
+    /// Lets an anonymous uploader delete the image later.
+    pub delete_token: Option<String>,
+    pub expires_at: Option<DateTimeWithTimeZone>,
+    pub max_views: Option<i32>,
     // pub status: String,
 }
 
 async fn upload(
     State(ctx): State<AppContext>,
+    ip: ClientIp,
     Query(upload_params): Query<UploadParams>,
     multipart: Multipart,
 ) -> Result<Response> {
     if !SettingsService::allow_everyone_upload().await {
         return Err(Error::Unauthorized("Upload is not allowed".to_string()));
     }
-
-    match upload_files(multipart, &ctx, upload_params.quality).await {
-        Ok(r) => {
+    if let Err(e) = rate_limit::check(Scope::Upload, &rate_limit::keys(ip, None)).await {
+        return Ok(e.into_response());
+    }
+    let (expires_at, max_views) =
+        upload_params.expiry(SettingsService::anonymous_max_lifetime().await)?;
+
+    match upload_files(multipart, &ctx, upload_params.quality, None).await {
+        Ok((mut r, args)) => {
+            r.delete_token = Some(images::Model::new_delete_token(r.uuid));
+            r.expires_at = expires_at;
+            r.max_views = max_views;
             images::Model::save_local_with_result(&ctx.db, &r).await?;
+            enqueue_thumbnail(&ctx, args).await;
             format::json(UploadResponse::from(r))
         }
+        Err(e @ Error::BadRequest(_)) => Err(e),
         Err(e) => {
             tracing::error!("Failed to upload files: {}", e);
 
@@ -75,25 +140,47 @@ async fn upload(
 async fn upload_with_jwt(
     jwt: auth::JWT,
     State(ctx): State<AppContext>,
+    ip: ClientIp,
     Query(upload_params): Query<UploadParams>,
     multipart: Multipart,
 ) -> Result<Response> {
     let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+    let keys = rate_limit::keys(ip, Some(format!("user:{}", user.pid)));
+    if let Err(e) = rate_limit::check(Scope::Upload, &keys).await {
+        return Ok(e.into_response());
+    }
     let public = upload_params.public.unwrap_or(true);
+    let (expires_at, max_views) = upload_params.expiry(0)?;
+
+    let quota = Quota::for_user(&user).await;
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    quota.check(&usage, 0)?;
 
-    match upload_files(multipart, &ctx, upload_params.quality).await {
-        Ok(mut r) => {
+    match upload_files(multipart, &ctx, upload_params.quality, Some((&quota, &usage))).await {
+        Ok((mut r, args)) => {
             r.is_public = public;
             r.user_id = Some(user.pid);
+            r.expires_at = expires_at;
+            r.max_views = max_views;
 
-            images::Model::save_local_with_result(&ctx.db, &r).await?;
+            save_within_quota(&ctx, &r, &quota).await?;
+            enqueue_thumbnail(&ctx, args).await;
             let res = if public {
-                UploadResponse { url: Some(r.url) }
+                UploadResponse {
+                    url: Some(r.url),
+                    delete_token: None,
+                    expires_at: r.expires_at,
+                }
             } else {
-                UploadResponse { url: None }
+                UploadResponse {
+                    url: None,
+                    delete_token: None,
+                    expires_at: r.expires_at,
+                }
             };
             format::json(res)
         }
+        Err(e @ (Error::CustomError(..) | Error::BadRequest(_))) => Err(e),
         Err(e) => {
             tracing::error!("Failed to upload files: {}", e);
 
@@ -102,6 +189,156 @@ async fn upload_with_jwt(
     }
 }
 
+/// Rehosts the image at a remote URL, see [`crate::common::fetch`].
+async fn upload_url_with_jwt(
+    jwt: auth::JWT,
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    Json(params): Json<UrlUploadParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+    let keys = rate_limit::keys(ip, Some(format!("user:{}", user.pid)));
+    if let Err(e) = rate_limit::check(Scope::Upload, &keys).await {
+        return Ok(e.into_response());
+    }
+    let public = upload_params.public.unwrap_or(true);
+    let (expires_at, max_views) = upload_params.expiry(0)?;
+
+    let quota = Quota::for_user(&user).await;
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    quota.check(&usage, 0)?;
+
+    match fetch_file(
+        &params.url,
+        &ctx,
+        upload_params.quality,
+        Some((&quota, &usage)),
+    )
+    .await
+    {
+        Ok((mut r, args)) => {
+            r.is_public = public;
+            r.user_id = Some(user.pid);
+            r.expires_at = expires_at;
+            r.max_views = max_views;
+
+            save_within_quota(&ctx, &r, &quota).await?;
+            enqueue_thumbnail(&ctx, args).await;
+            format::json(UploadResponse {
+                url: public.then_some(r.url),
+                delete_token: None,
+                expires_at: r.expires_at,
+            })
+        }
+        Err(e @ (Error::CustomError(..) | Error::BadRequest(_))) => Err(e),
+        Err(e) => {
+            tracing::error!("Failed to upload from URL: {}", e);
+
+            Err(Error::InternalServerError)
+        }
+    }
+}
+
+/// Uploads a base64 payload, see [`decode_file`].
+async fn upload_base64(
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    Json(params): Json<Base64UploadParams>,
+) -> Result<Response> {
+    if !SettingsService::allow_everyone_upload().await {
+        return Err(Error::Unauthorized("Upload is not allowed".to_string()));
+    }
+    if let Err(e) = rate_limit::check(Scope::Upload, &rate_limit::keys(ip, None)).await {
+        return Ok(e.into_response());
+    }
+    let (expires_at, max_views) =
+        upload_params.expiry(SettingsService::anonymous_max_lifetime().await)?;
+
+    match decode_file(&params, &ctx, upload_params.quality, None).await {
+        Ok((mut r, args)) => {
+            r.delete_token = Some(images::Model::new_delete_token(r.uuid));
+            r.expires_at = expires_at;
+            r.max_views = max_views;
+            images::Model::save_local_with_result(&ctx.db, &r).await?;
+            enqueue_thumbnail(&ctx, args).await;
+            format::json(UploadResponse::from(r))
+        }
+        Err(e @ Error::BadRequest(_)) => Err(e),
+        Err(e) => {
+            tracing::error!("Failed to upload base64 file: {}", e);
+
+            Err(Error::InternalServerError)
+        }
+    }
+}
+
+async fn upload_base64_with_jwt(
+    jwt: auth::JWT,
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    Json(params): Json<Base64UploadParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+
+    upload_base64_as(&ctx, &user, ip, &upload_params, &params).await
+}
+
+async fn upload_base64_with_token(
+    auth: auth::ApiToken<users::Model>,
+    State(ctx): State<AppContext>,
+    ip: ClientIp,
+    Query(upload_params): Query<UploadParams>,
+    Json(params): Json<Base64UploadParams>,
+) -> Result<Response> {
+    upload_base64_as(&ctx, &auth.user, ip, &upload_params, &params).await
+}
+
+/// Uploads a base64 payload for a signed in user, like [`upload_with_jwt`].
+async fn upload_base64_as(
+    ctx: &AppContext,
+    user: &users::Model,
+    ip: ClientIp,
+    upload_params: &UploadParams,
+    params: &Base64UploadParams,
+) -> Result<Response> {
+    let keys = rate_limit::keys(ip, Some(format!("user:{}", user.pid)));
+    if let Err(e) = rate_limit::check(Scope::Upload, &keys).await {
+        return Ok(e.into_response());
+    }
+    let public = upload_params.public.unwrap_or(true);
+    let (expires_at, max_views) = upload_params.expiry(0)?;
+
+    let quota = Quota::for_user(user).await;
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    quota.check(&usage, 0)?;
+
+    match decode_file(params, ctx, upload_params.quality, Some((&quota, &usage))).await {
+        Ok((mut r, args)) => {
+            r.is_public = public;
+            r.user_id = Some(user.pid);
+            r.expires_at = expires_at;
+            r.max_views = max_views;
+
+            save_within_quota(ctx, &r, &quota).await?;
+            enqueue_thumbnail(ctx, args).await;
+            format::json(UploadResponse {
+                url: public.then_some(r.url),
+                delete_token: None,
+                expires_at: r.expires_at,
+            })
+        }
+        Err(e @ (Error::CustomError(..) | Error::BadRequest(_))) => Err(e),
+        Err(e) => {
+            tracing::error!("Failed to upload base64 file: {}", e);
+
+            Err(Error::InternalServerError)
+        }
+    }
+}
+
 // TODO: implement upload_with_token
 async fn upload_with_token(
     auth: auth::ApiToken<users::Model>,
@@ -128,87 +365,168 @@ impl Drop for TempFileGuard {
     }
 }
 
-async fn upload_files(
-    mut multipart: Multipart,
+/// Saves the upload of a signed in user. Their usage is read again with the
+/// user row locked, so parallel uploads can't all pass the check made before
+/// the file was received.
+///
+/// # Errors
+///
+/// When the image no longer fits the quota or can't be saved
+pub async fn save_within_quota(
     ctx: &AppContext,
-    quality: u8,
-) -> Result<UploadResult> {
-    tokio::fs::create_dir_all(TEMP_DIR).await?;
-    // let client = get_garage();
+    r: &UploadResult,
+    quota: &Quota,
+) -> Result<images::Model> {
+    let Some(user_pid) = r.user_id else {
+        return Ok(images::Model::save_local_with_result(&ctx.db, r).await?);
+    };
+
+    let txn = ctx.db.begin().await?;
+    let usage = images::Model::usage_by_user_pid_locked(&txn, user_pid).await?;
+    quota.check(&usage, u64::try_from(r.size).unwrap_or(0))?;
+    let image = images::Model::save_local_with_result(&txn, r).await?;
+    txn.commit().await?;
+
+    Ok(image)
+}
 
-    if let Some(mut field) = multipart
-        .next_field()
-        .await
-        .map_err(|e| Error::BadRequest(e.to_string()))?
-    {
-        let raw_name = field
-            .file_name()
-            .and_then(|p| Path::new(p).file_name().and_then(|e| e.to_str()))
-            .unwrap_or("")
-            .to_string();
-        let ext = field
-            .file_name()
-            .and_then(|p| Path::new(p).extension().and_then(|e| e.to_str()))
-            .unwrap_or("");
+pub async fn enqueue_thumbnail(ctx: &AppContext, args: WorkerArgs) {
+    if let Err(e) = Worker::perform_later(ctx, args).await {
+        tracing::error!("Failed to enqueue worker task: {}", e);
+    }
+}
+
+/// A file being received into the temp dir, named after a fresh UUID with the
+/// extension of its raw name.
+pub struct TempUpload {
+    uuid: Uuid,
+    raw_name: String,
+    path: PathBuf,
+    file: File,
+    guard: TempFileGuard,
+}
+
+impl TempUpload {
+    /// # Errors
+    ///
+    /// When the temp file can't be created
+    pub async fn create(raw_name: &str) -> Result<Self> {
+        tokio::fs::create_dir_all(TEMP_DIR).await?;
 
         let uuid = Uuid::now_v7();
+        let path = Self::path(uuid, raw_name);
+        let file = File::create(&path).await?;
+
+        Ok(Self::new(uuid, raw_name, path, file))
+    }
+
+    /// Picks up a file received earlier into [`TempUpload::path`], e.g. over
+    /// several requests.
+    ///
+    /// # Errors
+    ///
+    /// When the temp file can't be opened
+    pub async fn resume(uuid: Uuid, raw_name: &str) -> Result<Self> {
+        let path = Self::path(uuid, raw_name);
+        let file = fs::OpenOptions::new().append(true).open(&path).await?;
+
+        Ok(Self::new(uuid, raw_name, path, file))
+    }
+
+    /// Where the file of an upload is received, named after its UUID with the
+    /// extension of its raw name.
+    #[must_use]
+    pub fn path(uuid: Uuid, raw_name: &str) -> PathBuf {
+        let ext = Path::new(raw_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
         let mut file_name = String::with_capacity(37 + ext.len());
-        let mut avif_name = String::with_capacity(41);
         let _ = write!(file_name, "{}.{}", uuid, ext);
-        let _ = write!(avif_name, "{}.avif", uuid);
 
-        let tmp_path = Path::new(TEMP_DIR).join(&file_name);
-        let mut tmp_file = File::create(&tmp_path).await?;
+        Path::new(TEMP_DIR).join(&file_name)
+    }
 
-        let tmp_file_guard = TempFileGuard(tmp_path.clone());
+    fn new(uuid: Uuid, raw_name: &str, path: PathBuf, file: File) -> Self {
+        let raw_name = Path::new(raw_name)
+            .file_name()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_string();
+        let guard = TempFileGuard(path.clone());
 
-        while let Some(chunk) = field
-            .chunk()
-            .await
-            .map_err(|e| Error::BadRequest(e.to_string()))?
-        {
-            tmp_file.write_all(&chunk).await?;
+        Self {
+            uuid,
+            raw_name,
+            path,
+            file,
+            guard,
         }
-        tmp_file.flush().await?;
+    }
+
+    /// # Errors
+    ///
+    /// When writing the temp file fails
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        self.file.write_all(chunk).await?;
+        Ok(())
+    }
+
+    /// Name of the file as sent, without its path.
+    #[must_use]
+    pub fn raw_name(&self) -> &str {
+        &self.raw_name
+    }
+
+    /// Ends the upload without checking the file, for a job that reads it
+    /// itself. The file is deleted once the returned guard is dropped.
+    ///
+    /// # Errors
+    ///
+    /// When writing the temp file fails
+    pub async fn keep(mut self) -> Result<TempFileGuard> {
+        self.file.flush().await?;
+        Ok(self.guard)
+    }
 
-        // let size = format!(
-        //     "{:.2} MB",
-        //     tmp_path.metadata()?.len() as f32 / 1024.0 / 1024.0
-        // );
+    /// Checks the received file against the uploader's quota if given. The
+    /// returned thumbnail job is to be enqueued once the image is saved, it
+    /// keeps the file as the image's original.
+    ///
+    /// # Errors
+    ///
+    /// When the file is not an image or doesn't fit the quota
+    pub async fn finish(
+        mut self,
+        ctx: &AppContext,
+        quality: u8,
+        quota: Option<(&Quota, &images::Usage)>,
+    ) -> Result<(UploadResult, WorkerArgs)> {
+        self.file.flush().await?;
+
+        let size = self.path.metadata()?.len();
+        if let Some((quota, usage)) = quota {
+            quota.check(usage, size)?;
+        }
 
-        let mime = mime_guess2::from_path(&tmp_path).first_or_octet_stream();
+        let mime = mime_guess2::from_path(&self.path).first_or_octet_stream();
         if mime.type_() != mime::IMAGE {
             return Err(Error::BadRequest("Invalid file type".to_string()));
         }
 
-        // let body = ByteStream::from_path(&tmp_path).await.map_err(|e| {
-        //     tracing::error!("Failed to read file: {}", e);
-        //     Error::InternalServerError
-        // })?;
-
-        // let result = client
-        //     .pub_object(
-        //         &file_name,
-        //         body,
-        //         mime.as_ref(),
-        //         crate::common::client::Position::Original,
-        //     )
-        //     .await;
-
-        let quality = quality.min(100);
+        let mut avif_name = String::with_capacity(41);
+        let _ = write!(avif_name, "{}.avif", self.uuid);
         let args = WorkerArgs {
             preview_key: avif_name.clone(),
-            tmp_file_guard,
-            quality,
+            original_key: self
+                .path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_owned),
+            tmp_file_guard: self.guard,
+            quality: quality.min(100),
         };
 
-        if let Err(e) = Worker::perform_later(ctx, args).await {
-            tracing::error!("Failed to enqueue worker task: {}", e);
-        }
-
-        // match result {
-        //     Ok(_) => {
-        // tracing::debug!("Ok, uploaded file to S3");
         let local_base_url = SettingsService::local_base_url().await;
         let url = if local_base_url.trim().is_empty() {
             ctx.config.server.full_url() + "/api/view"
@@ -216,36 +534,191 @@ async fn upload_files(
             local_base_url.to_string()
         };
 
-        return Ok(UploadResult {
-            url: format!("{}/{}", url, avif_name),
-            file_name: avif_name,
-            is_public: true,
-            user_id: None,
-            uuid,
-            raw_name,
-            // size,
-        });
-        // }
-        // Err(e) => {
-        //     tracing::error!("Failed to upload file to S3: {}", e);
-        //     return Err(Error::InternalServerError);
-        // }
-        // }
+        Ok((
+            UploadResult {
+                url: format!("{}/{}", url, avif_name),
+                file_name: avif_name,
+                is_public: true,
+                user_id: None,
+                uuid: self.uuid,
+                raw_name: self.raw_name,
+                size: i64::try_from(size).unwrap_or(i64::MAX),
+                delete_token: None,
+                expires_at: None,
+                max_views: None,
+            },
+            args,
+        ))
+    }
+}
+
+/// Receives the first file of the form into the temp dir, see
+/// [`TempUpload::finish`].
+async fn upload_files(
+    multipart: Multipart,
+    ctx: &AppContext,
+    quality: u8,
+    quota: Option<(&Quota, &images::Usage)>,
+) -> Result<(UploadResult, WorkerArgs)> {
+    let max_bytes = SettingsService::max_upload_size()
+        .await
+        .saturating_mul(1024 * 1024);
+    receive_file(multipart, max_bytes)
+        .await?
+        .finish(ctx, quality, quota)
+        .await
+}
+
+/// Receives the first file of the form into the temp dir, refusing it once it
+/// grows past `max_bytes`.
+///
+/// # Errors
+///
+/// When the form has no file, can't be read or the file is too large
+pub async fn receive_file(mut multipart: Multipart, max_bytes: u64) -> Result<TempUpload> {
+    let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?
+    else {
+        return Err(Error::BadRequest("No file".to_string()));
+    };
+
+    let mut upload = TempUpload::create(field.file_name().unwrap_or("")).await?;
+    let mut size: u64 = 0;
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?
+    {
+        size = size.saturating_add(chunk.len() as u64);
+        if size > max_bytes {
+            return Err(Error::BadRequest("File too large".to_string()));
+        }
+        upload.write(&chunk).await?;
+    }
+
+    Ok(upload)
+}
+
+/// Downloads the file at `url` into the temp dir, see [`TempUpload::finish`].
+/// Its name comes from the last path segment of the final URL, given an
+/// extension from the content type when it has none.
+async fn fetch_file(
+    url: &str,
+    ctx: &AppContext,
+    quality: u8,
+    quota: Option<(&Quota, &images::Usage)>,
+) -> Result<(UploadResult, WorkerArgs)> {
+    let policy = SettingsService::url_upload_policy().await;
+    let fetcher = Fetcher::new(&policy).map_err(|e| Error::BadRequest(e.to_string()))?;
+    let mut download = fetcher
+        .get(url)
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    let raw_name = download
+        .url()
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .and_then(|segment| percent_decode_str(segment).decode_utf8().ok())
+        .map(|segment| segment.into_owned())
+        .unwrap_or_default();
+    let raw_name = with_extension(raw_name, download.content_type());
+
+    let mut upload = TempUpload::create(&raw_name).await?;
+    while let Some(chunk) = download
+        .chunk()
+        .await
+        .map_err(|e| Error::BadRequest(e.to_string()))?
+    {
+        upload.write(&chunk).await?;
+    }
+
+    upload.finish(ctx, quality, quota).await
+}
+
+/// Writes a base64 payload or `data:` URI into the temp dir, see
+/// [`TempUpload::finish`]. Without an extension in the file name, it comes
+/// from the content type of the URI.
+async fn decode_file(
+    params: &Base64UploadParams,
+    ctx: &AppContext,
+    quality: u8,
+    quota: Option<(&Quota, &images::Usage)>,
+) -> Result<(UploadResult, WorkerArgs)> {
+    let (content_type, payload) = match params.data.strip_prefix("data:") {
+        Some(uri) => {
+            let (meta, payload) = uri
+                .split_once(',')
+                .ok_or_else(|| Error::BadRequest("Invalid data URI".to_string()))?;
+            let content_type = meta
+                .strip_suffix(";base64")
+                .ok_or_else(|| Error::BadRequest("Data URI is not base64".to_string()))?;
+            (Some(content_type), payload)
+        }
+        None => (None, params.data.as_str()),
+    };
+
+    // clipboard tools may wrap the payload
+    let payload: Vec<u8> = payload
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    let max_bytes = SettingsService::max_upload_size()
+        .await
+        .saturating_mul(1024 * 1024);
+    if base64::decoded_len_estimate(payload.len()) as u64 > max_bytes.saturating_add(3) {
+        return Err(Error::BadRequest("File too large".to_string()));
+    }
+    let data = BASE64_STANDARD
+        .decode(&payload)
+        .map_err(|_| Error::BadRequest("Invalid base64 data".to_string()))?;
+    if data.len() as u64 > max_bytes {
+        return Err(Error::BadRequest("File too large".to_string()));
+    }
+
+    let raw_name = with_extension(params.file_name.clone().unwrap_or_default(), content_type);
+    let mut upload = TempUpload::create(&raw_name).await?;
+    upload.write(&data).await?;
+
+    upload.finish(ctx, quality, quota).await
+}
+
+/// Gives a name without extension one for the content type, if it has any.
+pub fn with_extension(mut raw_name: String, content_type: Option<&str>) -> String {
+    if Path::new(&raw_name).extension().is_none()
+        && let Some(ext) = content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .and_then(|mime| mime_guess2::get_mime_extensions_str(mime.trim()))
+            .and_then(|exts| exts.first())
+    {
+        if raw_name.is_empty() {
+            raw_name = "image".to_string();
+        }
+        let _ = write!(raw_name, ".{}", ext);
     }
 
-    Err(Error::BadRequest("No file".to_string()))
+    raw_name
 }
 
 async fn presign(
     State(_ctx): State<AppContext>,
+    ip: ClientIp,
     Json(params): Json<PresignParams>,
 ) -> Result<Response> {
     if !SettingsService::allow_everyone_upload().await {
         return Err(Error::Unauthorized("Upload not allowed".to_string()));
     }
+    if let Err(e) = rate_limit::check(Scope::Presign, &rate_limit::keys(ip, None)).await {
+        return Ok(e.into_response());
+    }
 
     match presign_file(&params.file_name, &params.content_type).await {
-        Ok((_, url)) => format::json(PresignResponse { upload_url: url }),
+        Ok((file_name, url)) => format::json(PresignResponse {
+            upload_url: url,
+            file_name,
+        }),
         Err(e) => {
             tracing::error!("Failed to presign file: {}", e);
             Err(Error::InternalServerError)
@@ -256,15 +729,28 @@ async fn presign(
 async fn presign_with_jwt(
     auth: auth::JWT,
     State(ctx): State<AppContext>,
+    ip: ClientIp,
     Json(params): Json<PresignParams>,
 ) -> Result<Response> {
     let user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let keys = rate_limit::keys(ip, Some(format!("user:{}", user.pid)));
+    if let Err(e) = rate_limit::check(Scope::Presign, &keys).await {
+        return Ok(e.into_response());
+    }
+
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    Quota::for_user(&user)
+        .await
+        .check(&usage, u64::try_from(params.size).unwrap_or(0))?;
 
     match presign_file(&params.file_name, &params.content_type).await {
         Ok((file_name, url)) => {
             tmps::Model::create_tmp_record(&ctx.db, user.pid, &file_name).await?;
 
-            format::json(PresignResponse { upload_url: url })
+            format::json(PresignResponse {
+                upload_url: url,
+                file_name,
+            })
         }
         Err(e) => {
             tracing::error!("Failed to presign file: {}", e);
@@ -278,7 +764,7 @@ async fn presign_with_token(
     State(ctx): State<AppContext>,
     Json(params): Json<PresignParams>,
 ) -> Result<Response> {
-    todo!()
+    Err(not_implemented())
 }
 
 async fn presign_file(file_name: &str, content_type: &str) -> Result<(String, String)> {
@@ -301,15 +787,97 @@ async fn confirm(
     State(_ctx): State<AppContext>,
     Json(params): Json<PresignParams>,
 ) -> Result<Response> {
-    todo!()
+    Err(not_implemented())
 }
 
-async fn confirm_with_jwt() -> Result<Response> {
-    todo!()
+/// Takes in a file put to a URL from [`presign_with_jwt`]. The size claimed
+/// there is not trusted, the stored file is checked against the quota again
+/// and then processed like any other upload.
+async fn confirm_with_jwt(
+    jwt: auth::JWT,
+    State(ctx): State<AppContext>,
+    Query(upload_params): Query<UploadParams>,
+    Json(params): Json<ConfirmParams>,
+) -> Result<Response> {
+    let user = users::Model::find_by_pid(&ctx.db, &jwt.claims.pid).await?;
+    let tmp = match tmps::Model::find_by_user_pid_and_file_name(
+        &ctx.db,
+        &user.pid.to_string(),
+        &params.file_name,
+    )
+    .await
+    {
+        Ok(tmp) => tmp,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    let (expires_at, max_views) = upload_params.expiry(0)?;
+
+    let client = get_r2();
+    let mut output = match client.get_object(&tmp.file_name).await {
+        Ok(output) => output,
+        Err(e) => {
+            if e.into_service_error().is_no_such_key() {
+                return Err(Error::BadRequest("File not uploaded".to_string()));
+            }
+            return Err(Error::InternalServerError);
+        }
+    };
+
+    let quota = Quota::for_user(&user).await;
+    let usage = images::Model::usage_by_user_pid(&ctx.db, user.pid).await?;
+    let stored_size = output
+        .content_length()
+        .and_then(|len| u64::try_from(len).ok())
+        .unwrap_or(0);
+    let result = match quota.check(&usage, stored_size) {
+        Ok(()) => {
+            let mut upload = TempUpload::create(&tmp.file_name).await?;
+            while let Some(chunk) = output.body.next().await {
+                let chunk = chunk.map_err(|e| Error::string(&e.to_string()))?;
+                upload.write(&chunk).await?;
+            }
+            upload
+                .finish(&ctx, upload_params.quality, Some((&quota, &usage)))
+                .await
+        }
+        Err(e) => Err(e),
+    };
+
+    // the staged file is done with either way
+    if let Err(e) = client.delete_object(&tmp.file_name).await {
+        tracing::warn!("Failed to delete presigned upload {}: {}", tmp.file_name, e);
+    }
+    tmp.into_active_model().delete(&ctx.db).await?;
+
+    let (mut r, args) = result?;
+    r.is_public = params.is_public;
+    r.user_id = Some(user.pid);
+    r.expires_at = expires_at;
+    r.max_views = max_views;
+
+    save_within_quota(&ctx, &r, &quota).await?;
+    enqueue_thumbnail(&ctx, args).await;
+    format::json(UploadResponse {
+        url: r.is_public.then_some(r.url),
+        delete_token: None,
+        expires_at: r.expires_at,
+    })
 }
 
 async fn confirm_with_token() -> Result<Response> {
-    todo!()
+    Err(not_implemented())
+}
+
+/// Answer of the presign and confirm routes that have no implementation yet.
+fn not_implemented() -> Error {
+    Error::CustomError(
+        StatusCode::NOT_IMPLEMENTED,
+        ErrorDetail::new(
+            "Not Implemented".to_string(),
+            "Not available yet, use the JWT route".to_string(),
+        ),
+    )
 }
 
 pub fn routes() -> Routes {
@@ -318,6 +886,10 @@ pub fn routes() -> Routes {
         .add("/upload", post(upload))
         .add("/upload/jwt", post(upload_with_jwt))
         .add("/upload/token", post(upload_with_token))
+        .add("/upload/url/jwt", post(upload_url_with_jwt))
+        .add("/upload/base64", post(upload_base64))
+        .add("/upload/base64/jwt", post(upload_base64_with_jwt))
+        .add("/upload/base64/token", post(upload_base64_with_token))
         .add("/presign", post(presign))
         .add("/presign/jwt", post(presign_with_jwt))
         .add("/presign/token", post(presign_with_token))
@@ -6,7 +6,7 @@ use axum::{
     },
     response::Redirect,
 };
-use loco_rs::prelude::*;
+use loco_rs::{controller::ErrorDetail, prelude::*};
 use serde::Deserialize;
 use std::fmt::Write;
 use tokio_util::io::ReaderStream;
@@ -15,20 +15,61 @@ use crate::{
     common::{
         client::{Position, S3Client, get_garage, get_r2},
         settings::SettingsService,
+        stats,
     },
     models::{
         _entities::images::{self, Location},
+        images::{Cursor, ListFilter, ListSort, TagFilter},
+        tags,
         users::users,
     },
     views::view::{Image, ListViewResponse},
 };
 
 const MAX_PAGE_SIZE: u64 = 20;
+const NO_STORE: &str = "private, no-store";
+const PRIVATE_CACHE: &str = "private, no-cache";
 
 #[derive(Deserialize)]
 pub struct ListViewParams {
-    pub page: u64,
+    /// Zero based page for offset paging, can't be combined with `cursor`.
+    pub page: Option<u64>,
     pub limit: u64,
+    /// Token from a previous response's `nextCursor`.
+    pub cursor: Option<String>,
+    /// Also count the matching images when paging by cursor.
+    #[serde(default)]
+    pub with_total: bool,
+    /// Comma separated tag names.
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub tag_mode: TagMode,
+    /// Search in the file name.
+    pub q: Option<String>,
+    /// First day to include, `YYYY-MM-DD`.
+    pub from: Option<String>,
+    /// Last day to include, `YYYY-MM-DD`.
+    pub to: Option<String>,
+    pub visibility: Option<Visibility>,
+    /// File extension of the original, e.g. `png`.
+    pub format: Option<String>,
+    #[serde(default)]
+    pub sort: ListSort,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+#[derive(Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMode {
+    #[default]
+    Any,
+    All,
 }
 
 async fn view(
@@ -40,10 +81,34 @@ async fn view(
         return Err(Error::NotFound);
     }
 
-    images::Model::find_by_filename(&ctx.db, &name, Some(Location::Local)).await?;
+    let image = match images::Model::find_by_filename(&ctx.db, &name, Some(Location::Local)).await {
+        Ok(image) => image,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    if image.is_expired() || image.is_broken() {
+        return Err(Error::NotFound);
+    }
+    if image.is_taken_down() {
+        return Ok(taken_down());
+    }
     let s3_client = get_garage();
 
-    fetch_file(headers, s3_client, &name, Position::Avif).await
+    let response = fetch_file(
+        headers.clone(),
+        &s3_client,
+        &name,
+        Position::Avif,
+        cache_control(&image),
+    )
+    .await?;
+    // only a view that is served counts against the limit
+    if !image.record_view(&ctx.db).await? {
+        return Err(Error::NotFound);
+    }
+    record_stats(image.id, &headers, &response);
+
+    Ok(response)
 }
 
 async fn preview(
@@ -60,17 +125,35 @@ async fn preview(
 
     let uuid = &name[..name.len() - 5];
 
-    if let Err(e) =
-        images::Model::find_by_uuid_and_pid(&ctx.db, user.pid, uuid, Some(Location::Local)).await
-    {
-        tracing::error!("Failed to find image by UUID and PID: {}", e);
-        return Err(Error::NotFound);
-    }
+    let image =
+        match images::Model::find_by_uuid_and_pid(&ctx.db, user.pid, uuid, Some(Location::Local))
+            .await
+        {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::error!("Failed to find image by UUID and PID: {}", e);
+                return Err(Error::NotFound);
+            }
+        };
 
     let s3_client = get_garage();
-    fetch_file(headers, s3_client, &name, Position::Preview).await
+    // only ever served to the owner
+    let response = fetch_file(
+        headers.clone(),
+        &s3_client,
+        &name,
+        Position::Preview,
+        PRIVATE_CACHE,
+    )
+    .await?;
+    record_stats(image.id, &headers, &response);
+
+    Ok(response)
 }
 
+/// Lists the user's images. Paging is by cursor unless `page` is given, so
+/// leaving both out returns the first cursor page: `pages` is left out and
+/// `total` only counted when `with_total` is set.
 async fn list(
     auth: auth::JWT,
     State(ctx): State<AppContext>,
@@ -79,9 +162,111 @@ async fn list(
     let user_pid = auth.claims.pid;
     let user = users::Model::find_by_pid(&ctx.db, &user_pid).await?;
 
-    let page_size = params.limit.min(MAX_PAGE_SIZE);
-    let (images, num_items_and_pages) =
-        images::Model::find_by_user_pid(&ctx.db, user.pid, params.page, page_size, None).await?;
+    let tag_names: Vec<String> = params
+        .tags
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let tag_filter = if tag_names.is_empty() {
+        None
+    } else {
+        let found = tags::Model::find_by_names(&ctx.db, user.pid, &tag_names).await?;
+        // an unknown tag can't match, in `all` mode that empties the listing
+        let missing = found.len() < tag_names.len();
+        let tag_ids = if missing && params.tag_mode == TagMode::All {
+            Vec::new()
+        } else {
+            found.into_iter().map(|t| t.id).collect()
+        };
+        Some(TagFilter {
+            tag_ids,
+            match_all: params.tag_mode == TagMode::All,
+        })
+    };
+
+    let format = params
+        .format
+        .as_deref()
+        .map(|f| f.trim().trim_start_matches('.').to_lowercase())
+        .filter(|f| !f.is_empty());
+    if format
+        .as_deref()
+        .is_some_and(|f| !f.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return Err(Error::BadRequest("Invalid format".to_string()));
+    }
+
+    let list_filter = ListFilter {
+        location: None,
+        tags: tag_filter,
+        name: params
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(str::to_owned),
+        created_from: params.from.as_deref().map(parse_day).transpose()?,
+        created_to: params
+            .to
+            .as_deref()
+            .map(parse_day)
+            .transpose()?
+            .map(|day| day + chrono::Duration::days(1)),
+        public: params.visibility.map(|v| matches!(v, Visibility::Public)),
+        format,
+        sort: params.sort,
+    };
+
+    if params.page.is_some() && params.cursor.is_some() {
+        return Err(Error::BadRequest(
+            "Use either page or cursor, not both".to_string(),
+        ));
+    }
+    let page_size = params.limit.clamp(1, MAX_PAGE_SIZE);
+    let (images, total, pages, next_cursor) = match (params.page, params.cursor.as_deref()) {
+        (Some(page), None) => {
+            let (images, num_items_and_pages) =
+                images::Model::find_by_user_pid(&ctx.db, user.pid, page, page_size, &list_filter)
+                    .await?;
+            (
+                images,
+                Some(num_items_and_pages.number_of_items),
+                Some(num_items_and_pages.number_of_pages),
+                None,
+            )
+        }
+        (_, cursor) => {
+            if matches!(params.sort, ListSort::NameAsc | ListSort::NameDesc) {
+                return Err(Error::BadRequest(
+                    "Sorting by name requires page".to_string(),
+                ));
+            }
+            let after = cursor
+                .map(|c| {
+                    Cursor::decode(c).ok_or_else(|| Error::BadRequest("Invalid cursor".to_string()))
+                })
+                .transpose()?;
+            let (images, next) = images::Model::find_by_user_pid_after(
+                &ctx.db,
+                user.pid,
+                after.as_ref(),
+                page_size,
+                &list_filter,
+            )
+            .await?;
+            let total = if params.with_total {
+                Some(images::Model::count_by_user_pid(&ctx.db, user.pid, &list_filter).await?)
+            } else {
+                None
+            };
+            (images, total, None, next.map(|c| c.encode()))
+        }
+    };
+    let image_ids: Vec<i32> = images.iter().map(|m| m.id).collect();
+    let mut image_tags = tags::Model::names_by_image_ids(&ctx.db, &image_ids).await?;
 
     let local_base_url = SettingsService::local_base_url().await;
     let base_url = if local_base_url.trim().is_empty() {
@@ -102,7 +287,7 @@ async fn list(
             let url = if m.location == Location::Local {
                 format!("{}/{}.avif", base_url, m.uuid)
             } else {
-                format!("{}/{}.avif", r2_base_url, m.url)
+                format!("{}/{}", r2_base_url, m.file_name)
             };
 
             Image {
@@ -110,6 +295,7 @@ async fn list(
                 original_url: m.url,
                 name: m.raw_name,
                 // size: m.size,
+                tags: image_tags.remove(&m.id).unwrap_or_default(),
                 id: m.id,
             }
         })
@@ -117,8 +303,9 @@ async fn list(
 
     format::json(ListViewResponse {
         images,
-        pages: num_items_and_pages.number_of_pages,
-        total: num_items_and_pages.number_of_items,
+        total,
+        pages,
+        next_cursor,
     })
 }
 
@@ -127,6 +314,7 @@ async fn fetch_file(
     s3_client: &S3Client,
     name: &str,
     position: Position,
+    cache_control: &'static str,
 ) -> Result<Response> {
     let if_none_match = headers.get(IF_NONE_MATCH).and_then(|h| h.to_str().ok());
 
@@ -149,7 +337,11 @@ async fn fetch_file(
             && client_etag == etag
         {
             tracing::debug!("Etag matched, returning NOT_MODIFIED");
-            return Ok(StatusCode::NOT_MODIFIED.into_response());
+            return Ok((
+                StatusCode::NOT_MODIFIED,
+                [(header::CACHE_CONTROL, cache_control)],
+            )
+                .into_response());
         }
         response = response.header(header::ETAG, etag)
     }
@@ -160,7 +352,7 @@ async fn fetch_file(
         response = response.header(header::CONTENT_LENGTH, len)
     }
 
-    response = response.header(header::CACHE_CONTROL, "public, max-age=2592000, immutable");
+    response = response.header(header::CACHE_CONTROL, cache_control);
     let async_read = output.body.into_async_read();
     let reader_stream = ReaderStream::new(async_read);
     let body = Body::from_stream(reader_stream);
@@ -170,22 +362,102 @@ async fn fetch_file(
     Ok(response)
 }
 
-pub async fn r2_view(State(ctx): State<AppContext>, Path(name): Path<String>) -> Result<Response> {
+pub async fn r2_view(
+    State(ctx): State<AppContext>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Response> {
     if !check(&name) {
         return Err(Error::NotFound);
     }
 
-    images::Model::find_by_filename(&ctx.db, &name, Some(Location::R2)).await?;
+    let image = match images::Model::find_by_filename(&ctx.db, &name, Some(Location::R2)).await {
+        Ok(image) => image,
+        Err(ModelError::EntityNotFound) => return Err(Error::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    if image.is_expired() || image.is_broken() {
+        return Err(Error::NotFound);
+    }
+    if image.is_taken_down() {
+        return Ok(taken_down());
+    }
     let client = get_r2();
 
     let signed_url = client.sign_download_url(&name, 60).await.map_err(|e| {
         tracing::error!("Failed to presign url: {}", e);
         Error::InternalServerError
     })?;
+    if !image.record_view(&ctx.db).await? {
+        return Err(Error::NotFound);
+    }
+
+    // the body is served by R2, only the view is counted
+    stats::record(image.id, &headers, 0);
 
     Ok(Redirect::temporary(&signed_url).into_response())
 }
 
+/// Public URL of an image stored in `location`.
+pub async fn image_url(ctx: &AppContext, location: Location, file_name: &str) -> String {
+    let local_base_url = SettingsService::local_base_url().await;
+    let base_url = match (location, local_base_url.trim().is_empty()) {
+        (Location::Local, true) => ctx.config.server.full_url() + "/api/view",
+        (Location::Local, false) => local_base_url,
+        (Location::R2, true) => ctx.config.server.full_url() + "/api/r2/view",
+        (Location::R2, false) => local_base_url + "/r2/view",
+    };
+
+    format!("{}/{}", base_url, file_name)
+}
+
+/// Taken down images are kept for review but no longer served. The answer
+/// isn't to be cached, the image may be restored.
+fn taken_down() -> Response {
+    (
+        StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+        [(header::CACHE_CONTROL, NO_STORE)],
+        Json(ErrorDetail::new(
+            "Unavailable For Legal Reasons".to_string(),
+            "Image was taken down".to_string(),
+        )),
+    )
+        .into_response()
+}
+
+/// How a served image may be cached. Expiring images are not cached at all,
+/// otherwise a cached copy would outlive them.
+fn cache_control(image: &images::Model) -> &'static str {
+    if image.expires_at.is_some() || image.max_views.is_some() {
+        NO_STORE
+    } else {
+        "public, max-age=2592000, immutable"
+    }
+}
+
+/// Counts a served image, with the body size taken from the response.
+fn record_stats(image_id: i32, headers: &HeaderMap, response: &Response) {
+    let bytes = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(0);
+
+    stats::record(image_id, headers, bytes);
+}
+
+/// Start of the given `YYYY-MM-DD` day in UTC.
+fn parse_day(day: &str) -> Result<DateTimeWithTimeZone> {
+    let date = chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .map_err(|_| Error::BadRequest(format!("Invalid date: {}", day)))?;
+
+    Ok(date
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+        .fixed_offset())
+}
+
 fn check(name: &str) -> bool {
     if name.len() < 39 && name.len() > 42 {
         return false;
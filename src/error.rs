@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header::RETRY_AFTER},
     response::{IntoResponse, Response},
 };
 use thiserror::Error;
@@ -15,6 +15,10 @@ pub enum AppError {
     WrongCredentials,
     #[error("邮箱没有通过验证")]
     EmailNotVerified,
+    #[error("账号已被禁用")]
+    AccountDisabled,
+    #[error("账号正在注销")]
+    AccountDeleting,
     #[error("token已过期")]
     TokenOutdated,
     #[error("无效的token")]
@@ -23,6 +27,8 @@ pub enum AppError {
     InvalidRequest,
     #[error("参数校验失败: {0}")]
     Validation(String),
+    #[error("请求过于频繁，请{0}秒后重试")]
+    TooManyRequests(u64),
     #[error(transparent)]
     LocoError(#[from] loco_rs::Error),
     #[error(transparent)]
@@ -41,6 +47,11 @@ impl IntoResponse for AppError {
                 StatusCode::UNAUTHORIZED,
                 "此用户邮箱没有通过验证，请检查收件箱并点击链接激活账号".to_string(),
             ),
+            AppError::AccountDisabled => (StatusCode::FORBIDDEN, "账号已被禁用".to_string()),
+            AppError::AccountDeleting => (
+                StatusCode::FORBIDDEN,
+                "账号正在注销，请通过邮件中的链接撤销".to_string(),
+            ),
             AppError::TokenOutdated => (
                 StatusCode::BAD_REQUEST,
                 "验证链接已过期，请重新发送".to_string(),
@@ -50,6 +61,10 @@ impl IntoResponse for AppError {
             AppError::Validation(msg) => {
                 (StatusCode::BAD_REQUEST, format!("参数校验失败: {}", msg))
             }
+            AppError::TooManyRequests(secs) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("请求过于频繁，请{}秒后重试", secs),
+            ),
             AppError::LocoError(e) => {
                 tracing::error!("{}", e);
                 (
@@ -71,6 +86,10 @@ impl IntoResponse for AppError {
             "description": message
         }));
 
+        if let AppError::TooManyRequests(secs) = self {
+            return (status, [(RETRY_AFTER, secs.to_string())], body).into_response();
+        }
+
         (status, body).into_response()
     }
 }
@@ -8,6 +8,7 @@ use crate::models::users;
 
 static welcome: Dir<'_> = include_dir!("src/mailers/auth/welcome");
 static forgot: Dir<'_> = include_dir!("src/mailers/auth/forgot");
+static deletion: Dir<'_> = include_dir!("src/mailers/auth/deletion");
 
 #[allow(clippy::module_name_repetitions)]
 pub struct AuthMailer {}
@@ -60,4 +61,29 @@ impl AuthMailer {
 
         Ok(())
     }
+
+    /// Sending the confirmation of a scheduled account deletion, with the link
+    /// to undo it
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_deletion_scheduled(ctx: &AppContext, user: &users::Model) -> Result<()> {
+        Self::mail_template(
+            ctx,
+            &deletion,
+            mailer::Args {
+                to: user.email.to_string(),
+                locals: json!({
+                  "username": user.username,
+                  "deletionToken": user.deletion_token,
+                  "deletesAt": user.deletes_at.map(|at| at.to_rfc3339())
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,11 @@
+;<html>
+
+<body>
+  Hey {{username}},
+  Your account and all of your images will be deleted at {{deletesAt}}.
+  Changed your mind? Keep your account by clicking the link below:
+  <a href="http://localhost:5173/auth/restore?token={{deletionToken}}">Keep Your Account</a>
+  <p>Best regards,<br>The Loco Team</p>
+</body>
+
+</html>
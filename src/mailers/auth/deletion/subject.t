@@ -0,0 +1 @@
+Your account will be deleted
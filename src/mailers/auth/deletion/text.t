@@ -0,0 +1,5 @@
+Hey {{username}},
+  Your account and all of your images will be deleted at {{deletesAt}}.
+  Changed your mind? Keep your account with the link below:
+
+  http://localhost:5173/auth/restore?token={{deletionToken}}
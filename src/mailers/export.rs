@@ -0,0 +1,44 @@
+// export mailer
+#![allow(non_upper_case_globals)]
+
+use loco_rs::prelude::*;
+use serde_json::json;
+
+use crate::models::{exports, users};
+
+static ready: Dir<'_> = include_dir!("src/mailers/export/ready");
+
+#[allow(clippy::module_name_repetitions)]
+pub struct ExportMailer {}
+impl Mailer for ExportMailer {}
+impl ExportMailer {
+    /// Sending the download link of a finished export
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_ready(
+        ctx: &AppContext,
+        user: &users::Model,
+        export: &exports::Model,
+        url: &str,
+    ) -> Result<()> {
+        Self::mail_template(
+            ctx,
+            &ready,
+            mailer::Args {
+                to: user.email.to_string(),
+                locals: json!({
+                  "username": user.username,
+                  "images": export.images,
+                  "url": url,
+                  "expiresAt": export.expires_at.map(|at| at.to_rfc3339())
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+;<html>
+
+<body>
+  Hey {{username}},
+  Your export of {{images}} images is ready. Download it with the link below:
+  <a href="{{url}}">Download Your Export</a>
+  <p>The link expires at {{expiresAt}}.</p>
+  <p>Best regards,<br>The Loco Team</p>
+</body>
+
+</html>
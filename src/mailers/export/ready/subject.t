@@ -0,0 +1 @@
+Your export is ready
@@ -0,0 +1,6 @@
+Hey {{username}},
+  Your export of {{images}} images is ready. Download it with the link below:
+
+  {{url}}
+
+  The link expires at {{expiresAt}}.
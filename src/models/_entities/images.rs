@@ -22,6 +22,21 @@ pub struct Model {
     #[sea_orm(unique)]
     pub uuid: Uuid,
     pub location: Location,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub alt_text: Option<String>,
+    pub size: i64,
+    pub delete_token_hash: Option<String>,
+    pub expires_at: Option<DateTimeWithTimeZone>,
+    pub max_views: Option<i32>,
+    pub views: i32,
+    pub taken_down_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub takedown_reason: Option<String>,
+    pub broken_at: Option<DateTimeWithTimeZone>,
+    pub tier_failed_at: Option<DateTimeWithTimeZone>,
+    pub original_ext: String,
 }
 
 #[derive(Clone, Copy, Debug, EnumIter, PartialEq, Eq, DeriveActiveEnum, Serialize, Deserialize)]
@@ -35,4 +50,37 @@ pub enum Location {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::abuse_reports::Entity")]
+    AbuseReports,
+    #[sea_orm(has_many = "super::album_images::Entity")]
+    AlbumImages,
+    #[sea_orm(has_many = "super::image_stats::Entity")]
+    ImageStats,
+    #[sea_orm(has_many = "super::image_tags::Entity")]
+    ImageTags,
+}
+
+impl Related<super::abuse_reports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AbuseReports.def()
+    }
+}
+
+impl Related<super::album_images::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AlbumImages.def()
+    }
+}
+
+impl Related<super::image_stats::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ImageStats.def()
+    }
+}
+
+impl Related<super::image_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ImageTags.def()
+    }
+}
@@ -2,7 +2,18 @@
 
 pub mod prelude;
 
+pub mod abuse_reports;
+pub mod album_images;
+pub mod albums;
+pub mod exports;
+pub mod image_stats;
+pub mod image_tags;
+pub mod imports;
 pub mod images;
+pub mod pending_removals;
+pub mod regenerations;
 pub mod settings;
+pub mod tags;
 pub mod tmps;
+pub mod tus_uploads;
 pub mod users;
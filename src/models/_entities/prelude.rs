@@ -1,6 +1,17 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::abuse_reports::Entity as AbuseReports;
+pub use super::album_images::Entity as AlbumImages;
+pub use super::albums::Entity as Albums;
+pub use super::exports::Entity as Exports;
+pub use super::image_stats::Entity as ImageStats;
+pub use super::image_tags::Entity as ImageTags;
 pub use super::images::Entity as Images;
+pub use super::imports::Entity as Imports;
+pub use super::pending_removals::Entity as PendingRemovals;
+pub use super::regenerations::Entity as Regenerations;
 pub use super::settings::Entity as Settings;
+pub use super::tags::Entity as Tags;
 pub use super::tmps::Entity as Tmps;
+pub use super::tus_uploads::Entity as TusUploads;
 pub use super::users::Entity as Users;
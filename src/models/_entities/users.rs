@@ -24,6 +24,11 @@ pub struct Model {
     pub email_verification_token: Option<String>,
     pub email_verification_sent_at: Option<DateTimeWithTimeZone>,
     pub email_verified_at: Option<DateTimeWithTimeZone>,
+    pub storage_quota: Option<i64>,
+    pub image_quota: Option<i32>,
+    pub disabled_at: Option<DateTimeWithTimeZone>,
+    pub deletes_at: Option<DateTimeWithTimeZone>,
+    pub deletion_token: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, EnumIter, PartialEq, Eq, DeriveActiveEnum, Serialize, Deserialize)]
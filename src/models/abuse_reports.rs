@@ -0,0 +1,128 @@
+use crate::models::_entities::{
+    abuse_reports::{self, ReportStatus},
+    images,
+};
+
+pub use super::_entities::abuse_reports::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{ItemsAndPagesNumber, QueryOrder, entity::prelude::*};
+use serde::Deserialize;
+pub type AbuseReports = Entity;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReportParams {
+    /// File name of the image as in its view URL.
+    pub name: String,
+    #[validate(length(min = 1, max = 2000, message = "举报原因长度必须在1到2000之间"))]
+    pub reason: String,
+    /// Email or other way to reach the reporter.
+    #[validate(length(min = 1, max = 255, message = "联系方式长度必须在1到255之间"))]
+    pub contact: String,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn create(
+        db: &DatabaseConnection,
+        image_id: i32,
+        params: &ReportParams,
+    ) -> ModelResult<Self> {
+        let report = abuse_reports::ActiveModel {
+            image_id: ActiveValue::Set(image_id),
+            reason: ActiveValue::Set(params.reason.trim().to_string()),
+            contact: ActiveValue::Set(params.contact.trim().to_string()),
+            status: ActiveValue::Set(ReportStatus::Open),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(report)
+    }
+
+    /// Reports with their image, oldest first so the queue is worked in
+    /// order. `page` is zero based.
+    pub async fn find_by_status(
+        db: &DatabaseConnection,
+        status: ReportStatus,
+        page: u64,
+        page_size: u64,
+    ) -> ModelResult<(Vec<(Self, images::Model)>, ItemsAndPagesNumber)> {
+        let query = abuse_reports::Entity::find()
+            .find_also_related(images::Entity)
+            .filter(abuse_reports::Column::Status.eq(status))
+            .order_by_asc(abuse_reports::Column::Id)
+            .paginate(db, page_size);
+        let num_items_and_pages = query.num_items_and_pages().await?;
+        let reports = query
+            .fetch_page(page)
+            .await?
+            .into_iter()
+            // the image is a required foreign key
+            .filter_map(|(report, image)| image.map(|image| (report, image)))
+            .collect();
+
+        Ok((reports, num_items_and_pages))
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let report = abuse_reports::Entity::find_by_id(id).one(db).await?;
+
+        report.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// Closes the image's open reports with `status`.
+    pub async fn resolve_by_image_id<C>(
+        db: &C,
+        image_id: i32,
+        status: ReportStatus,
+    ) -> ModelResult<()>
+    where
+        C: ConnectionTrait,
+    {
+        abuse_reports::Entity::update_many()
+            .col_expr(abuse_reports::Column::Status, status.as_enum())
+            .col_expr(
+                abuse_reports::Column::ResolvedAt,
+                Expr::value(DateTimeWithTimeZone::from(chrono::Utc::now())),
+            )
+            .filter(abuse_reports::Column::ImageId.eq(image_id))
+            .filter(abuse_reports::Column::Status.eq(ReportStatus::Open))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn resolve(
+        mut self,
+        db: &DatabaseConnection,
+        status: ReportStatus,
+    ) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(status);
+        self.resolved_at = ActiveValue::Set(Some(chrono::Utc::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
@@ -0,0 +1,284 @@
+use crate::models::_entities::{album_images, albums, images};
+
+pub use super::_entities::albums::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{QueryOrder, QuerySelect, TransactionTrait, entity::prelude::*};
+use serde::Deserialize;
+pub type Albums = Entity;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateParams {
+    #[validate(length(min = 1, max = 255, message = "相册名称长度必须在1到255之间"))]
+    pub name: String,
+    #[validate(length(max = 2000, message = "描述不能超过2000个字符"))]
+    pub description: Option<String>,
+    pub public: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateParams {
+    #[validate(length(min = 1, max = 255, message = "相册名称长度必须在1到255之间"))]
+    pub name: Option<String>,
+    #[validate(length(max = 2000, message = "描述不能超过2000个字符"))]
+    pub description: Option<String>,
+    pub public: Option<bool>,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if insert {
+            let mut this = self;
+            this.pid = ActiveValue::Set(Uuid::new_v4());
+            Ok(this)
+        } else if self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn find_by_user_pid(db: &DatabaseConnection, user_pid: Uuid) -> ModelResult<Vec<Self>> {
+        let albums = albums::Entity::find()
+            .filter(
+                model::query::condition()
+                    .eq(albums::Column::UserPid, user_pid)
+                    .build(),
+            )
+            .order_by_desc(albums::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(albums)
+    }
+
+    pub async fn find_by_pid_and_user_pid(
+        db: &DatabaseConnection,
+        pid: &str,
+        user_pid: Uuid,
+    ) -> ModelResult<Self> {
+        let parse_uuid = Uuid::parse_str(pid).map_err(|e| ModelError::Any(e.into()))?;
+        let album = albums::Entity::find()
+            .filter(
+                model::query::condition()
+                    .eq(albums::Column::Pid, parse_uuid)
+                    .eq(albums::Column::UserPid, user_pid)
+                    .build(),
+            )
+            .one(db)
+            .await?;
+
+        album.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    pub async fn find_public_by_pid(db: &DatabaseConnection, pid: &str) -> ModelResult<Self> {
+        let parse_uuid = Uuid::parse_str(pid).map_err(|e| ModelError::Any(e.into()))?;
+        let album = albums::Entity::find()
+            .filter(
+                model::query::condition()
+                    .eq(albums::Column::Pid, parse_uuid)
+                    .eq(albums::Column::Public, true)
+                    .build(),
+            )
+            .one(db)
+            .await?;
+
+        album.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// Images of the album in their stored order. With `public_only` set,
+    /// private images are skipped.
+    pub async fn images(
+        &self,
+        db: &DatabaseConnection,
+        public_only: bool,
+    ) -> ModelResult<Vec<images::Model>> {
+        let mut filter = model::query::condition().eq(album_images::Column::AlbumId, self.id);
+        if public_only {
+            filter = filter.eq(images::Column::Public, true);
+        }
+
+        let images = images::Entity::find()
+            .inner_join(album_images::Entity)
+            .filter(filter.build())
+            .order_by_asc(album_images::Column::Position)
+            .order_by_asc(images::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(images)
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+        params: &CreateParams,
+    ) -> ModelResult<Self> {
+        let album = albums::ActiveModel {
+            user_pid: ActiveValue::Set(user_pid),
+            name: ActiveValue::Set(params.name.trim().to_string()),
+            description: ActiveValue::Set(params.description.clone()),
+            public: ActiveValue::Set(params.public.unwrap_or(false)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(album)
+    }
+
+    /// Appends the given images to the end of the album, skipping those
+    /// already in it. Returns the ids that were added.
+    pub async fn add_images<C>(&self, db: &C, image_ids: &[i32]) -> ModelResult<Vec<i32>>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let txn = db.begin().await?;
+
+        let existing: Vec<i32> = album_images::Entity::find()
+            .select_only()
+            .column(album_images::Column::ImageId)
+            .filter(album_images::Column::AlbumId.eq(self.id))
+            .into_tuple()
+            .all(&txn)
+            .await?;
+        let mut position: i32 = album_images::Entity::find()
+            .select_only()
+            .column_as(album_images::Column::Position.max(), "position")
+            .filter(album_images::Column::AlbumId.eq(self.id))
+            .into_tuple::<Option<i32>>()
+            .one(&txn)
+            .await?
+            .flatten()
+            .unwrap_or(-1);
+
+        let mut added = Vec::new();
+        for &image_id in image_ids {
+            if existing.contains(&image_id) || added.contains(&image_id) {
+                continue;
+            }
+            position += 1;
+            album_images::ActiveModel {
+                album_id: ActiveValue::Set(self.id),
+                image_id: ActiveValue::Set(image_id),
+                position: ActiveValue::Set(position),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            added.push(image_id);
+        }
+
+        txn.commit().await?;
+
+        Ok(added)
+    }
+
+    pub async fn remove_images(&self, db: &DatabaseConnection, image_ids: &[i32]) -> ModelResult<()> {
+        let txn = db.begin().await?;
+
+        album_images::Entity::delete_many()
+            .filter(album_images::Column::AlbumId.eq(self.id))
+            .filter(album_images::Column::ImageId.is_in(image_ids.iter().copied()))
+            .exec(&txn)
+            .await?;
+
+        if let Some(cover) = self.cover_image_id
+            && image_ids.contains(&cover)
+        {
+            albums::ActiveModel {
+                id: ActiveValue::Unchanged(self.id),
+                cover_image_id: ActiveValue::Set(None),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Reorders the album so that `image_ids` come first in the given order,
+    /// followed by any images that were not listed.
+    pub async fn reorder(&self, db: &DatabaseConnection, image_ids: &[i32]) -> ModelResult<()> {
+        let txn = db.begin().await?;
+
+        let current = album_images::Entity::find()
+            .filter(album_images::Column::AlbumId.eq(self.id))
+            .order_by_asc(album_images::Column::Position)
+            .all(&txn)
+            .await?;
+
+        let (mut listed, rest): (Vec<_>, Vec<_>) = current
+            .into_iter()
+            .partition(|item| image_ids.contains(&item.image_id));
+        listed.sort_by_key(|item| image_ids.iter().position(|id| *id == item.image_id));
+
+        for (position, item) in listed.into_iter().chain(rest).enumerate() {
+            let position = i32::try_from(position).map_err(|e| ModelError::Any(e.into()))?;
+            if item.position == position {
+                continue;
+            }
+            let mut item = item.into_active_model();
+            item.position = ActiveValue::Set(position);
+            item.update(&txn).await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn contains_image(&self, db: &DatabaseConnection, image_id: i32) -> ModelResult<bool> {
+        let item = album_images::Entity::find_by_id((self.id, image_id))
+            .one(db)
+            .await?;
+
+        Ok(item.is_some())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn update_with(
+        mut self,
+        db: &DatabaseConnection,
+        params: &UpdateParams,
+    ) -> ModelResult<Model> {
+        if let Some(name) = &params.name {
+            self.name = ActiveValue::Set(name.trim().to_string());
+        }
+        if let Some(description) = &params.description {
+            let description = description.trim();
+            self.description =
+                ActiveValue::Set((!description.is_empty()).then(|| description.to_string()));
+        }
+        if let Some(public) = params.public {
+            self.public = ActiveValue::Set(public);
+        }
+
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    pub async fn set_cover(
+        mut self,
+        db: &DatabaseConnection,
+        image_id: Option<i32>,
+    ) -> ModelResult<Model> {
+        self.cover_image_id = ActiveValue::Set(image_id);
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
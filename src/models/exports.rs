@@ -0,0 +1,204 @@
+use crate::models::_entities::{
+    exports::{self, ExportStatus, ExportVariant},
+    images::Location,
+};
+
+pub use super::_entities::exports::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{QueryOrder, QuerySelect, entity::prelude::*};
+use serde::Deserialize;
+pub type Exports = Entity;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportParams {
+    /// Which file of each image goes into the archive.
+    #[serde(default)]
+    pub variant: ExportVariant,
+}
+
+/// Where a finished archive is stored.
+#[derive(Debug)]
+pub struct StoredExport {
+    pub location: Location,
+    pub file_key: String,
+    pub size: i64,
+    pub images: i32,
+    pub missing: i32,
+    pub expires_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn create(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+        params: &ExportParams,
+    ) -> ModelResult<Self> {
+        let export = exports::ActiveModel {
+            user_pid: ActiveValue::Set(user_pid),
+            status: ActiveValue::Set(ExportStatus::Queued),
+            variant: ActiveValue::Set(params.variant),
+            images: ActiveValue::Set(0),
+            missing: ActiveValue::Set(0),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(export)
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let export = exports::Entity::find_by_id(id).one(db).await?;
+
+        export.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// The user's most recent export.
+    pub async fn find_latest_by_user_pid(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+    ) -> ModelResult<Self> {
+        let export = exports::Entity::find()
+            .filter(exports::Column::UserPid.eq(user_pid))
+            .order_by_desc(exports::Column::Id)
+            .one(db)
+            .await?;
+
+        export.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    pub async fn find_by_user_pid(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+    ) -> ModelResult<Vec<Self>> {
+        let exports = exports::Entity::find()
+            .filter(exports::Column::UserPid.eq(user_pid))
+            .all(db)
+            .await?;
+
+        Ok(exports)
+    }
+
+    /// The user's exports still queued or running.
+    pub async fn find_pending_by_user_pid(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+    ) -> ModelResult<Vec<Self>> {
+        let exports = exports::Entity::find()
+            .filter(exports::Column::UserPid.eq(user_pid))
+            .filter(exports::Column::Status.is_in([ExportStatus::Queued, ExportStatus::Running]))
+            .all(db)
+            .await?;
+
+        Ok(exports)
+    }
+
+    /// Whether a pending export went without progress since `before`, its job
+    /// most likely lost to a restart.
+    #[must_use]
+    pub fn is_stale(&self, before: DateTimeWithTimeZone) -> bool {
+        matches!(self.status, ExportStatus::Queued | ExportStatus::Running)
+            && self.updated_at < before
+    }
+
+    /// Ready exports whose link ran out, their archives left to delete.
+    pub async fn find_expired(db: &DatabaseConnection, limit: u64) -> ModelResult<Vec<Self>> {
+        let exports = exports::Entity::find()
+            .filter(exports::Column::Status.eq(ExportStatus::Ready))
+            .filter(exports::Column::ExpiresAt.lte(chrono::Utc::now()))
+            .order_by_asc(exports::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(exports)
+    }
+
+    /// Location and key of every stored archive.
+    pub async fn find_file_keys(db: &DatabaseConnection) -> ModelResult<Vec<(Location, String)>> {
+        let keys: Vec<(Option<Location>, Option<String>)> = exports::Entity::find()
+            .select_only()
+            .column(exports::Column::Location)
+            .column(exports::Column::FileKey)
+            .filter(exports::Column::Status.eq(ExportStatus::Ready))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(keys
+            .into_iter()
+            .filter_map(|(location, key)| location.zip(key))
+            .collect())
+    }
+
+    /// The stored archive, while its link is still valid.
+    #[must_use]
+    pub fn stored(&self) -> Option<(Location, &str)> {
+        if self.status != ExportStatus::Ready
+            || self.expires_at.is_none_or(|at| at <= chrono::Utc::now())
+        {
+            return None;
+        }
+
+        self.location.zip(self.file_key.as_deref())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn start(mut self, db: &DatabaseConnection) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ExportStatus::Running);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    pub async fn finish(
+        mut self,
+        db: &DatabaseConnection,
+        stored: StoredExport,
+    ) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ExportStatus::Ready);
+        self.location = ActiveValue::Set(Some(stored.location));
+        self.file_key = ActiveValue::Set(Some(stored.file_key));
+        self.size = ActiveValue::Set(Some(stored.size));
+        self.images = ActiveValue::Set(stored.images);
+        self.missing = ActiveValue::Set(stored.missing);
+        self.expires_at = ActiveValue::Set(Some(stored.expires_at));
+        self.finished_at = ActiveValue::Set(Some(chrono::Utc::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    pub async fn fail(mut self, db: &DatabaseConnection, error: String) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ExportStatus::Failed);
+        self.error = ActiveValue::Set(Some(error));
+        self.finished_at = ActiveValue::Set(Some(chrono::Utc::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Marks the archive deleted after its link ran out.
+    pub async fn expire(mut self, db: &DatabaseConnection) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ExportStatus::Expired);
+        self.file_key = ActiveValue::Set(None);
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
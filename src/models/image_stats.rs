@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::_entities::{image_stats, images};
+
+pub use super::_entities::image_stats::{ActiveModel, Entity, Model};
+use loco_rs::model::ModelResult;
+use sea_orm::{
+    ActiveValue, QueryOrder, QuerySelect, TransactionTrait,
+    entity::prelude::*,
+    sea_query::{Alias, Expr, Func, OnConflict, Query, SimpleExpr},
+};
+pub type ImageStats = Entity;
+
+/// Rows per statement, well within the bind limits of every backend.
+const BATCH_ROWS: usize = 1000;
+
+/// Image, day and referring domain the counts are kept by.
+pub type StatsKey = (i32, Date, String);
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counts {
+    pub views: i64,
+    pub bytes: i64,
+}
+
+/// Images the stats are summed over.
+#[derive(Debug, Clone, Copy)]
+pub enum StatsScope {
+    Image(i32),
+    User(Uuid),
+}
+
+impl StatsScope {
+    fn condition(self) -> SimpleExpr {
+        match self {
+            Self::Image(id) => image_stats::Column::ImageId.eq(id),
+            Self::User(pid) => image_stats::Column::ImageId.in_subquery(
+                Query::select()
+                    .column(images::Column::Id)
+                    .from(images::Entity)
+                    .and_where(images::Column::UserPid.eq(pid))
+                    .to_owned(),
+            ),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// `SUM` of a bigint column, cast back from numeric on Postgres.
+fn sum(col: image_stats::Column) -> SimpleExpr {
+    Expr::expr(Func::coalesce([Expr::col(col).sum(), Expr::val(0).into()]))
+        .cast_as(Alias::new("bigint"))
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Adds the collected counts to the stored ones. Counts of images deleted
+    /// in the meantime are dropped. Either all counts are added or none.
+    pub async fn add_batch(
+        db: &DatabaseConnection,
+        batch: &HashMap<StatsKey, Counts>,
+    ) -> ModelResult<()> {
+        let ids: Vec<i32> = batch
+            .keys()
+            .map(|(id, _, _)| *id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let mut existing = HashSet::new();
+        for chunk in ids.chunks(BATCH_ROWS) {
+            let found: Vec<i32> = images::Entity::find()
+                .select_only()
+                .column(images::Column::Id)
+                .filter(images::Column::Id.is_in(chunk.iter().copied()))
+                .into_tuple()
+                .all(db)
+                .await?;
+            existing.extend(found);
+        }
+
+        let rows: Vec<ActiveModel> = batch
+            .iter()
+            .filter(|((id, _, _), _)| existing.contains(id))
+            .map(|((image_id, day, referrer), counts)| ActiveModel {
+                image_id: ActiveValue::Set(*image_id),
+                day: ActiveValue::Set(*day),
+                referrer: ActiveValue::Set(referrer.clone()),
+                views: ActiveValue::Set(counts.views),
+                bytes: ActiveValue::Set(counts.bytes),
+                ..Default::default()
+            })
+            .collect();
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let excluded = |col| Expr::col((Alias::new("excluded"), col));
+        let txn = db.begin().await?;
+        for chunk in rows.chunks(BATCH_ROWS) {
+            image_stats::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    OnConflict::columns([
+                        image_stats::Column::ImageId,
+                        image_stats::Column::Day,
+                        image_stats::Column::Referrer,
+                    ])
+                    .value(
+                        image_stats::Column::Views,
+                        Expr::col((image_stats::Entity, image_stats::Column::Views))
+                            .add(excluded(image_stats::Column::Views)),
+                    )
+                    .value(
+                        image_stats::Column::Bytes,
+                        Expr::col((image_stats::Entity, image_stats::Column::Bytes))
+                            .add(excluded(image_stats::Column::Bytes)),
+                    )
+                    .to_owned(),
+                )
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Views and bytes per day between `from` and `to`, both included.
+    pub async fn daily(
+        db: &DatabaseConnection,
+        scope: StatsScope,
+        from: Date,
+        to: Date,
+    ) -> ModelResult<Vec<(Date, i64, i64)>> {
+        let days = image_stats::Entity::find()
+            .select_only()
+            .column(image_stats::Column::Day)
+            .column_as(sum(image_stats::Column::Views), "views")
+            .column_as(sum(image_stats::Column::Bytes), "bytes")
+            .filter(scope.condition())
+            .filter(image_stats::Column::Day.between(from, to))
+            .group_by(image_stats::Column::Day)
+            .order_by_asc(image_stats::Column::Day)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(days)
+    }
+
+    /// Referring domains with the most views between `from` and `to`.
+    pub async fn top_referrers(
+        db: &DatabaseConnection,
+        scope: StatsScope,
+        from: Date,
+        to: Date,
+        limit: u64,
+    ) -> ModelResult<Vec<(String, i64)>> {
+        let views = sum(image_stats::Column::Views);
+        let referrers = image_stats::Entity::find()
+            .select_only()
+            .column(image_stats::Column::Referrer)
+            .column_as(views.clone(), "views")
+            .filter(scope.condition())
+            .filter(image_stats::Column::Day.between(from, to))
+            .filter(image_stats::Column::Referrer.ne(""))
+            .group_by(image_stats::Column::Referrer)
+            .order_by_desc(views)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(referrers)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
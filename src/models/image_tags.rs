@@ -0,0 +1,70 @@
+use crate::models::_entities::image_tags;
+
+pub use super::_entities::image_tags::{ActiveModel, Entity, Model};
+use loco_rs::model::ModelResult;
+use sea_orm::{ActiveValue, QuerySelect, entity::prelude::*};
+pub type ImageTags = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Links the tags to the image, skipping those already linked.
+    pub async fn attach<C>(db: &C, image_id: i32, tag_ids: &[i32]) -> ModelResult<()>
+    where
+        C: ConnectionTrait,
+    {
+        let existing: Vec<i32> = image_tags::Entity::find()
+            .select_only()
+            .column(image_tags::Column::TagId)
+            .filter(image_tags::Column::ImageId.eq(image_id))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        for &tag_id in tag_ids.iter().filter(|id| !existing.contains(id)) {
+            image_tags::ActiveModel {
+                image_id: ActiveValue::Set(image_id),
+                tag_id: ActiveValue::Set(tag_id),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn detach<C>(db: &C, image_id: i32, tag_ids: &[i32]) -> ModelResult<()>
+    where
+        C: ConnectionTrait,
+    {
+        image_tags::Entity::delete_many()
+            .filter(image_tags::Column::ImageId.eq(image_id))
+            .filter(image_tags::Column::TagId.is_in(tag_ids.iter().copied()))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
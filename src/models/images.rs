@@ -1,13 +1,203 @@
 use crate::{
     controllers::upload::UploadResult,
-    models::_entities::images::{self, Location},
+    models::{
+        _entities::{
+            image_stats, image_tags,
+            images::{self, Location},
+        },
+        users::users::{self, UserRole},
+    },
 };
 
 pub use super::_entities::images::{ActiveModel, Entity, Model};
-use loco_rs::{model::ModelResult, prelude::*};
-use sea_orm::{ItemsAndPagesNumber, QueryOrder, TransactionTrait, entity::prelude::*};
+use loco_rs::{hash, model::ModelResult, prelude::*};
+use sea_orm::{
+    DatabaseTransaction, DbBackend, ItemsAndPagesNumber, Order, QueryOrder, QuerySelect,
+    TransactionTrait,
+    entity::prelude::*,
+    sea_query::{Alias, BinOper, Expr, Func, Query, SimpleExpr},
+};
+use serde::Deserialize;
 pub type Images = Entity;
 
+/// Filters and sort order of a user's image listing.
+#[derive(Debug, Default)]
+pub struct ListFilter {
+    pub location: Option<Location>,
+    pub tags: Option<TagFilter>,
+    /// Case-insensitive substring of the file name.
+    pub name: Option<String>,
+    pub created_from: Option<DateTimeWithTimeZone>,
+    /// Exclusive upper bound.
+    pub created_to: Option<DateTimeWithTimeZone>,
+    pub public: Option<bool>,
+    /// Lowercase extension of the uploaded file, e.g. `png`.
+    pub format: Option<String>,
+    pub sort: ListSort,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    #[default]
+    Newest,
+    Oldest,
+    NameAsc,
+    NameDesc,
+}
+
+/// Restricts a listing to images carrying any or all of the given tags.
+#[derive(Debug)]
+pub struct TagFilter {
+    pub tag_ids: Vec<i32>,
+    pub match_all: bool,
+}
+
+impl TagFilter {
+    fn condition(&self) -> SimpleExpr {
+        let mut tagged = Query::select();
+        tagged
+            .column(image_tags::Column::ImageId)
+            .from(image_tags::Entity)
+            .and_where(image_tags::Column::TagId.is_in(self.tag_ids.iter().copied()));
+
+        if self.match_all {
+            let tag_count = i64::try_from(self.tag_ids.len()).unwrap_or(i64::MAX);
+            tagged.group_by_col(image_tags::Column::ImageId).and_having(
+                Expr::col(image_tags::Column::TagId)
+                    .count_distinct()
+                    .eq(tag_count),
+            );
+        }
+
+        images::Column::Id.in_subquery(tagged.to_owned())
+    }
+}
+
+/// Position in a date ordered listing, handed to clients as an opaque token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTimeWithTimeZone,
+    pub id: i32,
+}
+
+impl Cursor {
+    #[must_use]
+    pub fn new(image: &Model) -> Self {
+        Self {
+            created_at: image.created_at,
+            id: image.id,
+        }
+    }
+
+    #[must_use]
+    pub fn encode(&self) -> String {
+        format!("{:016x}{:08x}", self.created_at.timestamp_micros(), self.id)
+    }
+
+    #[must_use]
+    pub fn decode(token: &str) -> Option<Self> {
+        if token.len() != 24 || !token.is_ascii() {
+            return None;
+        }
+        let micros = u64::from_str_radix(&token[..16], 16).ok()? as i64;
+        let id = u32::from_str_radix(&token[16..], 16).ok()? as i32;
+        let created_at = chrono::DateTime::from_timestamp_micros(micros)?.fixed_offset();
+
+        Some(Self { created_at, id })
+    }
+
+    /// Rows strictly past the cursor in the listing order.
+    fn after(&self, backend: DbBackend, descending: bool) -> SimpleExpr {
+        let op = if descending {
+            BinOper::SmallerThan
+        } else {
+            BinOper::GreaterThan
+        };
+
+        created_at_cmp(backend, op, self.created_at).or(created_at_cmp(
+            backend,
+            BinOper::Equal,
+            self.created_at,
+        )
+        .and(Expr::col((images::Entity, images::Column::Id)).binary(op, self.id)))
+    }
+}
+
+/// `created_at` as compared and ordered. On SQLite the column default is
+/// stored as `YYYY-MM-DD HH:MM:SS` text, which doesn't order against RFC 3339
+/// values, so it goes through `datetime()`.
+fn created_at_expr(backend: DbBackend) -> SimpleExpr {
+    let col = Expr::col((images::Entity, images::Column::CreatedAt));
+    match backend {
+        DbBackend::Sqlite => Func::cust(Alias::new("datetime")).arg(col).into(),
+        _ => col.into(),
+    }
+}
+
+/// Compares `created_at` against `at`, see [`created_at_expr`].
+fn created_at_cmp(backend: DbBackend, op: BinOper, at: DateTimeWithTimeZone) -> SimpleExpr {
+    let expr = Expr::expr(created_at_expr(backend));
+    match backend {
+        DbBackend::Sqlite => expr.binary(
+            op,
+            at.with_timezone(&chrono::Utc)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        ),
+        _ => expr.binary(op, at),
+    }
+}
+
+/// Images a bulk job works on, each set field narrowing it down.
+#[derive(Debug, Default, Clone)]
+pub struct ImageScope {
+    pub user_pid: Option<Uuid>,
+    pub location: Option<Location>,
+    pub created_from: Option<DateTimeWithTimeZone>,
+    /// Exclusive upper bound.
+    pub created_to: Option<DateTimeWithTimeZone>,
+}
+
+impl ImageScope {
+    fn select(&self, backend: DbBackend) -> Select<Entity> {
+        let mut select = images::Entity::find();
+        if let Some(pid) = self.user_pid {
+            select = select.filter(images::Column::UserPid.eq(pid));
+        }
+        if let Some(location) = self.location {
+            select = select.filter(images::Column::Location.eq(location));
+        }
+        if let Some(from) = self.created_from {
+            select = select.filter(created_at_cmp(backend, BinOper::GreaterThanOrEqual, from));
+        }
+        if let Some(to) = self.created_to {
+            select = select.filter(created_at_cmp(backend, BinOper::SmallerThan, to));
+        }
+
+        select
+    }
+}
+
+/// Storage used by a user, originals and derivatives together.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Usage {
+    pub bytes: i64,
+    pub images: i64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataParams {
+    #[validate(length(min = 1, max = 255, message = "名称长度必须在1到255之间"))]
+    pub name: Option<String>,
+    #[validate(length(max = 2000, message = "描述不能超过2000个字符"))]
+    pub description: Option<String>,
+    #[validate(length(max = 1000, message = "替代文本不能超过1000个字符"))]
+    pub alt_text: Option<String>,
+    pub public: Option<bool>,
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
@@ -26,29 +216,215 @@ impl ActiveModelBehavior for ActiveModel {
 
 // implement your read-oriented logic here
 impl Model {
+    /// Offset paginated listing, `page` is zero based. Pages past the end
+    /// are empty.
     pub async fn find_by_user_pid(
         db: &DatabaseConnection,
         pid: Uuid,
         page: u64,
         page_size: u64,
-        location: Option<Location>,
+        list_filter: &ListFilter,
     ) -> ModelResult<(Vec<Self>, ItemsAndPagesNumber)> {
+        let raw_name = || Expr::expr(Func::lower(Expr::col(images::Column::RawName)));
+        let select = Self::list_select(db.get_database_backend(), pid, list_filter);
+        let select = match list_filter.sort {
+            ListSort::Newest => select.order_by_desc(images::Column::Id),
+            ListSort::Oldest => select.order_by_asc(images::Column::Id),
+            ListSort::NameAsc => select
+                .order_by(raw_name(), Order::Asc)
+                .order_by_desc(images::Column::Id),
+            ListSort::NameDesc => select
+                .order_by(raw_name(), Order::Desc)
+                .order_by_desc(images::Column::Id),
+        };
+
+        let query = select.paginate(db, page_size);
+        let num_items_and_pages = query.num_items_and_pages().await?;
+        let images = query.fetch_page(page).await?;
+
+        Ok((images, num_items_and_pages))
+    }
+
+    /// Keyset paginated listing ordered by `(created_at, id)`, oldest first
+    /// for [`ListSort::Oldest`] and newest first otherwise. Returns the images
+    /// following `after` and the cursor of the next page, if there is one.
+    pub async fn find_by_user_pid_after(
+        db: &DatabaseConnection,
+        pid: Uuid,
+        after: Option<&Cursor>,
+        limit: u64,
+        list_filter: &ListFilter,
+    ) -> ModelResult<(Vec<Self>, Option<Cursor>)> {
+        let backend = db.get_database_backend();
+        let descending = !matches!(list_filter.sort, ListSort::Oldest);
+        let order = if descending { Order::Desc } else { Order::Asc };
+
+        let mut select = Self::list_select(backend, pid, list_filter);
+        if let Some(cursor) = after {
+            select = select.filter(cursor.after(backend, descending));
+        }
+
+        // one extra row tells whether another page follows
+        let mut images = select
+            .order_by(created_at_expr(backend), order.clone())
+            .order_by(images::Column::Id, order)
+            .limit(limit + 1)
+            .all(db)
+            .await?;
+
+        let next = if images.len() as u64 > limit {
+            images.truncate(images.len() - 1);
+            images.last().map(Cursor::new)
+        } else {
+            None
+        };
+
+        Ok((images, next))
+    }
+
+    pub async fn count_by_user_pid(
+        db: &DatabaseConnection,
+        pid: Uuid,
+        list_filter: &ListFilter,
+    ) -> ModelResult<u64> {
+        let count = Self::list_select(db.get_database_backend(), pid, list_filter)
+            .count(db)
+            .await?;
+
+        Ok(count)
+    }
+
+    fn list_select(backend: DbBackend, pid: Uuid, list_filter: &ListFilter) -> Select<Entity> {
         let mut filter = model::query::condition();
-        if let Some(loc) = location {
+        if let Some(loc) = list_filter.location {
             filter = filter.eq(images::Column::Location, loc);
         }
+        if let Some(public) = list_filter.public {
+            filter = filter.eq(images::Column::Public, public);
+        }
         filter = filter.eq(images::Column::UserPid, Some(pid));
 
-        let query = images::Entity::find()
-            .filter(filter.build())
-            .order_by_desc(images::Column::Id)
-            .paginate(db, page_size);
-        let num_items_and_pages = query.num_items_and_pages().await?;
-        let page = page.min(num_items_and_pages.number_of_pages);
+        let raw_name = || Expr::expr(Func::lower(Expr::col(images::Column::RawName)));
+        let mut select = images::Entity::find().filter(filter.build());
+        if let Some(name) = &list_filter.name {
+            select = select.filter(raw_name().like(format!("%{}%", name.to_lowercase())));
+        }
+        if let Some(format) = &list_filter.format {
+            select = select.filter(raw_name().like(format!("%.{}", format)));
+        }
+        if let Some(from) = list_filter.created_from {
+            select = select.filter(created_at_cmp(backend, BinOper::GreaterThanOrEqual, from));
+        }
+        if let Some(to) = list_filter.created_to {
+            select = select.filter(created_at_cmp(backend, BinOper::SmallerThan, to));
+        }
+        if let Some(tags) = &list_filter.tags {
+            select = select.filter(tags.condition());
+        }
 
-        let images = query.fetch_page(page).await?;
+        select
+    }
 
-        Ok((images, num_items_and_pages))
+    pub async fn usage_by_user_pid<C>(db: &C, pid: Uuid) -> ModelResult<Usage>
+    where
+        C: ConnectionTrait,
+    {
+        // SUM over a bigint is numeric on Postgres, bring it back to bigint
+        let bytes = Expr::expr(Func::coalesce([
+            Expr::col(images::Column::Size).sum(),
+            Expr::val(0).into(),
+        ]))
+        .cast_as(Alias::new("bigint"));
+
+        let (bytes, images): (i64, i64) = images::Entity::find()
+            .select_only()
+            .column_as(bytes, "bytes")
+            .column_as(images::Column::Id.count(), "images")
+            .filter(images::Column::UserPid.eq(pid))
+            .into_tuple()
+            .one(db)
+            .await?
+            .unwrap_or_default();
+
+        Ok(Usage { bytes, images })
+    }
+
+    /// Like [`Self::usage_by_user_pid`], with the user row locked until `txn`
+    /// ends so uploads of the same user are counted one after another.
+    pub async fn usage_by_user_pid_locked(
+        txn: &DatabaseTransaction,
+        pid: Uuid,
+    ) -> ModelResult<Usage> {
+        users::Entity::find()
+            .filter(users::Column::Pid.eq(pid))
+            .lock_exclusive()
+            .one(txn)
+            .await?;
+
+        Self::usage_by_user_pid(txn, pid).await
+    }
+
+    /// Storage used by all images, per location.
+    pub async fn usage_by_location(db: &DatabaseConnection) -> ModelResult<Vec<(Location, Usage)>> {
+        let bytes = Expr::expr(Func::coalesce([
+            Expr::col(images::Column::Size).sum(),
+            Expr::val(0).into(),
+        ]))
+        .cast_as(Alias::new("bigint"));
+
+        let rows: Vec<(Location, i64, i64)> = images::Entity::find()
+            .select_only()
+            .column(images::Column::Location)
+            .column_as(bytes, "bytes")
+            .column_as(images::Column::Id.count(), "images")
+            .group_by(images::Column::Location)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(location, bytes, images)| (location, Usage { bytes, images }))
+            .collect())
+    }
+
+    /// Number of images uploaded per day since `from`, days without uploads
+    /// are left out.
+    pub async fn uploads_per_day(
+        db: &DatabaseConnection,
+        from: DateTimeWithTimeZone,
+    ) -> ModelResult<Vec<(Date, i64)>> {
+        let backend = db.get_database_backend();
+        let day: SimpleExpr = Func::cust(Alias::new("date"))
+            .arg(created_at_expr(backend))
+            .into();
+
+        let days = images::Entity::find()
+            .select_only()
+            .column_as(day.clone(), "day")
+            .column_as(images::Column::Id.count(), "uploads")
+            .filter(created_at_cmp(backend, BinOper::GreaterThanOrEqual, from))
+            .group_by(day.clone())
+            .order_by_asc(day)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(days)
+    }
+
+    /// Adds the size of stored derivatives to the image.
+    pub async fn add_size(db: &DatabaseConnection, file_name: &str, bytes: i64) -> ModelResult<()> {
+        images::Entity::update_many()
+            .col_expr(
+                images::Column::Size,
+                Expr::col(images::Column::Size).add(bytes),
+            )
+            .filter(images::Column::FileName.eq(file_name))
+            .exec(db)
+            .await?;
+
+        Ok(())
     }
 
     pub async fn find_by_uuid_and_pid(
@@ -96,10 +472,301 @@ impl Model {
         images.ok_or_else(|| ModelError::EntityNotFound)
     }
 
-    pub async fn save_local_with_result(
+    /// A new deletion token for an anonymous upload, `{uuid}.{secret}`. Only
+    /// a hash of the secret is stored.
+    #[must_use]
+    pub fn new_delete_token(uuid: Uuid) -> String {
+        format!("{}.{}", uuid.simple(), Uuid::new_v4().simple())
+    }
+
+    pub async fn find_by_delete_token(db: &DatabaseConnection, token: &str) -> ModelResult<Self> {
+        let (uuid, secret) = token.split_once('.').ok_or(ModelError::EntityNotFound)?;
+        let uuid = Uuid::parse_str(uuid).map_err(|_| ModelError::EntityNotFound)?;
+
+        let image = images::Entity::find()
+            .filter(
+                model::query::condition()
+                    .eq(images::Column::Uuid, uuid)
+                    .is_null(images::Column::UserPid)
+                    .build(),
+            )
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        match &image.delete_token_hash {
+            Some(hash) if hash::verify_password(secret, hash) => Ok(image),
+            _ => Err(ModelError::EntityNotFound),
+        }
+    }
+
+    /// Whether the image is past its expiry time or out of views.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= chrono::Utc::now())
+            || self.max_views.is_some_and(|max| self.views >= max)
+    }
+
+    #[must_use]
+    pub fn is_taken_down(&self) -> bool {
+        self.taken_down_at.is_some()
+    }
+
+    /// Whether the storage check found the image's objects missing.
+    #[must_use]
+    pub fn is_broken(&self) -> bool {
+        self.broken_at.is_some()
+    }
+
+    /// Counts a view of an image limited by `max_views`. Returns false when
+    /// the image has no views left.
+    pub async fn record_view(&self, db: &DatabaseConnection) -> ModelResult<bool> {
+        if self.max_views.is_none() {
+            return Ok(true);
+        }
+
+        // checked in the update so concurrent views can't go over the limit
+        let res = images::Entity::update_many()
+            .col_expr(
+                images::Column::Views,
+                Expr::col(images::Column::Views).add(1),
+            )
+            .filter(images::Column::Id.eq(self.id))
+            .filter(Expr::col(images::Column::Views).lt(Expr::col(images::Column::MaxViews)))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Images past their expiry time or out of views, oldest first.
+    pub async fn find_expired(db: &DatabaseConnection, limit: u64) -> ModelResult<Vec<Self>> {
+        let now: DateTimeWithTimeZone = chrono::Utc::now().into();
+        let images =
+            images::Entity::find()
+                .filter(images::Column::ExpiresAt.lte(now).or(
+                    images::Column::MaxViews.is_not_null().and(
+                        Expr::col(images::Column::Views).gte(Expr::col(images::Column::MaxViews)),
+                    ),
+                ))
+                .order_by_asc(images::Column::Id)
+                .limit(limit)
+                .all(db)
+                .await?;
+
+        Ok(images)
+    }
+
+    /// Ids of images not stored in `location`, oldest first, optionally only
+    /// those of a user or uploaded before a time.
+    pub async fn find_ids_not_in(
         db: &DatabaseConnection,
+        location: Location,
+        user_pid: Option<Uuid>,
+        created_before: Option<DateTimeWithTimeZone>,
+        limit: Option<u64>,
+    ) -> ModelResult<Vec<i32>> {
+        let backend = db.get_database_backend();
+        let mut select = images::Entity::find()
+            .select_only()
+            .column(images::Column::Id)
+            .filter(images::Column::Location.ne(location));
+        if let Some(pid) = user_pid {
+            select = select.filter(images::Column::UserPid.eq(pid));
+        }
+        if let Some(before) = created_before {
+            select = select.filter(created_at_cmp(backend, BinOper::SmallerThan, before));
+        }
+
+        let ids = select
+            .order_by_asc(images::Column::Id)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// Ids of Garage images uploaded before `created_before` with fewer than
+    /// `max_views` views since `since`, oldest first. Images with an expiry
+    /// or view limit are left to the purger, broken ones and those that
+    /// failed to move after `failed_before` are skipped.
+    pub async fn find_cold_ids(
+        db: &DatabaseConnection,
+        created_before: DateTimeWithTimeZone,
+        since: Date,
+        max_views: u64,
+        failed_before: DateTimeWithTimeZone,
+        limit: u64,
+    ) -> ModelResult<Vec<i32>> {
+        let backend = db.get_database_backend();
+        let max_views = i64::try_from(max_views).unwrap_or(i64::MAX);
+        let hot = Query::select()
+            .column(image_stats::Column::ImageId)
+            .from(image_stats::Entity)
+            .and_where(image_stats::Column::Day.gte(since))
+            .group_by_col(image_stats::Column::ImageId)
+            .and_having(Expr::expr(Expr::col(image_stats::Column::Views).sum()).gte(max_views))
+            .to_owned();
+
+        let ids = images::Entity::find()
+            .select_only()
+            .column(images::Column::Id)
+            .filter(images::Column::Location.eq(Location::Local))
+            .filter(images::Column::ExpiresAt.is_null())
+            .filter(images::Column::MaxViews.is_null())
+            .filter(images::Column::BrokenAt.is_null())
+            .filter(
+                images::Column::TierFailedAt
+                    .is_null()
+                    .or(images::Column::TierFailedAt.lt(failed_before)),
+            )
+            .filter(created_at_cmp(
+                backend,
+                BinOper::SmallerThan,
+                created_before,
+            ))
+            .filter(images::Column::Id.not_in_subquery(hot))
+            .order_by_asc(images::Column::Id)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// Up to `limit` images with an id above `after_id`, to go through the
+    /// whole table in batches.
+    pub async fn find_batch_after_id(
+        db: &DatabaseConnection,
+        after_id: i32,
+        limit: u64,
+    ) -> ModelResult<Vec<Self>> {
+        let images = images::Entity::find()
+            .filter(images::Column::Id.gt(after_id))
+            .order_by_asc(images::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(images)
+    }
+
+    pub async fn count_in_scope(db: &DatabaseConnection, scope: &ImageScope) -> ModelResult<u64> {
+        let count = scope.select(db.get_database_backend()).count(db).await?;
+
+        Ok(count)
+    }
+
+    /// Up to `limit` images of the scope with an id above `after_id`.
+    pub async fn find_batch_in_scope(
+        db: &DatabaseConnection,
+        scope: &ImageScope,
+        after_id: i32,
+        limit: u64,
+    ) -> ModelResult<Vec<Self>> {
+        let images = scope
+            .select(db.get_database_backend())
+            .filter(images::Column::Id.gt(after_id))
+            .order_by_asc(images::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(images)
+    }
+
+    /// Sets or clears `broken_at` on the given images.
+    pub async fn set_broken(db: &DatabaseConnection, ids: &[i32], broken: bool) -> ModelResult<()> {
+        let broken_at: Option<DateTimeWithTimeZone> = broken.then(|| chrono::Utc::now().into());
+        images::Entity::update_many()
+            .col_expr(images::Column::BrokenAt, Expr::value(broken_at))
+            .filter(images::Column::Id.is_in(ids.iter().copied()))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records a failed move to R2, so tiering leaves the image for a while.
+    pub async fn set_tier_failed(db: &DatabaseConnection, id: i32) -> ModelResult<()> {
+        let failed_at: DateTimeWithTimeZone = chrono::Utc::now().into();
+        images::Entity::update_many()
+            .col_expr(images::Column::TierFailedAt, Expr::value(failed_at))
+            .filter(images::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The user's first `limit` images, to go through all of them in batches
+    /// as they get deleted.
+    pub async fn find_batch_by_user_pid(
+        db: &DatabaseConnection,
+        pid: Uuid,
+        limit: u64,
+    ) -> ModelResult<Vec<Self>> {
+        let images = images::Entity::find()
+            .filter(images::Column::UserPid.eq(pid))
+            .order_by_asc(images::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(images)
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let image = images::Entity::find_by_id(id).one(db).await?;
+
+        image.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    pub async fn find_by_ids<C>(db: &C, ids: &[i32]) -> ModelResult<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let images = images::Entity::find()
+            .filter(images::Column::Id.is_in(ids.iter().copied()))
+            .all(db)
+            .await?;
+
+        Ok(images)
+    }
+
+    /// Whether the given user may modify or remove this image, either as its
+    /// owner or as an admin.
+    #[must_use]
+    pub fn can_be_managed_by(&self, user: &users::Model) -> bool {
+        user.role == UserRole::Admin || self.user_pid == Some(user.pid)
+    }
+
+    /// Key of the uploaded original in the origin bucket. It carries the
+    /// extension of the file the client sent, kept apart from `raw_name` so a
+    /// rename doesn't move it.
+    #[must_use]
+    pub fn original_key(&self) -> String {
+        format!("{}.{}", self.uuid, self.original_ext)
+    }
+
+    pub async fn save_local_with_result<C>(
+        db: &C,
         upload_result: &UploadResult,
-    ) -> ModelResult<Self> {
+    ) -> ModelResult<Self>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let delete_token_hash = upload_result
+            .delete_token
+            .as_deref()
+            .and_then(|token| token.split_once('.'))
+            .map(|(_, secret)| hash::hash_password(secret))
+            .transpose()
+            .map_err(|e| ModelError::Any(e.into()))?;
+
         let txn = db.begin().await?;
 
         if images::Entity::find()
@@ -122,7 +789,15 @@ impl Model {
             url: Set(upload_result.url.clone()),
             uuid: Set(upload_result.uuid),
             raw_name: Set(upload_result.raw_name.clone()),
-            // size: Set(upload_result.size.clone()),
+            original_ext: Set(std::path::Path::new(&upload_result.raw_name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default()
+                .to_string()),
+            size: Set(upload_result.size),
+            delete_token_hash: Set(delete_token_hash),
+            expires_at: Set(upload_result.expires_at),
+            max_views: Set(upload_result.max_views),
             location: Set(images::Location::Local),
             ..Default::default()
         }
@@ -136,7 +811,85 @@ impl Model {
 }
 
 // implement your write-oriented logic here
-impl ActiveModel {}
+impl ActiveModel {
+    pub async fn set_public<C>(mut self, db: &C, public: bool) -> ModelResult<Model>
+    where
+        C: ConnectionTrait,
+    {
+        self.public = Set(public);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Points the image at its copy in `location`, served from `url`.
+    pub async fn set_location<C>(
+        mut self,
+        db: &C,
+        location: Location,
+        url: String,
+    ) -> ModelResult<Model>
+    where
+        C: ConnectionTrait,
+    {
+        self.location = Set(location);
+        self.url = Set(url);
+        self.tier_failed_at = Set(None);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Takes the image down, or restores it with `None`. The stored objects
+    /// are kept either way.
+    pub async fn set_taken_down<C>(mut self, db: &C, reason: Option<String>) -> ModelResult<Model>
+    where
+        C: ConnectionTrait,
+    {
+        self.taken_down_at = Set(reason.is_some().then(|| chrono::Utc::now().into()));
+        self.takedown_reason = Set(reason);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Applies the fields present in `params`. An empty description or alt
+    /// text clears it.
+    ///
+    /// The original object is keyed by the extension of `raw_name`, so a
+    /// rename keeps the stored extension.
+    pub async fn update_metadata(
+        mut self,
+        db: &DatabaseConnection,
+        params: &MetadataParams,
+    ) -> ModelResult<Model> {
+        if let Some(name) = params.name.as_deref().and_then(non_empty) {
+            let ext_of = |n: &str| {
+                std::path::Path::new(n)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(str::to_owned)
+            };
+            let raw_name = match ext_of(self.raw_name.as_ref()) {
+                Some(ext) if ext_of(&name).as_deref() != Some(ext.as_str()) => {
+                    format!("{}.{}", name, ext)
+                }
+                _ => name,
+            };
+            self.raw_name = Set(raw_name);
+        }
+        if let Some(description) = &params.description {
+            self.description = Set(non_empty(description));
+        }
+        if let Some(alt_text) = &params.alt_text {
+            self.alt_text = Set(non_empty(alt_text));
+        }
+        if let Some(public) = params.public {
+            self.public = Set(public);
+        }
+
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
 
 // implement your custom finders, selectors oriented logic here
 impl Entity {}
@@ -0,0 +1,145 @@
+use crate::models::_entities::imports::{self, ImportStatus, ImportStructure};
+
+pub use super::_entities::imports::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::entity::prelude::*;
+use serde::Deserialize;
+pub type Imports = Entity;
+
+#[derive(Debug, Deserialize)]
+pub struct ImportParams {
+    pub public: Option<bool>,
+    pub quality: u8,
+    /// How folders in the archive are kept.
+    #[serde(default)]
+    pub structure: ImportStructure,
+}
+
+/// Counts of a running import, see [`ActiveModel::progress`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportProgress {
+    pub imported: i32,
+    pub skipped: i32,
+    pub failed: i32,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn create(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+        file_name: &str,
+        structure: ImportStructure,
+    ) -> ModelResult<Self> {
+        let import = imports::ActiveModel {
+            user_pid: ActiveValue::Set(user_pid),
+            status: ActiveValue::Set(ImportStatus::Queued),
+            structure: ActiveValue::Set(structure),
+            file_name: ActiveValue::Set(file_name.to_string()),
+            total: ActiveValue::Set(0),
+            imported: ActiveValue::Set(0),
+            skipped: ActiveValue::Set(0),
+            failed: ActiveValue::Set(0),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(import)
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let import = imports::Entity::find_by_id(id).one(db).await?;
+
+        import.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    pub async fn find_by_id_and_user_pid(
+        db: &DatabaseConnection,
+        id: i32,
+        user_pid: Uuid,
+    ) -> ModelResult<Self> {
+        let import = imports::Entity::find_by_id(id)
+            .filter(imports::Column::UserPid.eq(user_pid))
+            .one(db)
+            .await?;
+
+        import.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// The user's imports still queued or running.
+    pub async fn find_pending_by_user_pid(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+    ) -> ModelResult<Vec<Self>> {
+        let imports = imports::Entity::find()
+            .filter(imports::Column::UserPid.eq(user_pid))
+            .filter(imports::Column::Status.is_in([ImportStatus::Queued, ImportStatus::Running]))
+            .all(db)
+            .await?;
+
+        Ok(imports)
+    }
+
+    /// Whether a pending import went without progress since `before`, its job
+    /// most likely lost to a restart.
+    #[must_use]
+    pub fn is_stale(&self, before: DateTimeWithTimeZone) -> bool {
+        matches!(self.status, ImportStatus::Queued | ImportStatus::Running)
+            && self.updated_at < before
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    /// Marks the import running with the number of images found.
+    pub async fn start(mut self, db: &DatabaseConnection, total: i32) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ImportStatus::Running);
+        self.total = ActiveValue::Set(total);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    pub async fn progress(
+        mut self,
+        db: &DatabaseConnection,
+        progress: ImportProgress,
+    ) -> ModelResult<Model> {
+        self.imported = ActiveValue::Set(progress.imported);
+        self.skipped = ActiveValue::Set(progress.skipped);
+        self.failed = ActiveValue::Set(progress.failed);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    pub async fn finish(mut self, db: &DatabaseConnection) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ImportStatus::Done);
+        self.finished_at = ActiveValue::Set(Some(chrono::Utc::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Stops the import, keeping the images imported so far.
+    pub async fn fail(mut self, db: &DatabaseConnection, error: String) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(ImportStatus::Failed);
+        self.error = ActiveValue::Set(Some(error));
+        self.finished_at = ActiveValue::Set(Some(chrono::Utc::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
@@ -3,3 +3,14 @@ pub mod users;
 pub mod settings;
 pub mod images;
 pub mod tmps;
+pub mod tus_uploads;
+pub mod albums;
+pub mod album_images;
+pub mod tags;
+pub mod image_tags;
+pub mod image_stats;
+pub mod abuse_reports;
+pub mod regenerations;
+pub mod exports;
+pub mod imports;
+pub mod pending_removals;
@@ -0,0 +1,92 @@
+use crate::models::_entities::{images, pending_removals};
+
+pub use super::_entities::pending_removals::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{QueryOrder, QuerySelect, entity::prelude::*};
+pub type PendingRemovals = Entity;
+
+/// Longest wait between two attempts at removing the same objects.
+const MAX_RETRY_SECS: i64 = 24 * 3600;
+const RETRY_BASE_SECS: i64 = 30;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Records the stored objects of an image as to be removed. Meant to run
+    /// in the transaction deleting the image, so that the objects are never
+    /// left without a row pointing at them.
+    pub async fn create<C>(db: &C, image: &images::Model) -> ModelResult<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let removal = pending_removals::ActiveModel {
+            location: ActiveValue::Set(image.location),
+            file_name: ActiveValue::Set(image.file_name.clone()),
+            original_key: ActiveValue::Set(image.original_key()),
+            attempts: ActiveValue::Set(0),
+            retry_at: ActiveValue::Set(chrono::Utc::now().into()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(removal)
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let removal = pending_removals::Entity::find_by_id(id).one(db).await?;
+
+        removal.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// Removals whose next attempt is due, oldest first.
+    pub async fn find_due(db: &DatabaseConnection, limit: u64) -> ModelResult<Vec<Self>> {
+        let removals = pending_removals::Entity::find()
+            .filter(pending_removals::Column::RetryAt.lte(chrono::Utc::now()))
+            .order_by_asc(pending_removals::Column::RetryAt)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(removals)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    /// Records a failed attempt and puts the next one off, twice as long as
+    /// the last time up to a day.
+    pub async fn retry_later(
+        mut self,
+        db: &DatabaseConnection,
+        error: String,
+    ) -> ModelResult<Model> {
+        let attempts = self.attempts.take().unwrap_or_default().saturating_add(1);
+        let shift = u32::try_from(attempts - 1).unwrap_or_default().min(20);
+        let delay = (RETRY_BASE_SECS << shift).min(MAX_RETRY_SECS);
+
+        self.attempts = ActiveValue::Set(attempts);
+        self.retry_at =
+            ActiveValue::Set((chrono::Utc::now() + chrono::TimeDelta::seconds(delay)).into());
+        self.error = ActiveValue::Set(Some(error));
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
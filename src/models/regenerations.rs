@@ -0,0 +1,138 @@
+use crate::models::{
+    _entities::{
+        images::Location,
+        regenerations::{self, RegenerationStatus},
+    },
+    images::ImageScope,
+};
+
+pub use super::_entities::regenerations::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{QueryOrder, QuerySelect, entity::prelude::*};
+use serde::Deserialize;
+pub type Regenerations = Entity;
+
+/// Which images to encode again and how.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RegenerateParams {
+    pub user_pid: Option<Uuid>,
+    pub location: Option<Location>,
+    pub created_from: Option<DateTimeWithTimeZone>,
+    /// Exclusive upper bound.
+    pub created_to: Option<DateTimeWithTimeZone>,
+    #[validate(range(min = 1, max = 100, message = "质量必须在1到100之间"))]
+    pub quality: u8,
+    /// Pause between two images.
+    #[validate(range(max = 60000, message = "间隔不能超过60000毫秒"))]
+    #[serde(default)]
+    pub delay_ms: u32,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn create(db: &DatabaseConnection, params: &RegenerateParams) -> ModelResult<Self> {
+        let regeneration = regenerations::ActiveModel {
+            status: ActiveValue::Set(RegenerationStatus::Queued),
+            user_pid: ActiveValue::Set(params.user_pid),
+            location: ActiveValue::Set(params.location),
+            created_from: ActiveValue::Set(params.created_from),
+            created_to: ActiveValue::Set(params.created_to),
+            quality: ActiveValue::Set(i32::from(params.quality)),
+            delay_ms: ActiveValue::Set(i32::try_from(params.delay_ms).unwrap_or(i32::MAX)),
+            total: ActiveValue::Set(0),
+            encoded: ActiveValue::Set(0),
+            skipped: ActiveValue::Set(0),
+            failed: ActiveValue::Set(0),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(regeneration)
+    }
+
+    pub async fn find_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let regeneration = regenerations::Entity::find_by_id(id).one(db).await?;
+
+        regeneration.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// The latest runs, newest first.
+    pub async fn find_recent(db: &DatabaseConnection, limit: u64) -> ModelResult<Vec<Self>> {
+        let regenerations = regenerations::Entity::find()
+            .order_by_desc(regenerations::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(regenerations)
+    }
+
+    #[must_use]
+    pub fn scope(&self) -> ImageScope {
+        ImageScope {
+            user_pid: self.user_pid,
+            location: self.location,
+            created_from: self.created_from,
+            created_to: self.created_to,
+        }
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn start(mut self, db: &DatabaseConnection, total: u64) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(RegenerationStatus::Running);
+        self.total = ActiveValue::Set(i32::try_from(total).unwrap_or(i32::MAX));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    pub async fn set_progress(
+        mut self,
+        db: &DatabaseConnection,
+        encoded: i32,
+        skipped: i32,
+        failed: i32,
+    ) -> ModelResult<Model> {
+        self.encoded = ActiveValue::Set(encoded);
+        self.skipped = ActiveValue::Set(skipped);
+        self.failed = ActiveValue::Set(failed);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Ends the run, failed when `error` is given.
+    pub async fn finish(
+        mut self,
+        db: &DatabaseConnection,
+        error: Option<String>,
+    ) -> ModelResult<Model> {
+        self.status = ActiveValue::Set(if error.is_some() {
+            RegenerationStatus::Failed
+        } else {
+            RegenerationStatus::Finished
+        });
+        self.error = ActiveValue::Set(error);
+        self.finished_at = ActiveValue::Set(Some(chrono::Utc::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
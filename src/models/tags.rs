@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::models::_entities::{image_tags, tags};
+
+pub use super::_entities::tags::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{QueryOrder, QuerySelect, entity::prelude::*};
+use serde::Deserialize;
+pub type Tags = Entity;
+
+const MAX_TAG_LEN: usize = 32;
+const MAX_TAGS_PER_REQUEST: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct TagsParams {
+    pub tags: Vec<String>,
+}
+
+impl TagsParams {
+    /// Trimmed, lowercased and deduplicated tag names.
+    ///
+    /// # Errors
+    ///
+    /// When a tag is empty or too long, or too many tags are given
+    pub fn normalized(&self) -> std::result::Result<Vec<String>, String> {
+        if self.tags.len() > MAX_TAGS_PER_REQUEST {
+            return Err(format!("最多一次提交{}个标签", MAX_TAGS_PER_REQUEST));
+        }
+
+        let mut names: Vec<String> = Vec::with_capacity(self.tags.len());
+        for tag in &self.tags {
+            let name = tag.trim().to_lowercase();
+            if name.is_empty() || name.chars().count() > MAX_TAG_LEN {
+                return Err(format!("标签长度必须在1到{}之间", MAX_TAG_LEN));
+            }
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn find_by_names<C>(db: &C, user_pid: Uuid, names: &[String]) -> ModelResult<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let tags = tags::Entity::find()
+            .filter(tags::Column::UserPid.eq(user_pid))
+            .filter(tags::Column::Name.is_in(names.iter().cloned()))
+            .all(db)
+            .await?;
+
+        Ok(tags)
+    }
+
+    /// Returns the user's tags with the given names, creating missing ones.
+    pub async fn find_or_create<C>(db: &C, user_pid: Uuid, names: &[String]) -> ModelResult<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut tags = Self::find_by_names(db, user_pid, names).await?;
+
+        for name in names {
+            if tags.iter().any(|t| &t.name == name) {
+                continue;
+            }
+            let tag = tags::ActiveModel {
+                user_pid: ActiveValue::Set(user_pid),
+                name: ActiveValue::Set(name.clone()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            tags.push(tag);
+        }
+
+        Ok(tags)
+    }
+
+    /// The user's tags with the number of images carrying each of them.
+    pub async fn find_by_user_pid_with_count(
+        db: &DatabaseConnection,
+        user_pid: Uuid,
+    ) -> ModelResult<Vec<(String, i64)>> {
+        let tags = tags::Entity::find()
+            .select_only()
+            .column(tags::Column::Name)
+            .column_as(image_tags::Column::ImageId.count(), "count")
+            .left_join(image_tags::Entity)
+            .filter(tags::Column::UserPid.eq(user_pid))
+            .group_by(tags::Column::Name)
+            .order_by_asc(tags::Column::Name)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(tags)
+    }
+
+    /// Tag names of every given image, keyed by image id.
+    pub async fn names_by_image_ids(
+        db: &DatabaseConnection,
+        image_ids: &[i32],
+    ) -> ModelResult<HashMap<i32, Vec<String>>> {
+        let rows: Vec<(i32, String)> = image_tags::Entity::find()
+            .select_only()
+            .column(image_tags::Column::ImageId)
+            .column(tags::Column::Name)
+            .inner_join(tags::Entity)
+            .filter(image_tags::Column::ImageId.is_in(image_ids.iter().copied()))
+            .order_by_asc(tags::Column::Name)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let mut names: HashMap<i32, Vec<String>> = HashMap::new();
+        for (image_id, name) in rows {
+            names.entry(image_id).or_default().push(name);
+        }
+
+        Ok(names)
+    }
+
+    /// Most used tag names across all users.
+    pub async fn most_used(db: &DatabaseConnection, limit: u64) -> ModelResult<Vec<(String, i64)>> {
+        let count = image_tags::Column::ImageId.count();
+        let tags = tags::Entity::find()
+            .select_only()
+            .column(tags::Column::Name)
+            .column_as(count.clone(), "count")
+            .inner_join(image_tags::Entity)
+            .group_by(tags::Column::Name)
+            .order_by_desc(count)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(tags)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
@@ -5,7 +5,7 @@ use loco_rs::{
     model::{ModelError, ModelResult},
     prelude::model,
 };
-use sea_orm::entity::prelude::*;
+use sea_orm::{QuerySelect, entity::prelude::*};
 pub type Tmps = Entity;
 
 #[async_trait::async_trait]
@@ -46,12 +46,32 @@ impl Model {
         item.ok_or_else(|| ModelError::EntityNotFound)
     }
 
+    /// Keys of presigned uploads not confirmed yet.
+    pub async fn find_file_names(db: &DatabaseConnection) -> ModelResult<Vec<String>> {
+        let file_names = tmps::Entity::find()
+            .select_only()
+            .column(tmps::Column::FileName)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        Ok(file_names)
+    }
+
     pub async fn create_tmp_record(
         db: &DatabaseConnection,
         user_pid: uuid::Uuid,
         tmp_path: &str,
     ) -> ModelResult<Self> {
-        todo!()
+        let tmp = tmps::ActiveModel {
+            user_pid: sea_orm::ActiveValue::Set(user_pid),
+            file_name: sea_orm::ActiveValue::Set(tmp_path.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(tmp)
     }
 }
 
@@ -0,0 +1,113 @@
+use crate::models::_entities::tus_uploads;
+
+pub use super::_entities::tus_uploads::{ActiveModel, Entity, Model};
+use loco_rs::{model::ModelResult, prelude::*};
+use sea_orm::{QueryOrder, QuerySelect, entity::prelude::*};
+pub type TusUploads = Entity;
+
+/// A resumable upload about to be created, with the options of the image it
+/// becomes.
+#[derive(Debug)]
+pub struct NewTusUpload {
+    pub user_pid: Option<Uuid>,
+    pub raw_name: String,
+    pub length: u64,
+    pub quality: u8,
+    pub public: bool,
+    pub expires_in: Option<u64>,
+    pub max_views: Option<i32>,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if insert {
+            let mut this = self;
+            this.uuid = ActiveValue::Set(Uuid::now_v7());
+            // written like later updates rather than left to the database
+            // default, whose format doesn't compare right in `find_stale`
+            this.updated_at = ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else if self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    pub async fn create(db: &DatabaseConnection, upload: NewTusUpload) -> ModelResult<Self> {
+        let upload = tus_uploads::ActiveModel {
+            user_pid: ActiveValue::Set(upload.user_pid),
+            raw_name: ActiveValue::Set(upload.raw_name),
+            upload_length: ActiveValue::Set(i64::try_from(upload.length).unwrap_or(i64::MAX)),
+            upload_offset: ActiveValue::Set(0),
+            quality: ActiveValue::Set(i16::from(upload.quality)),
+            public: ActiveValue::Set(upload.public),
+            expires_in: ActiveValue::Set(
+                upload
+                    .expires_in
+                    .map(|secs| i64::try_from(secs).unwrap_or(i64::MAX)),
+            ),
+            max_views: ActiveValue::Set(upload.max_views),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(upload)
+    }
+
+    pub async fn find_by_uuid(db: &DatabaseConnection, uuid: Uuid) -> ModelResult<Self> {
+        let upload = tus_uploads::Entity::find()
+            .filter(tus_uploads::Column::Uuid.eq(uuid))
+            .one(db)
+            .await?;
+
+        upload.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// Uploads that got no data since `before`, left by their clients.
+    pub async fn find_stale(
+        db: &DatabaseConnection,
+        before: DateTimeWithTimeZone,
+        limit: u64,
+    ) -> ModelResult<Vec<Self>> {
+        let uploads = tus_uploads::Entity::find()
+            .filter(tus_uploads::Column::UpdatedAt.lt(before))
+            .order_by_asc(tus_uploads::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(uploads)
+    }
+
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        u64::try_from(self.upload_offset).unwrap_or(0)
+    }
+
+    #[must_use]
+    pub fn length(&self) -> u64 {
+        u64::try_from(self.upload_length).unwrap_or(0)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn set_offset(mut self, db: &DatabaseConnection, offset: u64) -> ModelResult<Model> {
+        self.upload_offset = ActiveValue::Set(i64::try_from(offset).unwrap_or(i64::MAX));
+        self.update(db).await.map_err(ModelError::from)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {}
@@ -2,6 +2,10 @@ use async_trait::async_trait;
 use chrono::Local;
 use loco_rs::{auth::jwt, hash, prelude::*};
 use regex::Regex;
+use sea_orm::{
+    Condition, ItemsAndPagesNumber, PaginatorTrait, QueryOrder, QuerySelect,
+    sea_query::{Expr, Func},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
 use std::sync::OnceLock;
@@ -70,6 +74,7 @@ impl ActiveModelBehavior for super::_entities::users::ActiveModel {
 
 #[async_trait]
 impl Authenticable for Model {
+    /// Disabled users and those deleting their account are not found.
     async fn find_by_api_key(db: &DatabaseConnection, api_key: &str) -> ModelResult<Self> {
         let user = users::Entity::find()
             .filter(
@@ -77,13 +82,20 @@ impl Authenticable for Model {
                     .eq(users::Column::ApiKey, api_key)
                     .build(),
             )
+            .filter(users::Column::DisabledAt.is_null())
+            .filter(users::Column::DeletesAt.is_null())
             .one(db)
             .await?;
         user.ok_or_else(|| ModelError::EntityNotFound)
     }
 
+    /// Disabled users and those deleting their account are not found.
     async fn find_by_claims_key(db: &DatabaseConnection, claims_key: &str) -> ModelResult<Self> {
-        Self::find_by_pid(db, claims_key).await
+        let user = Self::find_by_pid(db, claims_key).await?;
+        if user.is_disabled() || user.is_deleting() {
+            return Err(ModelError::EntityNotFound);
+        }
+        Ok(user)
     }
 }
 
@@ -142,6 +154,42 @@ impl Model {
         user.ok_or_else(|| ModelError::EntityNotFound)
     }
 
+    /// finds a user by the token of their pending account deletion
+    ///
+    /// # Errors
+    ///
+    /// When could not find user by the given token or DB query error
+    pub async fn find_by_deletion_token(db: &DatabaseConnection, token: &str) -> ModelResult<Self> {
+        let user = users::Entity::find()
+            .filter(
+                model::query::condition()
+                    .eq(users::Column::DeletionToken, token)
+                    .build(),
+            )
+            .one(db)
+            .await?;
+        user.ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// Users whose account deletion is due, at most `limit` of them.
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error
+    pub async fn find_due_for_deletion(
+        db: &DatabaseConnection,
+        limit: u64,
+    ) -> ModelResult<Vec<Self>> {
+        let users = users::Entity::find()
+            .filter(users::Column::DeletesAt.lte(Local::now()))
+            .order_by_asc(users::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(users)
+    }
+
     /// finds a user by the provided pid
     ///
     /// # Errors
@@ -189,6 +237,70 @@ impl Model {
         user.ok_or_else(|| ModelError::EntityNotFound)
     }
 
+    /// Users whose username or email contains `query`, newest first.
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error
+    pub async fn search(
+        db: &DatabaseConnection,
+        query: Option<&str>,
+        page: u64,
+        page_size: u64,
+    ) -> ModelResult<(Vec<Self>, ItemsAndPagesNumber)> {
+        let mut select = users::Entity::find();
+        if let Some(query) = query {
+            let pattern = format!("%{}%", query.to_lowercase());
+            select = select.filter(
+                Condition::any()
+                    .add(Expr::expr(Func::lower(Expr::col(users::Column::Username))).like(&pattern))
+                    .add(Expr::expr(Func::lower(Expr::col(users::Column::Email))).like(&pattern)),
+            );
+        }
+
+        let query = select
+            .order_by_desc(users::Column::Id)
+            .paginate(db, page_size);
+        let num_items_and_pages = query.num_items_and_pages().await?;
+        let users = query.fetch_page(page).await?;
+
+        Ok((users, num_items_and_pages))
+    }
+
+    /// Whether the user with the given pid exists and is disabled or deleting
+    /// their account.
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error
+    pub async fn is_locked_pid(db: &DatabaseConnection, pid: &str) -> ModelResult<bool> {
+        let Ok(pid) = Uuid::parse_str(pid) else {
+            return Ok(false);
+        };
+        let count = users::Entity::find()
+            .filter(users::Column::Pid.eq(pid))
+            .filter(
+                Condition::any()
+                    .add(users::Column::DisabledAt.is_not_null())
+                    .add(users::Column::DeletesAt.is_not_null()),
+            )
+            .count(db)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    #[must_use]
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
+    /// Whether the user asked to delete their account and can still undo it.
+    #[must_use]
+    pub fn is_deleting(&self) -> bool {
+        self.deletes_at.is_some()
+    }
+
     /// Verifies whether the provided plain password matches the hashed password
     ///
     /// # Errors
@@ -336,9 +448,94 @@ impl ActiveModel {
         self.update(db).await.map_err(ModelError::from)
     }
 
+    /// Schedules deleting the account after `grace`, with a token to undo it.
+    /// The API key is replaced so the old one stays unusable after an undo.
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn schedule_deletion(
+        mut self,
+        db: &DatabaseConnection,
+        grace: chrono::Duration,
+    ) -> ModelResult<Model> {
+        self.deletes_at = ActiveValue::Set(Some((Local::now() + grace).into()));
+        self.deletion_token = ActiveValue::Set(Some(Uuid::new_v4().to_string()));
+        self.api_key = ActiveValue::Set(format!("ap-{}", Uuid::new_v4()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Puts off a deletion that failed by `delay`, so the accounts due after
+    /// it still get their turn. The token stays valid for an undo.
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn postpone_deletion(
+        mut self,
+        db: &DatabaseConnection,
+        delay: chrono::Duration,
+    ) -> ModelResult<Model> {
+        self.deletes_at = ActiveValue::Set(Some((Local::now() + delay).into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Undoes a scheduled account deletion.
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn cancel_deletion(mut self, db: &DatabaseConnection) -> ModelResult<Model> {
+        self.deletes_at = ActiveValue::Set(None);
+        self.deletion_token = ActiveValue::Set(None);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
     pub async fn reset_api_key(mut self, db: &DatabaseConnection) -> ModelResult<Model> {
         self.api_key = ActiveValue::Set(format!("ap-{}", Uuid::new_v4()));
 
         self.update(db).await.map_err(ModelError::from)
     }
+
+    /// Disables the user, or enables them again.
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn set_disabled(
+        mut self,
+        db: &DatabaseConnection,
+        disabled: bool,
+    ) -> ModelResult<Model> {
+        self.disabled_at = ActiveValue::Set(disabled.then(|| Local::now().into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn set_role(
+        mut self,
+        db: &DatabaseConnection,
+        role: users::UserRole,
+    ) -> ModelResult<Model> {
+        self.role = ActiveValue::Set(role);
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Sets the user's own quotas, `None` falls back to the site default.
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn set_quota(
+        mut self,
+        db: &DatabaseConnection,
+        storage_quota: Option<i64>,
+        image_quota: Option<i32>,
+    ) -> ModelResult<Model> {
+        self.storage_quota = ActiveValue::Set(storage_quota);
+        self.image_quota = ActiveValue::Set(image_quota);
+        self.update(db).await.map_err(ModelError::from)
+    }
 }
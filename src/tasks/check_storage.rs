@@ -0,0 +1,328 @@
+use std::collections::{HashMap, HashSet};
+
+use loco_rs::prelude::*;
+
+use crate::{
+    common::client::{Position, get_garage, get_r2, init_garage, init_r2, r2_original_key},
+    models::{_entities::images::Location, exports, images, tmps},
+    workers::regenerator,
+};
+
+const BATCH_SIZE: u64 = 500;
+/// Rows and objects younger than this may belong to an upload still in
+/// progress and are not reported.
+const GRACE_HOURS: i64 = 1;
+const DEFAULT_QUALITY: u8 = 80;
+
+/// Checks that every image's objects exist and that every stored object
+/// belongs to an image. Reports only, unless fixes are given:
+///
+/// - `requeue` encodes missing derivatives again from the kept original
+/// - `delete_orphans` deletes objects no image refers to
+/// - `mark_broken` sets `broken_at` on images still missing objects
+///
+/// Images encoded again by `requeue` lose their `broken_at`.
+///
+/// ```sh
+/// cargo loco task check_storage
+/// cargo loco task check_storage fix:requeue,mark_broken quality:80
+/// ```
+pub struct CheckStorage;
+#[async_trait]
+impl Task for CheckStorage {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "check_storage".to_string(),
+            detail: "Report missing and orphaned objects in storage".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, vars: &task::Vars) -> Result<()> {
+        let fixes = Fixes::parse(vars.cli_arg("fix").ok())?;
+        let quality = vars
+            .cli_arg("quality")
+            .ok()
+            .map(|quality| quality.parse::<u8>())
+            .transpose()
+            .map_err(|_| Error::string("Argument 'quality' must be 0 to 100"))?
+            .unwrap_or(DEFAULT_QUALITY);
+
+        // initializers only run with the server
+        init_garage(app_context).await;
+        init_r2(app_context).await;
+
+        let grace = chrono::Utc::now() - chrono::Duration::hours(GRACE_HOURS);
+        let mut buckets = list_buckets().await;
+        let mut missing = Vec::new();
+
+        let mut after_id = 0;
+        loop {
+            let batch =
+                images::Model::find_batch_after_id(&app_context.db, after_id, BATCH_SIZE).await?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            after_id = last.id;
+
+            for image in batch {
+                let mut absent = Vec::new();
+                for (store, key, required) in expected_objects(&image) {
+                    let Some(bucket) = buckets.iter_mut().find(|b| b.holds(store)) else {
+                        continue;
+                    };
+                    if required && !bucket.objects.contains_key(&key) {
+                        absent.push(format!("{}/{}", bucket.name, key));
+                    }
+                    bucket.expected.insert(key);
+                }
+
+                if !absent.is_empty() && image.created_at < grace {
+                    missing.push((image, absent));
+                }
+            }
+        }
+
+        let pending: HashSet<String> = tmps::Model::find_file_names(&app_context.db)
+            .await?
+            .into_iter()
+            .collect();
+        for bucket in &mut buckets {
+            if matches!(bucket.store, Store::R2) {
+                bucket.expected.extend(pending.iter().cloned());
+            }
+        }
+        for (location, key) in exports::Model::find_file_keys(&app_context.db).await? {
+            let store = match location {
+                Location::Local => Store::Garage(Position::Original),
+                Location::R2 => Store::R2,
+            };
+            if let Some(bucket) = buckets.iter_mut().find(|b| b.holds(store)) {
+                bucket.expected.insert(key);
+            }
+        }
+
+        for (image, absent) in &missing {
+            println!(
+                "missing: image {} ({}){}: {}",
+                image.id,
+                image.file_name,
+                if image.is_broken() { " broken" } else { "" },
+                absent.join(", ")
+            );
+        }
+        let mut orphans = 0;
+        for bucket in &buckets {
+            for key in bucket.orphans(grace) {
+                println!("orphan: {}/{}", bucket.name, key);
+                orphans += 1;
+            }
+        }
+        println!(
+            "{} images missing objects, {} orphaned objects",
+            missing.len(),
+            orphans
+        );
+
+        if fixes.requeue {
+            let encoded;
+            (missing, encoded) = requeue(app_context, missing, quality).await;
+            images::Model::set_broken(&app_context.db, &encoded, false).await?;
+        }
+        if fixes.delete_orphans {
+            delete_orphans(&buckets, grace).await;
+        }
+        let ids: Vec<i32> = missing
+            .iter()
+            .filter(|(image, _)| !image.is_broken())
+            .map(|(image, _)| image.id)
+            .collect();
+        if fixes.mark_broken && !ids.is_empty() {
+            images::Model::set_broken(&app_context.db, &ids, true).await?;
+            println!("Marked {} images broken", ids.len());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Fixes {
+    requeue: bool,
+    delete_orphans: bool,
+    mark_broken: bool,
+}
+
+impl Fixes {
+    fn parse(arg: Option<&String>) -> Result<Self> {
+        let mut fixes = Self::default();
+        for fix in arg.into_iter().flat_map(|arg| arg.split(',')) {
+            match fix.trim() {
+                "requeue" => fixes.requeue = true,
+                "delete_orphans" => fixes.delete_orphans = true,
+                "mark_broken" => fixes.mark_broken = true,
+                other => return Err(Error::string(&format!("Invalid fix: {}", other))),
+            }
+        }
+
+        Ok(fixes)
+    }
+}
+
+/// Where an object lives, Garage buckets by position.
+#[derive(Debug, Clone, Copy)]
+enum Store {
+    Garage(Position),
+    R2,
+}
+
+impl Store {
+    fn bucket(self) -> String {
+        match self {
+            Self::Garage(position) => get_garage().bucket(position).to_string(),
+            Self::R2 => get_r2().bucket().to_string(),
+        }
+    }
+}
+
+/// A listed bucket. Garage positions sharing a bucket share its listing,
+/// `store` being the first of them.
+struct Bucket {
+    store: Store,
+    name: String,
+    objects: HashMap<String, Option<chrono::DateTime<chrono::Utc>>>,
+    expected: HashSet<String>,
+}
+
+impl Bucket {
+    fn holds(&self, store: Store) -> bool {
+        let same_provider = matches!(
+            (self.store, store),
+            (Store::Garage(_), Store::Garage(_)) | (Store::R2, Store::R2)
+        );
+        same_provider && self.name == store.bucket()
+    }
+
+    /// Objects no image refers to, leaving out recent ones.
+    fn orphans(&self, grace: chrono::DateTime<chrono::Utc>) -> impl Iterator<Item = &String> {
+        self.objects
+            .iter()
+            .filter(move |(key, last_modified)| {
+                !self.expected.contains(*key) && last_modified.is_some_and(|at| at < grace)
+            })
+            .map(|(key, _)| key)
+    }
+}
+
+/// Lists every configured bucket. A bucket that can't be listed is left out
+/// of the check.
+async fn list_buckets() -> Vec<Bucket> {
+    let stores = [
+        Store::Garage(Position::Original),
+        Store::Garage(Position::Preview),
+        Store::Garage(Position::Avif),
+        Store::R2,
+    ];
+    let mut buckets: Vec<Bucket> = Vec::new();
+
+    for store in stores {
+        let name = store.bucket();
+        if name.is_empty() {
+            println!("skipped: {:?} bucket is not configured", store);
+            continue;
+        }
+        if buckets.iter().any(|b| b.holds(store)) {
+            continue;
+        }
+
+        let listed = match store {
+            Store::Garage(position) => get_garage().list_objects(position).await,
+            Store::R2 => get_r2().list_objects().await,
+        };
+        match listed {
+            Ok(objects) => buckets.push(Bucket {
+                store,
+                name,
+                objects: objects
+                    .into_iter()
+                    .map(|object| (object.key, object.last_modified))
+                    .collect(),
+                expected: HashSet::new(),
+            }),
+            Err(e) => println!("skipped: bucket {} can't be listed: {}", name, e),
+        }
+    }
+
+    buckets
+}
+
+/// The objects an image is stored as, with whether each must exist. Images
+/// uploaded before originals were kept have none, so it is never required.
+fn expected_objects(image: &images::Model) -> Vec<(Store, String, bool)> {
+    let original_key = image.original_key();
+    match image.location {
+        Location::Local => vec![
+            (Store::Garage(Position::Original), original_key, false),
+            (
+                Store::Garage(Position::Preview),
+                image.file_name.clone(),
+                true,
+            ),
+            (Store::Garage(Position::Avif), image.file_name.clone(), true),
+        ],
+        Location::R2 => vec![
+            (Store::R2, r2_original_key(&original_key), false),
+            (Store::R2, image.file_name.clone(), true),
+        ],
+    }
+}
+
+/// Encodes the derivatives of images again from their original, in place.
+/// Returns the images still missing objects and the ids of those encoded.
+async fn requeue(
+    ctx: &AppContext,
+    missing: Vec<(images::Model, Vec<String>)>,
+    quality: u8,
+) -> (Vec<(images::Model, Vec<String>)>, Vec<i32>) {
+    let mut left = Vec::new();
+    let mut encoded = Vec::new();
+    for (image, absent) in missing {
+        match regenerator::regenerate(ctx, &image, quality).await {
+            Ok(true) => encoded.push(image.id),
+            Ok(false) => {
+                println!("requeue: image {} has no original", image.id);
+                left.push((image, absent));
+            }
+            Err(e) => {
+                println!("requeue: image {} failed: {}", image.id, e);
+                left.push((image, absent));
+            }
+        }
+    }
+    println!("Encoded {} images again", encoded.len());
+
+    (left, encoded)
+}
+
+async fn delete_orphans(buckets: &[Bucket], grace: chrono::DateTime<chrono::Utc>) {
+    let mut deleted = 0;
+    for bucket in buckets {
+        for key in bucket.orphans(grace) {
+            let result = match bucket.store {
+                Store::Garage(position) => get_garage()
+                    .delete_object(key, position)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                Store::R2 => get_r2()
+                    .delete_object(key)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+            };
+            match result {
+                Ok(()) => deleted += 1,
+                Err(e) => println!("delete: {}/{} failed: {}", bucket.name, key, e),
+            }
+        }
+    }
+    println!("Deleted {} orphaned objects", deleted);
+}
@@ -0,0 +1,28 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    common::client::{init_garage, init_r2},
+    workers::account_deleter::{Worker, WorkerArgs},
+};
+
+/// Deletes accounts whose deletion grace period ran out, run by the
+/// scheduler. The worker runs in place so the accounts are gone when the task
+/// returns.
+pub struct DeleteAccounts;
+#[async_trait]
+impl Task for DeleteAccounts {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "delete_accounts".to_string(),
+            detail: "Delete accounts past their deletion grace period".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, _vars: &task::Vars) -> Result<()> {
+        // initializers only run with the server
+        init_garage(app_context).await;
+        init_r2(app_context).await;
+
+        Worker::build(app_context).perform(WorkerArgs {}).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    common::client::{init_garage, init_r2},
+    workers::purger::{Worker, WorkerArgs},
+};
+
+/// Purges expired images and export archives, run by the scheduler. The worker runs in place so
+/// the purge is done when the task returns.
+pub struct PurgeExpired;
+#[async_trait]
+impl Task for PurgeExpired {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "purge_expired".to_string(),
+            detail: "Delete expired images and exports".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, _vars: &task::Vars) -> Result<()> {
+        // initializers only run with the server
+        init_garage(app_context).await;
+        init_r2(app_context).await;
+
+        Worker::build(app_context).perform(WorkerArgs {}).await?;
+        Ok(())
+    }
+}
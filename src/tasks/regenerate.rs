@@ -0,0 +1,101 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    common::client::{init_garage, init_r2},
+    models::{
+        _entities::images::Location,
+        regenerations::{self, RegenerateParams},
+    },
+    workers::regenerator::{Worker, WorkerArgs},
+};
+
+const DEFAULT_QUALITY: u8 = 80;
+
+/// Encodes the derivatives of images again from their originals, after the
+/// encoder settings changed. Every filter is optional, `to` is exclusive and
+/// `delay_ms` pauses between images. The worker runs in place so the run is
+/// done when the task returns.
+///
+/// ```sh
+/// cargo loco task regenerate_derivatives quality:80
+/// cargo loco task regenerate_derivatives user:<pid> from:2026-01-01 to:2026-07-01 location:local delay_ms:200
+/// ```
+pub struct RegenerateDerivatives;
+#[async_trait]
+impl Task for RegenerateDerivatives {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "regenerate_derivatives".to_string(),
+            detail: "Encode image derivatives again from the originals".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, vars: &task::Vars) -> Result<()> {
+        let location = match vars.cli_arg("location").ok().map(String::as_str) {
+            None => None,
+            Some("local") => Some(Location::Local),
+            Some("r2") => Some(Location::R2),
+            Some(other) => return Err(Error::string(&format!("Invalid location: {}", other))),
+        };
+        let params = RegenerateParams {
+            user_pid: vars
+                .cli_arg("user")
+                .ok()
+                .map(|pid| Uuid::parse_str(pid))
+                .transpose()
+                .map_err(|_| Error::string("Argument 'user' must be a user pid"))?,
+            location,
+            created_from: day_arg(vars, "from")?,
+            created_to: day_arg(vars, "to")?,
+            quality: vars
+                .cli_arg("quality")
+                .ok()
+                .map(|quality| quality.parse::<u8>())
+                .transpose()
+                .map_err(|_| Error::string("Argument 'quality' must be 1 to 100"))?
+                .unwrap_or(DEFAULT_QUALITY),
+            delay_ms: vars
+                .cli_arg("delay_ms")
+                .ok()
+                .map(|delay| delay.parse::<u32>())
+                .transpose()
+                .map_err(|_| Error::string("Argument 'delay_ms' must be a number"))?
+                .unwrap_or(0),
+        };
+        validator::Validate::validate(&params).map_err(|e| Error::Validation(e.into()))?;
+
+        // initializers only run with the server
+        init_garage(app_context).await;
+        init_r2(app_context).await;
+
+        let regeneration = regenerations::Model::create(&app_context.db, &params).await?;
+        println!("Starting regeneration {}", regeneration.id);
+        Worker::build(app_context)
+            .perform(WorkerArgs {
+                regeneration_id: regeneration.id,
+            })
+            .await?;
+
+        let regeneration =
+            regenerations::Model::find_by_id(&app_context.db, regeneration.id).await?;
+        println!(
+            "Regenerated {} of {} images, {} skipped, {} failed",
+            regeneration.encoded, regeneration.total, regeneration.skipped, regeneration.failed
+        );
+        Ok(())
+    }
+}
+
+fn day_arg(vars: &task::Vars, name: &str) -> Result<Option<DateTimeWithTimeZone>> {
+    vars.cli_arg(name)
+        .ok()
+        .map(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d"))
+        .transpose()
+        .map_err(|_| Error::string(&format!("Argument '{}' must be YYYY-MM-DD", name)))
+        .map(|day| {
+            day.map(|day| {
+                day.and_time(chrono::NaiveTime::MIN)
+                    .and_utc()
+                    .fixed_offset()
+            })
+        })
+}
@@ -0,0 +1,82 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    common::client::{init_garage, init_r2},
+    models::{_entities::images::Location, images},
+    workers::relocator::{Worker, WorkerArgs},
+};
+
+/// Moves images between Garage and R2, either the given ones or every image
+/// matching the filters. The worker runs in place so the move is done when
+/// the task returns.
+///
+/// ```sh
+/// cargo loco task relocate_images to:r2 ids:12,15
+/// cargo loco task relocate_images to:r2 user:<pid> before:2026-01-01 limit:500
+/// ```
+pub struct RelocateImages;
+#[async_trait]
+impl Task for RelocateImages {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "relocate_images".to_string(),
+            detail: "Move images between Garage and R2".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, vars: &task::Vars) -> Result<()> {
+        let to = match vars
+            .cli_arg("to")
+            .map_err(|_| Error::string("Argument 'to' is required"))?
+            .as_str()
+        {
+            "local" => Location::Local,
+            "r2" => Location::R2,
+            other => return Err(Error::string(&format!("Invalid location: {}", other))),
+        };
+
+        let image_ids = match vars.cli_arg("ids") {
+            Ok(ids) => ids
+                .split(',')
+                .map(|id| id.trim().parse::<i32>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| Error::string("Argument 'ids' must be comma separated ids"))?,
+            Err(_) => {
+                let user_pid = vars
+                    .cli_arg("user")
+                    .ok()
+                    .map(|pid| Uuid::parse_str(pid))
+                    .transpose()
+                    .map_err(|_| Error::string("Argument 'user' must be a user pid"))?;
+                let before = vars
+                    .cli_arg("before")
+                    .ok()
+                    .map(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d"))
+                    .transpose()
+                    .map_err(|_| Error::string("Argument 'before' must be YYYY-MM-DD"))?
+                    .map(|day| {
+                        day.and_time(chrono::NaiveTime::MIN)
+                            .and_utc()
+                            .fixed_offset()
+                    });
+                let limit = vars
+                    .cli_arg("limit")
+                    .ok()
+                    .map(|limit| limit.parse::<u64>())
+                    .transpose()
+                    .map_err(|_| Error::string("Argument 'limit' must be a number"))?;
+
+                images::Model::find_ids_not_in(&app_context.db, to, user_pid, before, limit).await?
+            }
+        };
+
+        // initializers only run with the server
+        init_garage(app_context).await;
+        init_r2(app_context).await;
+
+        println!("Moving {} images", image_ids.len());
+        Worker::build(app_context)
+            .perform(WorkerArgs { image_ids, to })
+            .await?;
+        Ok(())
+    }
+}
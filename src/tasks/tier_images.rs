@@ -0,0 +1,28 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    common::client::{init_garage, init_r2},
+    workers::tiering::{Worker, WorkerArgs},
+};
+
+/// Moves cold images to R2 following the tiering policy, run by the
+/// scheduler. The worker runs in place so the batch is done when the task
+/// returns.
+pub struct TierImages;
+#[async_trait]
+impl Task for TierImages {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "tier_images".to_string(),
+            detail: "Move cold images from Garage to R2".to_string(),
+        }
+    }
+    async fn run(&self, app_context: &AppContext, _vars: &task::Vars) -> Result<()> {
+        // initializers only run with the server
+        init_garage(app_context).await;
+        init_r2(app_context).await;
+
+        Worker::build(app_context).perform(WorkerArgs {}).await?;
+        Ok(())
+    }
+}
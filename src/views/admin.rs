@@ -0,0 +1,272 @@
+use chrono::NaiveDate;
+use sea_orm::{ItemsAndPagesNumber, prelude::DateTimeWithTimeZone};
+use serde::Serialize;
+
+use crate::{
+    common::{queue::QueueStats, quota::Quota},
+    models::{
+        _entities::{
+            abuse_reports::{self, ReportStatus},
+            images::{self, Location},
+            regenerations::{self, RegenerationStatus},
+        },
+        images::Usage,
+        users::users::{self, UserRole},
+    },
+    views::profile::UsageResponse,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaResponse {
+    /// The user's own quotas, `null` when the site default applies.
+    pub storage_quota: Option<i64>,
+    pub image_quota: Option<i32>,
+    pub usage: UsageResponse,
+}
+
+impl QuotaResponse {
+    #[must_use]
+    pub fn new(user: &users::Model, usage: &Usage, quota: &Quota) -> Self {
+        Self {
+            storage_quota: user.storage_quota,
+            image_quota: user.image_quota,
+            usage: UsageResponse::new(usage, quota),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverviewResponse {
+    pub total_users: u64,
+    pub total_images: i64,
+    pub locations: Vec<LocationOverview>,
+    /// Last 30 days, days without uploads are left out.
+    pub uploads: Vec<DayUploads>,
+    /// `null` when jobs don't go through a queue provider.
+    pub queue: Option<QueueOverview>,
+    pub garage: ConnectionHealth,
+    pub r2: ConnectionHealth,
+}
+
+/// Images stored in a location and the bytes they take across its buckets.
+#[derive(Serialize)]
+pub struct LocationOverview {
+    pub location: Location,
+    pub buckets: Vec<String>,
+    pub images: i64,
+    pub bytes: i64,
+}
+
+#[derive(Serialize)]
+pub struct DayUploads {
+    pub day: NaiveDate,
+    pub uploads: i64,
+}
+
+#[derive(Serialize)]
+pub struct QueueOverview {
+    pub queued: u64,
+    pub processing: u64,
+    pub failed: u64,
+}
+
+impl From<QueueStats> for QueueOverview {
+    fn from(stats: QueueStats) -> Self {
+        Self {
+            queued: stats.queued,
+            processing: stats.processing,
+            failed: stats.failed,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ConnectionHealth {
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+impl From<Result<(), String>> for ConnectionHealth {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsersResponse {
+    pub users: Vec<UserResponse>,
+    pub total: u64,
+    pub pages: u64,
+}
+
+impl UsersResponse {
+    #[must_use]
+    pub fn new(users: &[users::Model], num_items_and_pages: &ItemsAndPagesNumber) -> Self {
+        Self {
+            users: users.iter().map(UserResponse::new).collect(),
+            total: num_items_and_pages.number_of_items,
+            pages: num_items_and_pages.number_of_pages,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserResponse {
+    pub pid: String,
+    pub username: String,
+    pub email: String,
+    pub role: UserRole,
+    pub is_verified: bool,
+    pub disabled_at: Option<DateTimeWithTimeZone>,
+    /// When the account gets deleted, as the user asked.
+    pub deletes_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+impl UserResponse {
+    #[must_use]
+    pub fn new(user: &users::Model) -> Self {
+        Self {
+            pid: user.pid.to_string(),
+            username: user.username.clone(),
+            email: user.email.clone(),
+            role: user.role,
+            is_verified: user.email_verified_at.is_some(),
+            disabled_at: user.disabled_at,
+            deletes_at: user.deletes_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportsResponse {
+    pub reports: Vec<ReportResponse>,
+    pub total: u64,
+    pub pages: u64,
+}
+
+impl ReportsResponse {
+    #[must_use]
+    pub fn new(
+        reports: &[(abuse_reports::Model, images::Model)],
+        num_items_and_pages: &ItemsAndPagesNumber,
+    ) -> Self {
+        Self {
+            reports: reports
+                .iter()
+                .map(|(report, image)| ReportResponse::new(report, image))
+                .collect(),
+            total: num_items_and_pages.number_of_items,
+            pages: num_items_and_pages.number_of_pages,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportResponse {
+    pub id: i32,
+    pub reason: String,
+    pub contact: String,
+    pub status: ReportStatus,
+    pub created_at: DateTimeWithTimeZone,
+    pub resolved_at: Option<DateTimeWithTimeZone>,
+    pub image: ModeratedImageResponse,
+}
+
+impl ReportResponse {
+    #[must_use]
+    pub fn new(report: &abuse_reports::Model, image: &images::Model) -> Self {
+        Self {
+            id: report.id,
+            reason: report.reason.clone(),
+            contact: report.contact.clone(),
+            status: report.status,
+            created_at: report.created_at,
+            resolved_at: report.resolved_at,
+            image: ModeratedImageResponse::new(image),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeratedImageResponse {
+    pub id: i32,
+    pub url: String,
+    pub name: String,
+    pub location: Location,
+    /// `null` for anonymous uploads.
+    pub user_pid: Option<String>,
+    pub taken_down_at: Option<DateTimeWithTimeZone>,
+    pub takedown_reason: Option<String>,
+}
+
+impl ModeratedImageResponse {
+    #[must_use]
+    pub fn new(image: &images::Model) -> Self {
+        Self {
+            id: image.id,
+            url: image.url.clone(),
+            name: image.raw_name.clone(),
+            location: image.location,
+            user_pid: image.user_pid.map(|pid| pid.to_string()),
+            taken_down_at: image.taken_down_at,
+            takedown_reason: image.takedown_reason.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegenerationResponse {
+    pub id: i32,
+    pub status: RegenerationStatus,
+    pub user_pid: Option<String>,
+    pub location: Option<Location>,
+    pub created_from: Option<DateTimeWithTimeZone>,
+    pub created_to: Option<DateTimeWithTimeZone>,
+    pub quality: i32,
+    pub delay_ms: i32,
+    /// Images in scope when the run started.
+    pub total: i32,
+    pub encoded: i32,
+    /// Images without an original to encode from.
+    pub skipped: i32,
+    pub failed: i32,
+    pub error: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub finished_at: Option<DateTimeWithTimeZone>,
+}
+
+impl RegenerationResponse {
+    #[must_use]
+    pub fn new(regeneration: &regenerations::Model) -> Self {
+        Self {
+            id: regeneration.id,
+            status: regeneration.status,
+            user_pid: regeneration.user_pid.map(|pid| pid.to_string()),
+            location: regeneration.location,
+            created_from: regeneration.created_from,
+            created_to: regeneration.created_to,
+            quality: regeneration.quality,
+            delay_ms: regeneration.delay_ms,
+            total: regeneration.total,
+            encoded: regeneration.encoded,
+            skipped: regeneration.skipped,
+            failed: regeneration.failed,
+            error: regeneration.error.clone(),
+            created_at: regeneration.created_at,
+            finished_at: regeneration.finished_at,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+
+use crate::models::{albums, images};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumResponse {
+    pub pid: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub public: bool,
+    pub cover_image_id: Option<i32>,
+}
+
+impl AlbumResponse {
+    #[must_use]
+    pub fn new(album: &albums::Model) -> Self {
+        Self {
+            pid: album.pid.to_string(),
+            name: album.name.clone(),
+            description: album.description.clone(),
+            public: album.public,
+            cover_image_id: album.cover_image_id,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AlbumListResponse {
+    pub albums: Vec<AlbumResponse>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumDetailResponse {
+    #[serde(flatten)]
+    pub album: AlbumResponse,
+    pub cover_url: Option<String>,
+    pub images: Vec<AlbumImage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumImage {
+    pub id: i32,
+    pub url: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+impl AlbumImage {
+    #[must_use]
+    pub fn new(image: &images::Model) -> Self {
+        Self {
+            id: image.id,
+            url: image.url.clone(),
+            name: image.raw_name.clone(),
+            description: image.description.clone(),
+            alt_text: image.alt_text.clone(),
+        }
+    }
+}
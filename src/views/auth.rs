@@ -1,3 +1,4 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
 
 use crate::models::{_entities::users, users::users::UserRole};
@@ -47,3 +48,10 @@ impl CurrentResponse {
         }
     }
 }
+
+/// When the account gets deleted unless the user undoes it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteAccountResponse {
+    pub deletes_at: Option<DateTimeWithTimeZone>,
+}
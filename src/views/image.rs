@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+use crate::models::images;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageResponse {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub alt_text: Option<String>,
+    pub public: bool,
+}
+
+impl ImageResponse {
+    #[must_use]
+    pub fn new(image: &images::Model) -> Self {
+        Self {
+            id: image.id,
+            name: image.raw_name.clone(),
+            description: image.description.clone(),
+            alt_text: image.alt_text.clone(),
+            public: image.public,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BulkResponse {
+    pub results: Vec<BulkItemResult>,
+}
+
+#[derive(Serialize)]
+pub struct BulkItemResult {
+    pub id: i32,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BulkItemResult {
+    #[must_use]
+    pub fn ok(id: i32) -> Self {
+        Self {
+            id,
+            ok: true,
+            error: None,
+        }
+    }
+
+    #[must_use]
+    pub fn err(id: i32, error: &str) -> Self {
+        Self {
+            id,
+            ok: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
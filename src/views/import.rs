@@ -0,0 +1,43 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Serialize;
+
+use crate::models::{
+    _entities::imports::{ImportStatus, ImportStructure},
+    imports,
+};
+
+/// State of an import, done once `imported`, `skipped` and `failed` add up to
+/// `total`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResponse {
+    pub id: i32,
+    pub status: ImportStatus,
+    pub structure: ImportStructure,
+    pub file_name: String,
+    pub total: i32,
+    pub imported: i32,
+    pub skipped: i32,
+    pub failed: i32,
+    pub error: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub finished_at: Option<DateTimeWithTimeZone>,
+}
+
+impl From<imports::Model> for ImportResponse {
+    fn from(import: imports::Model) -> Self {
+        Self {
+            id: import.id,
+            status: import.status,
+            structure: import.structure,
+            file_name: import.file_name,
+            total: import.total,
+            imported: import.imported,
+            skipped: import.skipped,
+            failed: import.failed,
+            error: import.error,
+            created_at: import.created_at,
+            finished_at: import.finished_at,
+        }
+    }
+}
@@ -1,5 +1,11 @@
+pub mod admin;
+pub mod album;
 pub mod auth;
+pub mod image;
+pub mod import;
 pub mod profile;
 pub mod settings;
+pub mod stats;
+pub mod tag;
 pub mod upload;
 pub mod view;
@@ -1,9 +1,78 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::Serialize;
 
+use crate::{
+    common::quota::Quota,
+    models::{
+        _entities::exports::{ExportStatus, ExportVariant},
+        exports,
+        images::Usage,
+    },
+};
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserProfileResponse {
     pub email: String,
     pub name: String,
     pub api_token: String,
+    pub usage: UsageResponse,
+}
+
+/// Storage used by a user against their quota, `null` limits are unlimited.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageResponse {
+    pub bytes: i64,
+    pub images: i64,
+    pub max_bytes: Option<u64>,
+    pub max_images: Option<u64>,
+}
+
+impl UsageResponse {
+    #[must_use]
+    pub fn new(usage: &Usage, quota: &Quota) -> Self {
+        Self {
+            bytes: usage.bytes,
+            images: usage.images,
+            max_bytes: quota.max_bytes,
+            max_images: quota.max_images,
+        }
+    }
+}
+
+/// State of the user's latest export, `url` only while it can be downloaded.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResponse {
+    pub id: i32,
+    pub status: ExportStatus,
+    pub variant: ExportVariant,
+    pub images: i32,
+    pub missing: i32,
+    pub size: Option<i64>,
+    pub error: Option<String>,
+    pub url: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub finished_at: Option<DateTimeWithTimeZone>,
+    pub expires_at: Option<DateTimeWithTimeZone>,
+}
+
+impl ExportResponse {
+    #[must_use]
+    pub fn new(export: exports::Model, url: Option<String>) -> Self {
+        Self {
+            id: export.id,
+            status: export.status,
+            variant: export.variant,
+            images: export.images,
+            missing: export.missing,
+            size: export.size,
+            error: export.error,
+            url,
+            created_at: export.created_at,
+            finished_at: export.finished_at,
+            expires_at: export.expires_at,
+        }
+    }
 }
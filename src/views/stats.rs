@@ -0,0 +1,54 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_views: i64,
+    pub total_bytes: i64,
+    /// Only days with views are listed.
+    pub days: Vec<DayStats>,
+    pub referrers: Vec<ReferrerStats>,
+}
+
+#[derive(Serialize)]
+pub struct DayStats {
+    pub day: NaiveDate,
+    pub views: i64,
+    pub bytes: i64,
+}
+
+#[derive(Serialize)]
+pub struct ReferrerStats {
+    pub domain: String,
+    pub views: i64,
+}
+
+impl StatsResponse {
+    #[must_use]
+    pub fn new(
+        from: NaiveDate,
+        to: NaiveDate,
+        days: Vec<(NaiveDate, i64, i64)>,
+        referrers: Vec<(String, i64)>,
+    ) -> Self {
+        let days: Vec<DayStats> = days
+            .into_iter()
+            .map(|(day, views, bytes)| DayStats { day, views, bytes })
+            .collect();
+
+        Self {
+            from,
+            to,
+            total_views: days.iter().map(|d| d.views).sum(),
+            total_bytes: days.iter().map(|d| d.bytes).sum(),
+            days,
+            referrers: referrers
+                .into_iter()
+                .map(|(domain, views)| ReferrerStats { domain, views })
+                .collect(),
+        }
+    }
+}
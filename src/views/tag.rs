@@ -0,0 +1,24 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct TagListResponse {
+    pub tags: Vec<TagCount>,
+}
+
+#[derive(Serialize)]
+pub struct TagCount {
+    pub name: String,
+    pub count: i64,
+}
+
+impl TagListResponse {
+    #[must_use]
+    pub fn new(tags: Vec<(String, i64)>) -> Self {
+        Self {
+            tags: tags
+                .into_iter()
+                .map(|(name, count)| TagCount { name, count })
+                .collect(),
+        }
+    }
+}
@@ -1,11 +1,17 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::Serialize;
 
 use crate::controllers::upload::UploadResult;
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UploadResponse {
     pub url: Option<String>,
     // pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTimeWithTimeZone>,
 }
 
 impl From<UploadResult> for UploadResponse {
@@ -13,6 +19,8 @@ impl From<UploadResult> for UploadResponse {
         UploadResponse {
             url: Some(value.url),
             // status: value.status,
+            delete_token: value.delete_token,
+            expires_at: value.expires_at,
         }
     }
 }
@@ -21,4 +29,6 @@ impl From<UploadResult> for UploadResponse {
 #[serde(rename_all = "camelCase")]
 pub struct PresignResponse {
     pub upload_url: String,
+    /// Key to confirm the upload with.
+    pub file_name: String,
 }
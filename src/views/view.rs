@@ -1,10 +1,16 @@
 use serde::Serialize;
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ListViewResponse {
     pub images: Vec<Image>,
-    pub total: u64,
-    pub pages: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// Only set for offset paging.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<u64>,
+    /// Cursor of the following page, `null` on the last one.
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -15,4 +21,5 @@ pub struct Image {
     pub original_url: String,
     pub name: String,
     // pub size: String,
+    pub tags: Vec<String>,
 }
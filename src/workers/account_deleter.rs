@@ -0,0 +1,57 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{controllers::admin::remove_user, models::users};
+
+const BATCH_SIZE: u64 = 100;
+/// How long an account that failed to delete is put off.
+const RETRY_AFTER_HOURS: i64 = 6;
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "AccountDeleter".to_string()
+    }
+
+    /// Deletes every account whose grace period ran out, like an admin
+    /// removing the user. One that fails is put off for a while, so it
+    /// doesn't hold up the others.
+    async fn perform(&self, _args: WorkerArgs) -> Result<()> {
+        let (mut deleted, mut failed) = (0, 0);
+        loop {
+            let due = users::Model::find_due_for_deletion(&self.ctx.db, BATCH_SIZE).await?;
+            if due.is_empty() {
+                break;
+            }
+            for user in due {
+                match remove_user(&self.ctx, user.clone()).await {
+                    Ok(()) => deleted += 1,
+                    Err(e) => {
+                        tracing::error!(pid = %user.pid, "Failed to delete account: {}", e);
+                        user.into_active_model()
+                            .postpone_deletion(
+                                &self.ctx.db,
+                                chrono::Duration::hours(RETRY_AFTER_HOURS),
+                            )
+                            .await?;
+                        failed += 1;
+                    }
+                }
+            }
+        }
+        tracing::info!("Deleted {} accounts, {} failed", deleted, failed);
+
+        Ok(())
+    }
+}
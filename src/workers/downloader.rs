@@ -1,5 +1,35 @@
+use std::path::PathBuf;
+
+use aws_sdk_s3::primitives::ByteStream;
 use loco_rs::prelude::*;
 use serde::{Deserialize, Serialize};
+use tokio::io::BufWriter;
+
+use crate::{
+    common::{
+        client::{Position, get_garage, get_r2, r2_original_key},
+        zip::ZipWriter,
+    },
+    controllers::upload::{TEMP_DIR, TempFileGuard},
+    mailers::export::ExportMailer,
+    models::{
+        _entities::{
+            exports::{ExportStatus, ExportVariant},
+            images::Location,
+        },
+        exports::{self, StoredExport},
+        images::{self, ImageScope},
+        tags, users,
+    },
+};
+
+const BATCH_SIZE: u64 = 100;
+/// How long the download link of an export stays valid, the archive is
+/// deleted once it runs out.
+pub const LINK_HOURS: i64 = 24;
+/// Hours an export may stay queued or running before it's taken for lost and
+/// no longer blocks the user from starting another.
+pub const STALE_HOURS: i64 = 6;
 
 pub struct DownloadWorker {
     pub ctx: AppContext,
@@ -7,7 +37,7 @@ pub struct DownloadWorker {
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct DownloadWorkerArgs {
-    pub user_guid: String,
+    pub export_id: i32,
 }
 
 #[async_trait]
@@ -15,9 +45,340 @@ impl BackgroundWorker<DownloadWorkerArgs> for DownloadWorker {
     fn build(ctx: &AppContext) -> Self {
         Self { ctx: ctx.clone() }
     }
-    async fn perform(&self, _args: DownloadWorkerArgs) -> Result<()> {
-        // TODO: Some actual work goes here...
+
+    /// Writes every image of the user into a ZIP with a `manifest.json` of
+    /// their metadata, stores it and emails the user a download link.
+    async fn perform(&self, args: DownloadWorkerArgs) -> Result<()> {
+        let db = &self.ctx.db;
+        let export = match exports::Model::find_by_id(db, args.export_id).await {
+            Ok(export) => export,
+            Err(ModelError::EntityNotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if export.status != ExportStatus::Queued {
+            // already failed as stale
+            return Ok(());
+        }
+
+        let result = async {
+            let user = users::Model::find_by_pid(db, &export.user_pid.to_string())
+                .await
+                .map_err(|e| e.to_string())?;
+            let export = export
+                .clone()
+                .into_active_model()
+                .start(db)
+                .await
+                .map_err(|e| e.to_string())?;
+            let stored = build_export(&self.ctx, &user, &export).await?;
+            let export = export
+                .into_active_model()
+                .finish(db, stored)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok::<_, String>((user, export))
+        }
+        .await;
+        let (user, export) = match result {
+            Ok(done) => done,
+            Err(e) => {
+                tracing::error!(export_id = export.id, "Failed to export: {}", e);
+                export.into_active_model().fail(db, e).await?;
+                return Ok(());
+            }
+        };
+        tracing::info!(
+            export_id = export.id,
+            "Exported {} images, {} missing",
+            export.images,
+            export.missing
+        );
+
+        // the link can still be fetched from the status endpoint
+        match sign_url(&export).await {
+            Some(Ok(url)) => {
+                if let Err(e) = ExportMailer::send_ready(&self.ctx, &user, &export, &url).await {
+                    tracing::error!(export_id = export.id, "Failed to send export email: {}", e);
+                }
+            }
+            Some(Err(e)) => {
+                tracing::error!(export_id = export.id, "Failed to sign export link: {}", e);
+            }
+            None => {}
+        }
 
         Ok(())
     }
 }
+
+/// A download link valid until the export expires, `None` once it did.
+pub async fn sign_url(export: &exports::Model) -> Option<std::result::Result<String, String>> {
+    let (location, key) = export.stored()?;
+    let expires_in = export
+        .expires_at?
+        .signed_duration_since(chrono::Utc::now())
+        .num_seconds();
+    let expires_in = u64::try_from(expires_in).ok().filter(|secs| *secs > 0)?;
+
+    Some(match location {
+        Location::Local => {
+            get_garage()
+                .sign_download_url(key, Position::Original, expires_in)
+                .await
+        }
+        Location::R2 => get_r2().sign_download_url(key, expires_in).await,
+    })
+}
+
+/// Deletes a stored archive.
+///
+/// # Errors
+///
+/// When the object can't be deleted
+pub async fn delete_archive(location: Location, key: &str) -> std::result::Result<(), String> {
+    match location {
+        Location::Local => get_garage()
+            .delete_object(key, Position::Original)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Location::R2 => get_r2()
+            .delete_object(key)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Deletes every export of a user with its archive. An archive that can't be
+/// deleted is left for `check_storage` to find.
+///
+/// # Errors
+///
+/// When has DB query error
+pub async fn remove_exports(db: &DatabaseConnection, user_pid: Uuid) -> Result<()> {
+    for export in exports::Model::find_by_user_pid(db, user_pid).await? {
+        if let Some((location, key)) = export.location.zip(export.file_key.as_deref())
+            && let Err(e) = delete_archive(location, key).await
+        {
+            tracing::warn!(
+                export_id = export.id,
+                "Failed to delete export archive: {}",
+                e
+            );
+        }
+        export.into_active_model().delete(db).await?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    exported_at: DateTimeWithTimeZone,
+    username: String,
+    variant: ExportVariant,
+    images: Vec<ManifestImage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestImage {
+    uuid: Uuid,
+    name: String,
+    description: Option<String>,
+    alt_text: Option<String>,
+    public: bool,
+    location: Location,
+    size: i64,
+    views: i32,
+    max_views: Option<i32>,
+    expires_at: Option<DateTimeWithTimeZone>,
+    created_at: DateTimeWithTimeZone,
+    tags: Vec<String>,
+    /// Path in the archive, `None` when no file of the image was found.
+    file: Option<String>,
+    variant: Option<ExportVariant>,
+}
+
+/// Writes the archive to the temp dir, then stores it next to the originals:
+/// in R2 when it is configured, otherwise in the Garage origin bucket.
+async fn build_export(
+    ctx: &AppContext,
+    user: &users::Model,
+    export: &exports::Model,
+) -> std::result::Result<StoredExport, String> {
+    tokio::fs::create_dir_all(TEMP_DIR)
+        .await
+        .map_err(|e| e.to_string())?;
+    let tmp_path = PathBuf::from(TEMP_DIR).join(format!("export-{}.zip", export.id));
+    let _tmp_file_guard = TempFileGuard(tmp_path.clone());
+    let file = tokio::fs::File::create(&tmp_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+
+    let scope = ImageScope {
+        user_pid: Some(user.pid),
+        location: None,
+        created_from: None,
+        created_to: None,
+    };
+    let mut manifest = Manifest {
+        exported_at: chrono::Utc::now().into(),
+        username: user.username.clone(),
+        variant: export.variant,
+        images: Vec::new(),
+    };
+    let mut missing = 0;
+    let mut after_id = 0;
+    loop {
+        let batch = images::Model::find_batch_in_scope(&ctx.db, &scope, after_id, BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after_id = last.id;
+        let ids: Vec<i32> = batch.iter().map(|image| image.id).collect();
+        let mut tag_names = tags::Model::names_by_image_ids(&ctx.db, &ids)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for image in batch {
+            let written = write_image(&mut zip, &image, export.variant).await?;
+            if written.is_none() {
+                missing += 1;
+            }
+            manifest.images.push(ManifestImage {
+                uuid: image.uuid,
+                name: image.raw_name,
+                description: image.description,
+                alt_text: image.alt_text,
+                public: image.public,
+                location: image.location,
+                size: image.size,
+                views: image.views,
+                max_views: image.max_views,
+                expires_at: image.expires_at,
+                created_at: image.created_at,
+                tags: tag_names.remove(&image.id).unwrap_or_default(),
+                file: written.as_ref().map(|(file, _)| file.clone()),
+                variant: written.map(|(_, variant)| variant),
+            });
+        }
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    let mut entry = zip
+        .start_entry("manifest.json", chrono::Utc::now())
+        .await
+        .map_err(|e| e.to_string())?;
+    entry.write(&json).await.map_err(|e| e.to_string())?;
+    entry.close().await.map_err(|e| e.to_string())?;
+    zip.finish().await.map_err(|e| e.to_string())?;
+
+    let size = tokio::fs::metadata(&tmp_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+    let file_key = format!("exports/{}/{}.zip", user.pid, export.id);
+    let body = ByteStream::from_path(&tmp_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let location = if get_r2().bucket().is_empty() {
+        get_garage()
+            .pub_object(&file_key, body, "application/zip", Position::Original)
+            .await
+            .map_err(|e| e.to_string())?;
+        Location::Local
+    } else {
+        get_r2()
+            .put_object(&file_key, body, "application/zip")
+            .await
+            .map_err(|e| e.to_string())?;
+        Location::R2
+    };
+
+    Ok(StoredExport {
+        location,
+        file_key,
+        size: i64::try_from(size).unwrap_or(i64::MAX),
+        images: i32::try_from(manifest.images.len()).unwrap_or(i32::MAX),
+        missing,
+        expires_at: (chrono::Utc::now() + chrono::Duration::hours(LINK_HOURS)).into(),
+    })
+}
+
+/// Streams the wanted file of an image into the archive, falling back to the
+/// other variant when it wasn't kept. Returns its path in the archive.
+async fn write_image<W>(
+    zip: &mut ZipWriter<W>,
+    image: &images::Model,
+    variant: ExportVariant,
+) -> std::result::Result<Option<(String, ExportVariant)>, String>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let original_key = image.original_key();
+    let original = match image.location {
+        Location::Local => vec![(Position::Original, original_key.clone())],
+        // uploads straight to R2 are served as sent
+        Location::R2 if image.file_name.ends_with(".avif") => {
+            vec![(Position::Original, r2_original_key(&original_key))]
+        }
+        Location::R2 => vec![
+            (Position::Original, r2_original_key(&original_key)),
+            (Position::Original, image.file_name.clone()),
+        ],
+    };
+    let avif = match image.location {
+        Location::Local => vec![(Position::Avif, image.file_name.clone())],
+        Location::R2 if image.file_name.ends_with(".avif") => {
+            vec![(Position::Avif, image.file_name.clone())]
+        }
+        Location::R2 => vec![],
+    };
+    let candidates = match variant {
+        ExportVariant::Original => [
+            (ExportVariant::Original, original),
+            (ExportVariant::Avif, avif),
+        ],
+        ExportVariant::Avif => [
+            (ExportVariant::Avif, avif),
+            (ExportVariant::Original, original),
+        ],
+    };
+
+    for (variant, keys) in candidates {
+        for (position, key) in keys {
+            let output = match image.location {
+                Location::Local => get_garage().get_object(&key, position).await,
+                Location::R2 => get_r2().get_object(&key).await,
+            };
+            let mut output = match output {
+                Ok(output) => output,
+                Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => continue,
+                Err(e) => return Err(e.to_string()),
+            };
+
+            let file = match variant {
+                ExportVariant::Original => format!("images/{}", original_key),
+                ExportVariant::Avif => format!("images/{}", image.file_name),
+            };
+            let mut entry = zip
+                .start_entry(&file, image.created_at.to_utc())
+                .await
+                .map_err(|e| e.to_string())?;
+            while let Some(chunk) = output.body.next().await {
+                let chunk = chunk.map_err(|e| e.to_string())?;
+                entry.write(&chunk).await.map_err(|e| e.to_string())?;
+            }
+            entry.close().await.map_err(|e| e.to_string())?;
+            return Ok(Some((file, variant)));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path},
+};
+
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{
+        quota::Quota,
+        settings::{SettingsService, ZipImportPolicy},
+        zip::{ZipEntry, ZipReader},
+    },
+    controllers::upload::{TempFileGuard, TempUpload, enqueue_thumbnail, save_within_quota},
+    models::{
+        _entities::imports::{ImportStatus, ImportStructure},
+        albums::{self, CreateParams},
+        image_tags,
+        images::{self, Usage},
+        imports::{self, ImportProgress},
+        tags::{self, TagsParams},
+        users,
+    },
+};
+
+const MAX_ALBUM_NAME: usize = 255;
+/// Hours an import may go without progress before it's taken for lost and no
+/// longer blocks the user from starting another.
+pub const STALE_HOURS: i64 = 2;
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {
+    pub import_id: i32,
+    pub tmp_file_guard: TempFileGuard,
+    pub quality: u8,
+    pub public: bool,
+}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "Importer".to_string()
+    }
+
+    /// Unpacks the image entries of an uploaded ZIP into images of the user,
+    /// each queued for its thumbnail like a single upload.
+    async fn perform(&self, args: WorkerArgs) -> Result<()> {
+        let db = &self.ctx.db;
+        let import = match imports::Model::find_by_id(db, args.import_id).await {
+            Ok(import) => import,
+            Err(ModelError::EntityNotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if import.status != ImportStatus::Queued {
+            // already failed as stale
+            return Ok(());
+        }
+
+        let mut import = import;
+        let result = async {
+            let user = users::Model::find_by_pid(db, &import.user_pid.to_string())
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut importer = Importer {
+                ctx: &self.ctx,
+                user: &user,
+                args: &args,
+                structure: import.structure,
+                albums: HashMap::new(),
+            };
+            importer.run(&mut import).await?;
+            import
+                .clone()
+                .into_active_model()
+                .finish(db)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        .await;
+        let import = match result {
+            Ok(import) => import,
+            Err(e) => {
+                tracing::error!(import_id = import.id, "Failed to import: {}", e);
+                import.into_active_model().fail(db, e).await?
+            }
+        };
+        tracing::info!(
+            import_id = import.id,
+            "Imported {} of {} images, {} skipped, {} failed",
+            import.imported,
+            import.total,
+            import.skipped,
+            import.failed
+        );
+
+        Ok(())
+    }
+}
+
+struct Importer<'a> {
+    ctx: &'a AppContext,
+    user: &'a users::Model,
+    args: &'a WorkerArgs,
+    structure: ImportStructure,
+    /// Albums created for the folders so far.
+    albums: HashMap<String, albums::Model>,
+}
+
+impl Importer<'_> {
+    /// Keeps `import` as last saved, so it can be failed with the progress
+    /// made before the error.
+    async fn run(&mut self, import: &mut imports::Model) -> std::result::Result<(), String> {
+        let db = &self.ctx.db;
+        let policy = SettingsService::zip_import_policy().await;
+        let file = tokio::fs::File::open(&self.args.tmp_file_guard.0)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut zip = ZipReader::new(file, policy.max_entries)
+            .await
+            .map_err(|e| e.to_string())?;
+        let entries: Vec<ZipEntry> = zip
+            .entries()
+            .iter()
+            .filter(|entry| is_image(entry))
+            .cloned()
+            .collect();
+        check_size(&entries, &policy)?;
+
+        let total = i32::try_from(entries.len()).unwrap_or(i32::MAX);
+        *import = import
+            .clone()
+            .into_active_model()
+            .start(db, total)
+            .await
+            .map_err(|e| e.to_string())?;
+        let quota = Quota::for_user(self.user).await;
+        let mut usage = images::Model::usage_by_user_pid(db, self.user.pid)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut progress = ImportProgress::default();
+        for entry in entries {
+            if entry.size > policy.max_entry_bytes {
+                progress.skipped += 1;
+            } else {
+                match self.import_entry(&mut zip, &entry, &quota, &usage).await {
+                    Ok(size) => {
+                        usage.bytes = usage.bytes.saturating_add(size);
+                        usage.images += 1;
+                        progress.imported += 1;
+                    }
+                    // the remaining images won't fit either
+                    Err(Error::CustomError(_, detail)) => {
+                        return Err(detail.description.unwrap_or_default());
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            import_id = import.id,
+                            "Failed to import {}: {}",
+                            entry.name,
+                            e
+                        );
+                        progress.failed += 1;
+                    }
+                }
+            }
+            *import = import
+                .clone()
+                .into_active_model()
+                .progress(db, progress)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Unpacks an entry into a new image, returning its size.
+    async fn import_entry<R>(
+        &mut self,
+        zip: &mut ZipReader<R>,
+        entry: &ZipEntry,
+        quota: &Quota,
+        usage: &Usage,
+    ) -> Result<i64>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        let mut upload = TempUpload::create(&entry.name).await?;
+        let mut reader = zip.open(entry).await?;
+        while let Some(chunk) = reader.chunk().await? {
+            upload.write(&chunk).await?;
+        }
+
+        let (mut r, args) = upload
+            .finish(self.ctx, self.args.quality, Some((quota, usage)))
+            .await?;
+        r.is_public = self.args.public;
+        r.user_id = Some(self.user.pid);
+        let image = save_within_quota(self.ctx, &r, quota).await?;
+        enqueue_thumbnail(self.ctx, args).await;
+
+        let folders: Vec<&str> = Path::new(&entry.name)
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .filter_map(|folder| match folder {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        if !folders.is_empty() {
+            self.keep_structure(image.id, &folders).await?;
+        }
+
+        Ok(r.size)
+    }
+
+    /// Adds the image to the album of its folder, or tags it with the names of
+    /// its folders.
+    async fn keep_structure(&mut self, image_id: i32, folders: &[&str]) -> Result<()> {
+        let db = &self.ctx.db;
+        match self.structure {
+            ImportStructure::Flat => {}
+            ImportStructure::Albums => {
+                let name: String = folders.join("/").chars().take(MAX_ALBUM_NAME).collect();
+                let album = match self.albums.get(&name) {
+                    Some(album) => album,
+                    None => {
+                        let params = CreateParams {
+                            name: name.clone(),
+                            description: None,
+                            public: Some(false),
+                        };
+                        let album = albums::Model::create(db, self.user.pid, &params).await?;
+                        self.albums.entry(name).or_insert(album)
+                    }
+                };
+                album.add_images(db, &[image_id]).await?;
+            }
+            ImportStructure::Tags => {
+                let params = TagsParams {
+                    tags: folders.iter().map(ToString::to_string).collect(),
+                };
+                // folders that can't be tags are left out
+                if let Ok(names) = params.normalized() {
+                    let tags = tags::Model::find_or_create(db, self.user.pid, &names).await?;
+                    let ids: Vec<i32> = tags.iter().map(|tag| tag.id).collect();
+                    image_tags::Model::attach(db, image_id, &ids).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether the entry looks like an image, leaving out folders and the hidden
+/// files some archivers add.
+fn is_image(entry: &ZipEntry) -> bool {
+    if entry.is_dir() || entry.name.starts_with("__MACOSX/") {
+        return false;
+    }
+    let path = Path::new(&entry.name);
+    if path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.'))
+    {
+        return false;
+    }
+
+    mime_guess2::from_path(path)
+        .first()
+        .is_some_and(|mime| mime.type_() == mime_guess2::mime::IMAGE)
+}
+
+fn check_size(entries: &[ZipEntry], policy: &ZipImportPolicy) -> std::result::Result<(), String> {
+    let total = entries
+        .iter()
+        .try_fold(0u64, |total, entry| total.checked_add(entry.size));
+    if total.is_none_or(|total| total > policy.max_bytes) {
+        return Err(format!(
+            "解压后的图片总大小超过{}MB",
+            policy.max_bytes / 1024 / 1024
+        ));
+    }
+
+    Ok(())
+}
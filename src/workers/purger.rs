@@ -0,0 +1,121 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controllers::{image::remove_image, upload::TempUpload},
+    models::{exports, images, pending_removals, tus_uploads},
+    workers::{downloader, remover},
+};
+
+const BATCH_SIZE: u64 = 100;
+/// Hours without data after which a resumable upload is given up on.
+const TUS_STALE_HOURS: i64 = 24;
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "Purger".to_string()
+    }
+
+    /// Deletes every expired image. Rows go right away, their objects are
+    /// left to the remover worker, whose failed removals are retried here once
+    /// due. Archives of exports whose link ran out are
+    /// deleted too, as are resumable uploads that stalled.
+    async fn perform(&self, _args: WorkerArgs) -> Result<()> {
+        let mut purged = 0;
+        loop {
+            let expired = images::Model::find_expired(&self.ctx.db, BATCH_SIZE).await?;
+            if expired.is_empty() {
+                break;
+            }
+            for image in expired {
+                remove_image(&self.ctx, image).await?;
+                purged += 1;
+            }
+        }
+        tracing::info!("Purged {} expired images", purged);
+
+        let (mut removed, mut failed) = (0, 0);
+        loop {
+            let due = pending_removals::Model::find_due(&self.ctx.db, BATCH_SIZE).await?;
+            if due.is_empty() {
+                break;
+            }
+            let before = removed;
+            for removal in due {
+                // failures are logged and put off by the remover
+                if remover::remove(&self.ctx.db, removal).await.is_ok() {
+                    removed += 1;
+                } else {
+                    failed += 1;
+                }
+            }
+            // storage is likely down, the rest waits for the next run
+            if removed == before {
+                break;
+            }
+        }
+        tracing::info!(
+            "Retried pending removals: {} done, {} failed",
+            removed,
+            failed
+        );
+
+        let mut purged = 0;
+        loop {
+            let expired = exports::Model::find_expired(&self.ctx.db, BATCH_SIZE).await?;
+            if expired.is_empty() {
+                break;
+            }
+            for export in expired {
+                // an archive left behind is found by `check_storage`
+                if let (Some(location), Some(key)) = (export.location, export.file_key.as_deref())
+                    && let Err(e) = downloader::delete_archive(location, key).await
+                {
+                    tracing::warn!(
+                        export_id = export.id,
+                        "Failed to delete export archive: {}",
+                        e
+                    );
+                }
+                export.into_active_model().expire(&self.ctx.db).await?;
+                purged += 1;
+            }
+        }
+        tracing::info!("Purged {} expired exports", purged);
+
+        let before = chrono::Utc::now() - chrono::TimeDelta::hours(TUS_STALE_HOURS);
+        let mut purged = 0;
+        loop {
+            let stale =
+                tus_uploads::Model::find_stale(&self.ctx.db, before.into(), BATCH_SIZE).await?;
+            if stale.is_empty() {
+                break;
+            }
+            for upload in stale {
+                let path = TempUpload::path(upload.uuid, &upload.raw_name);
+                if let Err(e) = tokio::fs::remove_file(&path).await
+                    && e.kind() != std::io::ErrorKind::NotFound
+                {
+                    tracing::warn!("Failed to remove {}: {}", path.display(), e);
+                }
+                upload.delete(&self.ctx.db).await?;
+                purged += 1;
+            }
+        }
+        tracing::info!("Purged {} stale resumable uploads", purged);
+
+        Ok(())
+    }
+}
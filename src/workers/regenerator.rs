@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use aws_sdk_s3::primitives::ByteStream;
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::client::{Position, get_garage, get_r2},
+    models::{_entities::images::Location, images, regenerations},
+    workers::thumbnail,
+};
+
+const BATCH_SIZE: u64 = 100;
+/// Progress is written to the run every this many images.
+const PROGRESS_EVERY: i32 = 10;
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {
+    pub regeneration_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "Regenerator".to_string()
+    }
+
+    /// Encodes the derivatives of every image of the run again, pausing
+    /// `delay_ms` between images. Counts are written to the run as it goes,
+    /// a failed image doesn't stop the rest.
+    async fn perform(&self, args: WorkerArgs) -> Result<()> {
+        let db = &self.ctx.db;
+        let regeneration = match regenerations::Model::find_by_id(db, args.regeneration_id).await {
+            Ok(regeneration) => regeneration,
+            Err(ModelError::EntityNotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let scope = regeneration.scope();
+        let quality = u8::try_from(regeneration.quality).unwrap_or(100);
+        let delay = Duration::from_millis(u64::try_from(regeneration.delay_ms).unwrap_or(0));
+
+        let total = images::Model::count_in_scope(db, &scope).await?;
+        let mut regeneration = regeneration.into_active_model().start(db, total).await?;
+
+        let (mut encoded, mut skipped, mut failed) = (0, 0, 0);
+        let result: Result<()> = async {
+            let mut after_id = 0;
+            loop {
+                let batch =
+                    images::Model::find_batch_in_scope(db, &scope, after_id, BATCH_SIZE).await?;
+                let Some(last) = batch.last() else {
+                    break;
+                };
+                after_id = last.id;
+
+                for image in batch {
+                    match regenerate(&self.ctx, &image, quality).await {
+                        Ok(true) => encoded += 1,
+                        Ok(false) => skipped += 1,
+                        Err(e) => {
+                            tracing::error!(image_id = image.id, "Failed to regenerate: {}", e);
+                            failed += 1;
+                        }
+                    }
+
+                    let done = encoded + skipped + failed;
+                    if done % PROGRESS_EVERY == 0 {
+                        regeneration = regeneration
+                            .clone()
+                            .into_active_model()
+                            .set_progress(db, encoded, skipped, failed)
+                            .await?;
+                        tracing::info!("Regenerated {}/{} images", done, regeneration.total);
+                    }
+                    if !delay.is_zero() {
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        let regeneration = regeneration
+            .into_active_model()
+            .set_progress(db, encoded, skipped, failed)
+            .await?;
+        tracing::info!(
+            "Regenerated {} images, {} skipped, {} failed",
+            encoded,
+            skipped,
+            failed
+        );
+        let error = result.as_ref().err().map(ToString::to_string);
+        regeneration.into_active_model().finish(db, error).await?;
+
+        result
+    }
+}
+
+/// Encodes the image's derivatives again from its original and updates its
+/// size. Returns false when there is nothing to encode from, or in R2 nothing
+/// derived to replace.
+///
+/// # Errors
+///
+/// When the original can't be read or the new derivatives stored
+pub async fn regenerate(
+    ctx: &AppContext,
+    image: &images::Model,
+    quality: u8,
+) -> std::result::Result<bool, String> {
+    // R2 serves uploads as sent, only relocated images are AVIF copies
+    if image.location == Location::R2 && !image.file_name.ends_with(".avif") {
+        return Ok(false);
+    }
+    let Some(args) = thumbnail::args_from_original(image, quality).await? else {
+        return Ok(false);
+    };
+    let old_size = stored_size(image).await?;
+
+    match image.location {
+        Location::Local => {
+            thumbnail::Worker::build(ctx)
+                .perform(args)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Location::R2 => {
+            let path = args.tmp_file_guard.0.clone();
+            let (_, avif) = tokio::task::spawn_blocking(move || {
+                thumbnail::process_thumbnail(path, args.quality)
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+            let size = i64::try_from(avif.len()).unwrap_or(i64::MAX);
+            get_r2()
+                .put_object(&image.file_name, ByteStream::from(avif), "image/avif")
+                .await
+                .map_err(|e| e.to_string())?;
+            images::Model::add_size(&ctx.db, &image.file_name, size)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    images::Model::add_size(&ctx.db, &image.file_name, -old_size)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+/// Bytes of the derivatives about to be replaced, missing ones counting 0.
+async fn stored_size(image: &images::Model) -> std::result::Result<i64, String> {
+    let lengths = match image.location {
+        Location::Local => {
+            let garage = get_garage();
+            vec![
+                garage
+                    .head_object(&image.file_name, Position::Preview)
+                    .await,
+                garage.head_object(&image.file_name, Position::Avif).await,
+            ]
+        }
+        Location::R2 => vec![get_r2().head_object(&image.file_name).await],
+    };
+
+    let mut size = 0;
+    for length in lengths {
+        match length {
+            Ok(head) => size += head.content_length().unwrap_or(0),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    Ok(size)
+}
@@ -0,0 +1,223 @@
+use aws_sdk_s3::{operation::get_object::GetObjectOutput, primitives::ByteStream};
+use axum::body::Bytes;
+use loco_rs::prelude::*;
+use sea_orm::TransactionTrait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::client::{Position, get_garage, get_r2, r2_original_key},
+    controllers::view::image_url,
+    models::{_entities::images::Location, images, pending_removals},
+    workers::{regenerator, remover},
+};
+
+/// Of the derivatives encoded again once an image is back in Garage.
+const REGENERATE_QUALITY: u8 = 80;
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {
+    pub image_ids: Vec<i32>,
+    pub to: Location,
+}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "Relocator".to_string()
+    }
+
+    /// Moves the images to `to`, one at a time. Images already there or
+    /// deleted meanwhile are skipped, a failed image doesn't stop the rest.
+    async fn perform(&self, args: WorkerArgs) -> Result<()> {
+        let mut moved = 0;
+        let mut failed = 0;
+        for id in args.image_ids {
+            let image = match images::Model::find_by_id(&self.ctx.db, id).await {
+                Ok(image) => image,
+                Err(ModelError::EntityNotFound) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if image.location == args.to {
+                continue;
+            }
+
+            match relocate(&self.ctx, image, args.to).await {
+                Ok(_) => moved += 1,
+                Err(e) => {
+                    tracing::error!(image_id = id, "Failed to move image: {}", e);
+                    failed += 1;
+                }
+            }
+        }
+        tracing::info!("Moved {} images, {} failed", moved, failed);
+
+        if failed > 0 {
+            return Err(Error::string(&format!("{} images failed to move", failed)));
+        }
+        Ok(())
+    }
+}
+
+/// Copies the image's objects to `to`, checks the copies and points the row
+/// at them. The source objects are only removed once the row is updated.
+///
+/// R2 keeps a single object served under `file_name`, plus the original
+/// under `originals/` when there is one. Coming back to Garage, that object
+/// serves as both the AVIF and the preview until the derivatives are encoded
+/// again from the original, right after the move.
+///
+/// # Errors
+///
+/// When a copy or the update fails, the image is left where it was. When the
+/// derivatives can't be encoded or the source objects removed, the image has
+/// moved, and a failed removal is kept for the purger
+pub async fn relocate(
+    ctx: &AppContext,
+    image: images::Model,
+    to: Location,
+) -> std::result::Result<images::Model, String> {
+    if image.location == to {
+        return Ok(image);
+    }
+    let original_key = image.original_key();
+
+    match to {
+        Location::R2 => copy_to_r2(&image.file_name, &original_key).await?,
+        Location::Local => copy_to_garage(&image.file_name, &original_key).await?,
+    }
+
+    let url = image_url(ctx, to, &image.file_name).await;
+    let txn = ctx.db.begin().await.map_err(|e| e.to_string())?;
+    let source = pending_removals::Model::create(&txn, &image)
+        .await
+        .map_err(|e| e.to_string())?;
+    let image = image
+        .into_active_model()
+        .set_location(&txn, to, url)
+        .await
+        .map_err(|e| e.to_string())?;
+    txn.commit().await.map_err(|e| e.to_string())?;
+
+    // in place, a move run from the CLI would exit before a queued job
+    let regenerated = match to {
+        Location::Local => regenerator::regenerate(ctx, &image, REGENERATE_QUALITY)
+            .await
+            .map(|_| ()),
+        Location::R2 => Ok(()),
+    };
+    let removed = remover::remove(&ctx.db, source).await;
+    if let Err(e) = regenerated {
+        return Err(format!("moved, but failed to encode derivatives: {e}"));
+    }
+    if let Err(e) = removed {
+        return Err(format!("moved, but failed to remove source objects: {e}"));
+    }
+
+    images::Model::find_by_id(&ctx.db, image.id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn copy_to_r2(file_name: &str, original_key: &str) -> std::result::Result<(), String> {
+    let garage = get_garage();
+    let r2 = get_r2();
+
+    let output = garage
+        .get_object(file_name, Position::Avif)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (body, content_type) = read(output).await?;
+    r2.put_object(file_name, ByteStream::from(body.clone()), &content_type)
+        .await
+        .map_err(|e| e.to_string())?;
+    let head = r2.head_object(file_name).await.map_err(|e| e.to_string())?;
+    verify(file_name, head.content_length(), &body)?;
+
+    let output = match garage.get_object(original_key, Position::Original).await {
+        Ok(output) => output,
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let key = r2_original_key(original_key);
+    let (body, content_type) = read(output).await?;
+    r2.put_object(&key, ByteStream::from(body.clone()), &content_type)
+        .await
+        .map_err(|e| e.to_string())?;
+    let head = r2.head_object(&key).await.map_err(|e| e.to_string())?;
+    verify(&key, head.content_length(), &body)
+}
+
+async fn copy_to_garage(file_name: &str, original_key: &str) -> std::result::Result<(), String> {
+    let r2 = get_r2();
+
+    let output = r2.get_object(file_name).await.map_err(|e| e.to_string())?;
+    let (body, content_type) = read(output).await?;
+    for position in [Position::Avif, Position::Preview] {
+        put_garage(file_name, &body, &content_type, position).await?;
+    }
+
+    let key = r2_original_key(original_key);
+    let output = match r2.get_object(&key).await {
+        Ok(output) => output,
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let (body, content_type) = read(output).await?;
+    put_garage(original_key, &body, &content_type, Position::Original).await
+}
+
+async fn put_garage(
+    key: &str,
+    body: &Bytes,
+    content_type: &str,
+    position: Position,
+) -> std::result::Result<(), String> {
+    let garage = get_garage();
+
+    garage
+        .pub_object(key, ByteStream::from(body.clone()), content_type, position)
+        .await
+        .map_err(|e| e.to_string())?;
+    let head = garage
+        .head_object(key, position)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    verify(key, head.content_length(), body)
+}
+
+async fn read(output: GetObjectOutput) -> std::result::Result<(Bytes, String), String> {
+    let content_type = output
+        .content_type()
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let body = output
+        .body
+        .collect()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_bytes();
+
+    Ok((body, content_type))
+}
+
+fn verify(key: &str, stored: Option<i64>, body: &Bytes) -> std::result::Result<(), String> {
+    if stored != i64::try_from(body.len()).ok() {
+        return Err(format!(
+            "copy of {} has {:?} bytes, expected {}",
+            key,
+            stored,
+            body.len()
+        ));
+    }
+
+    Ok(())
+}
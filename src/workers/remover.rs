@@ -0,0 +1,133 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::client::{Position, get_garage, get_r2, r2_original_key},
+    models::{_entities::images::Location, pending_removals},
+};
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {
+    /// The pending removal listing the objects.
+    pub removal_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "Remover".to_string()
+    }
+
+    /// Removes the objects of a pending removal, see [`remove`]. A failed run
+    /// stays recorded and is retried by the purger.
+    async fn perform(&self, args: WorkerArgs) -> Result<()> {
+        let removal = match pending_removals::Model::find_by_id(&self.ctx.db, args.removal_id).await
+        {
+            Ok(removal) => removal,
+            // done by an earlier run
+            Err(ModelError::EntityNotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        remove(&self.ctx.db, removal)
+            .await
+            .map_err(|e| Error::string(&e))
+    }
+}
+
+/// Hands a recorded removal to the worker. One that can't be enqueued is
+/// still picked up by the purger.
+pub async fn enqueue(ctx: &AppContext, removal: &pending_removals::Model) {
+    let args = WorkerArgs {
+        removal_id: removal.id,
+    };
+    if let Err(e) = Worker::perform_later(ctx, args).await {
+        tracing::error!(
+            removal_id = removal.id,
+            "Failed to run remover task, left to the purger: {}",
+            e
+        );
+    }
+}
+
+/// Removes every stored object of a pending removal and drops it. On failure
+/// it is kept with its next attempt put off.
+///
+/// Deleting a missing key succeeds on S3, so a removal is retried as a whole.
+///
+/// # Errors
+///
+/// When an object can't be removed or the removal can't be updated
+pub async fn remove(
+    db: &DatabaseConnection,
+    removal: pending_removals::Model,
+) -> std::result::Result<(), String> {
+    let result = match removal.location {
+        Location::Local => remove_local(&removal.file_name, &removal.original_key).await,
+        Location::R2 => remove_r2(&removal.file_name, &removal.original_key).await,
+    };
+
+    match result {
+        Ok(()) => {
+            tracing::debug!(file_name = removal.file_name, "removed stored objects");
+            removal.delete(db).await.map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!(
+                file_name = removal.file_name,
+                attempts = removal.attempts,
+                "Failed to remove stored objects: {}",
+                e
+            );
+            removal
+                .into_active_model()
+                .retry_later(db, e.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            Err(e)
+        }
+    }
+}
+
+async fn remove_local(file_name: &str, original_key: &str) -> std::result::Result<(), String> {
+    let client = get_garage();
+
+    client
+        .delete_object(original_key, Position::Original)
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .delete_object(file_name, Position::Preview)
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .delete_object(file_name, Position::Avif)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+async fn remove_r2(file_name: &str, original_key: &str) -> std::result::Result<(), String> {
+    let client = get_r2();
+
+    client
+        .delete_object(file_name)
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .delete_object(&r2_original_key(original_key))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
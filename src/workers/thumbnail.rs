@@ -7,7 +7,11 @@ use ravif::{Encoder, Img};
 use rgb::FromSlice;
 use serde::{Deserialize, Serialize};
 
-use crate::{common::client::get_garage, controllers::upload::TempFileGuard};
+use crate::{
+    common::client::{Position, get_garage, get_r2, r2_original_key},
+    controllers::upload::{TEMP_DIR, TempFileGuard},
+    models::{_entities::images::Location, images},
+};
 
 pub struct Worker {
     pub ctx: AppContext,
@@ -19,6 +23,10 @@ pub struct WorkerArgs {
     pub tmp_file_guard: TempFileGuard,
     pub preview_key: String,
     pub quality: u8,
+    /// Key to keep the file under in the origin bucket, `None` when it is
+    /// already there.
+    #[serde(default)]
+    pub original_key: Option<String>,
 }
 
 #[async_trait]
@@ -57,17 +65,34 @@ impl BackgroundWorker<WorkerArgs> for Worker {
     /// # Returns
     /// * `Result<()>` - Ok if the job completed successfully, Err otherwise
     async fn perform(&self, args: WorkerArgs) -> Result<()> {
-        println!("=================Thumbnail=======================");
+        tracing::debug!(key = %args.preview_key, "encoding thumbnail");
         let client = get_garage();
         let tmp_file_path = args.tmp_file_guard.0.clone();
         let preview_name = args.preview_key;
 
-        let result = async move {
+        let result = async {
+            if let Some(original_key) = &args.original_key {
+                let body = ByteStream::from_path(&tmp_file_path)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let content_type = mime_guess2::from_path(original_key).first_or_octet_stream();
+                client
+                    .pub_object(
+                        original_key,
+                        body,
+                        content_type.as_ref(),
+                        crate::common::client::Position::Original,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+
             let (thumbnail_data, avif_data) =
                 tokio::task::spawn_blocking(move || process_thumbnail(tmp_file_path, args.quality))
                     .await
                     .map_err(|e| e.to_string())?
                     .map_err(|e| e.to_string())?;
+            let size = thumbnail_data.len() + avif_data.len();
             let body = ByteStream::from(thumbnail_data);
             let avif_body = ByteStream::from(avif_data);
 
@@ -89,13 +114,20 @@ impl BackgroundWorker<WorkerArgs> for Worker {
                     crate::common::client::Position::Avif,
                 )
                 .await
-                .map_err(|e| e.to_string())
+                .map_err(|e| e.to_string())?;
+
+            Ok::<_, String>(i64::try_from(size).unwrap_or(i64::MAX))
         };
 
+        // the block only runs when awaited, keep the file until then
+        let result = result.await;
         drop(args.tmp_file_guard);
 
-        match result.await {
-            Ok(_) => Ok(()),
+        match result {
+            Ok(size) => {
+                images::Model::add_size(&self.ctx.db, &preview_name, size).await?;
+                Ok(())
+            }
             Err(e) => {
                 tracing::error!("Failed to upload thumbnail: {}", e);
                 Err(Error::InternalServerError)
@@ -104,7 +136,61 @@ impl BackgroundWorker<WorkerArgs> for Worker {
     }
 }
 
-fn process_thumbnail(file_path: PathBuf, quality: u8) -> Result<(Vec<u8>, Vec<u8>), String> {
+/// Downloads the image's original from the origin bucket, or from under
+/// `originals/` in R2, into the upload temp dir to encode its derivatives
+/// again. Returns `None` when the original wasn't kept.
+///
+/// # Errors
+///
+/// When the original can't be read or written to the temp dir
+pub async fn args_from_original(
+    image: &images::Model,
+    quality: u8,
+) -> std::result::Result<Option<WorkerArgs>, String> {
+    let original_key = image.original_key();
+    let output = match image.location {
+        Location::Local => {
+            get_garage()
+                .get_object(&original_key, Position::Original)
+                .await
+        }
+        Location::R2 => get_r2().get_object(&r2_original_key(&original_key)).await,
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    tokio::fs::create_dir_all(TEMP_DIR)
+        .await
+        .map_err(|e| e.to_string())?;
+    let tmp_path = PathBuf::from(TEMP_DIR).join(&original_key);
+    let tmp_file_guard = TempFileGuard(tmp_path.clone());
+    let body = output
+        .body
+        .collect()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_bytes();
+    tokio::fs::write(&tmp_path, body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(WorkerArgs {
+        tmp_file_guard,
+        preview_key: image.file_name.clone(),
+        quality: quality.min(100),
+        original_key: None,
+    }))
+}
+
+/// Encodes the preview and the full size AVIF of an image file.
+///
+/// # Errors
+///
+/// When the file can't be decoded or encoded
+pub fn process_thumbnail(file_path: PathBuf, quality: u8) -> Result<(Vec<u8>, Vec<u8>), String> {
     let img = ImageReader::open(file_path)
         .map_err(|e| e.to_string())?
         .with_guessed_format()
@@ -0,0 +1,84 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::settings::SettingsService,
+    models::{_entities::images::Location, images},
+    workers::relocator::relocate,
+};
+
+/// How long an image that failed to move is left out of tiering.
+const RETRY_AFTER_HOURS: i64 = 24;
+
+pub struct Worker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WorkerArgs {}
+
+#[async_trait]
+impl BackgroundWorker<WorkerArgs> for Worker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    fn class_name() -> String {
+        "Tiering".to_string()
+    }
+
+    /// Moves one batch of cold images from Garage to R2, following the
+    /// tiering policy in settings. Images that fail are recorded and left
+    /// out of the batches for a while, so they don't hold up the rest.
+    async fn perform(&self, _args: WorkerArgs) -> Result<()> {
+        let policy = SettingsService::tiering_policy().await;
+        if policy.after_days == 0 {
+            return Ok(());
+        }
+        if SettingsService::r2_bucket_name().await.is_empty() {
+            tracing::warn!("Tiering is on but R2 is not configured");
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now();
+        let days = |days: u64| chrono::Days::new(days);
+        let created_before = now
+            .checked_sub_days(days(policy.after_days))
+            .unwrap_or(now)
+            .fixed_offset();
+        let since = now
+            .date_naive()
+            .checked_sub_days(days(policy.window_days))
+            .unwrap_or(now.date_naive());
+
+        let ids = images::Model::find_cold_ids(
+            &self.ctx.db,
+            created_before,
+            since,
+            policy.max_views,
+            (now - chrono::Duration::hours(RETRY_AFTER_HOURS)).fixed_offset(),
+            policy.batch_size,
+        )
+        .await?;
+
+        let (mut moved, mut failed) = (0, 0);
+        for id in ids {
+            let image = match images::Model::find_by_id(&self.ctx.db, id).await {
+                Ok(image) => image,
+                Err(ModelError::EntityNotFound) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            match relocate(&self.ctx, image, Location::R2).await {
+                Ok(_) => moved += 1,
+                Err(e) => {
+                    tracing::error!(image_id = id, "Failed to move image to R2: {}", e);
+                    images::Model::set_tier_failed(&self.ctx.db, id).await?;
+                    failed += 1;
+                }
+            }
+        }
+        tracing::info!("Moved {} cold images to R2, {} failed", moved, failed);
+
+        Ok(())
+    }
+}
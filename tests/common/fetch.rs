@@ -0,0 +1,140 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use AetherPix::common::{
+    fetch::{FetchError, Fetcher, is_blocked},
+    settings::UrlUploadPolicy,
+};
+use axum::{Router, response::Redirect, routing::get};
+
+const LOOPBACK: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+/// Serves a small image and a few redirects on a random loopback port.
+async fn stand_in() -> SocketAddr {
+    let app = Router::new()
+        .route(
+            "/cat.png",
+            get(|| async { ([("content-type", "image/png")], vec![0u8; 64]) }),
+        )
+        .route("/big", get(|| async { vec![0u8; 4096] }))
+        .route("/hop", get(|| async { Redirect::temporary("/cat.png") }))
+        .route("/loop", get(|| async { Redirect::temporary("/loop") }))
+        .route(
+            "/metadata",
+            get(|| async { Redirect::temporary("http://169.254.169.254/latest/meta-data") }),
+        );
+    let listener = tokio::net::TcpListener::bind((LOOPBACK, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    addr
+}
+
+fn policy(allowed_ips: Vec<IpAddr>) -> UrlUploadPolicy {
+    UrlUploadPolicy {
+        max_bytes: 1024,
+        timeout: Duration::from_secs(5),
+        max_redirects: 3,
+        allowed_ips,
+    }
+}
+
+async fn body(url: &str, allowed_ips: Vec<IpAddr>) -> Result<Vec<u8>, FetchError> {
+    let mut download = Fetcher::new(&policy(allowed_ips))?.get(url).await?;
+    let mut body = Vec::new();
+    while let Some(chunk) = download.chunk().await? {
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+#[test]
+fn blocks_non_public_addresses() {
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "fe80::1",
+        "fd00::1",
+        "::ffff:127.0.0.1",
+        "64:ff9b::a9fe:a9fe",
+    ] {
+        assert!(is_blocked(ip.parse().unwrap()), "{ip} should be blocked");
+    }
+    assert!(!is_blocked(IpAddr::V4(Ipv4Addr::new(93, 184, 215, 14))));
+    assert!(!is_blocked(IpAddr::V6(Ipv6Addr::new(
+        0x2606, 0x4700, 0, 0, 0, 0, 0, 0x1111
+    ))));
+}
+
+#[tokio::test]
+async fn fetches_from_allowed_address() {
+    let addr = stand_in().await;
+
+    let mut download = Fetcher::new(&policy(vec![LOOPBACK]))
+        .unwrap()
+        .get(&format!("http://{addr}/hop"))
+        .await
+        .unwrap();
+    assert_eq!(download.url().path(), "/cat.png");
+    assert_eq!(download.content_type(), Some("image/png"));
+    assert_eq!(
+        download.chunk().await.unwrap().map(|chunk| chunk.len()),
+        Some(64)
+    );
+}
+
+#[tokio::test]
+async fn rejects_loopback_unless_allowed() {
+    let addr = stand_in().await;
+
+    for url in [
+        format!("http://{addr}/cat.png"),
+        format!("http://localhost:{}/cat.png", addr.port()),
+    ] {
+        assert!(
+            matches!(body(&url, vec![]).await, Err(FetchError::Blocked)),
+            "{url}"
+        );
+    }
+    assert!(matches!(
+        body("http://[::1]/cat.png", vec![]).await,
+        Err(FetchError::Blocked)
+    ));
+    assert!(matches!(
+        body("file:///etc/passwd", vec![]).await,
+        Err(FetchError::InvalidUrl)
+    ));
+}
+
+#[tokio::test]
+async fn rechecks_redirects() {
+    let addr = stand_in().await;
+
+    assert!(matches!(
+        body(&format!("http://{addr}/metadata"), vec![LOOPBACK]).await,
+        Err(FetchError::Blocked)
+    ));
+    assert!(matches!(
+        body(&format!("http://{addr}/loop"), vec![LOOPBACK]).await,
+        Err(FetchError::TooManyRedirects)
+    ));
+}
+
+#[tokio::test]
+async fn rejects_large_bodies() {
+    let addr = stand_in().await;
+
+    assert!(matches!(
+        body(&format!("http://{addr}/big"), vec![LOOPBACK]).await,
+        Err(FetchError::TooLarge)
+    ));
+}
@@ -0,0 +1,3 @@
+mod fetch;
+mod queue;
+mod zip;
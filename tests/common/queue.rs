@@ -0,0 +1,51 @@
+use AetherPix::common::queue;
+use loco_rs::{
+    bgworker::{Queue, sqlt},
+    config::SqliteQueueConfig,
+};
+use sea_orm::sqlx;
+
+async fn sqlite_queue() -> Queue {
+    let config: SqliteQueueConfig = serde_json::from_value(serde_json::json!({
+        "uri": "sqlite::memory:",
+        "min_connections": 1,
+        "max_connections": 1,
+    }))
+    .unwrap();
+    let queue = sqlt::create_provider(&config).await.unwrap();
+    queue.setup().await.unwrap();
+    queue
+}
+
+#[tokio::test]
+async fn counts_jobs_by_status() {
+    let queue = sqlite_queue().await;
+    let stats = queue::stats(&queue).await.unwrap();
+    assert_eq!((stats.queued, stats.processing, stats.failed), (0, 0, 0));
+
+    for _ in 0..3 {
+        queue
+            .enqueue("Worker".to_string(), None, serde_json::json!({}), None)
+            .await
+            .unwrap();
+    }
+    let Queue::Sqlite(pool, ..) = &queue else {
+        unreachable!()
+    };
+    sqlx::query(
+        "UPDATE sqlt_loco_queue SET status = 'failed' \
+         WHERE id = (SELECT id FROM sqlt_loco_queue LIMIT 1)",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let stats = queue::stats(&queue).await.unwrap();
+    assert_eq!((stats.queued, stats.processing, stats.failed), (2, 0, 1));
+}
+
+#[tokio::test]
+async fn counts_nothing_without_a_queue() {
+    let stats = queue::stats(&Queue::None).await.unwrap();
+    assert_eq!((stats.queued, stats.processing, stats.failed), (0, 0, 0));
+}
@@ -0,0 +1,278 @@
+use std::{
+    io::{Cursor, Write},
+    path::PathBuf,
+};
+
+use AetherPix::common::zip::{ZipReader, ZipWriter};
+use flate2::{Compression, write::DeflateEncoder};
+
+async fn read_all(archive: Vec<u8>, max_entries: usize) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let mut zip = ZipReader::new(Cursor::new(archive), max_entries).await?;
+    let mut files = Vec::new();
+    for entry in zip.entries().to_vec() {
+        let mut reader = zip.open(&entry).await?;
+        let mut data = Vec::new();
+        while let Some(chunk) = reader.chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+        files.push((entry.name, data));
+    }
+
+    Ok(files)
+}
+
+/// An archive of `tests/fixtures/zip`, made by the archiver in its name from
+/// `photos/cat.png`, `photos/trip/beach.jpg` and `notes.txt`.
+fn fixture(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/zip")
+        .join(name);
+
+    std::fs::read(path).unwrap()
+}
+
+/// The files by name, leaving out folders.
+async fn read_files(archive: Vec<u8>) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files: Vec<_> = read_all(archive, 10)
+        .await?
+        .into_iter()
+        .filter(|(name, _)| !name.ends_with('/'))
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+fn expected_files() -> Vec<(String, Vec<u8>)> {
+    vec![
+        ("notes.txt".to_string(), b"hello\n".to_vec()),
+        (
+            "photos/cat.png".to_string(),
+            (0..=255u8).collect::<Vec<_>>().repeat(40),
+        ),
+        (
+            "photos/trip/beach.jpg".to_string(),
+            b"not really a picture ".repeat(500),
+        ),
+    ]
+}
+
+/// A single deflated entry claiming to be `declared` bytes.
+fn deflated(name: &str, data: &[u8], declared: u32) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let crc = crc32fast::hash(data);
+    let name_len = u16::try_from(name.len()).unwrap();
+    let compressed_len = u32::try_from(compressed.len()).unwrap();
+
+    let mut archive = Vec::new();
+    archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&[20, 0, 0, 0, 8, 0, 0, 0, 0, 0]);
+    archive.extend_from_slice(&crc.to_le_bytes());
+    archive.extend_from_slice(&compressed_len.to_le_bytes());
+    archive.extend_from_slice(&declared.to_le_bytes());
+    archive.extend_from_slice(&name_len.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive.extend_from_slice(name.as_bytes());
+    archive.extend_from_slice(&compressed);
+
+    let start = u32::try_from(archive.len()).unwrap();
+    archive.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&[20, 0, 20, 0, 0, 0, 8, 0, 0, 0, 0, 0]);
+    archive.extend_from_slice(&crc.to_le_bytes());
+    archive.extend_from_slice(&compressed_len.to_le_bytes());
+    archive.extend_from_slice(&declared.to_le_bytes());
+    archive.extend_from_slice(&name_len.to_le_bytes());
+    archive.extend_from_slice(&[0; 12]);
+    archive.extend_from_slice(&0u32.to_le_bytes());
+    archive.extend_from_slice(name.as_bytes());
+    let size = u32::try_from(archive.len()).unwrap() - start;
+
+    archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+    archive.extend_from_slice(&size.to_le_bytes());
+    archive.extend_from_slice(&start.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+
+    archive
+}
+
+#[tokio::test]
+async fn reads_what_the_writer_wrote() {
+    let mut zip = ZipWriter::new(Vec::new());
+    for (name, data) in [("a.png", &b"first"[..]), ("dir/b.jpg", &[7u8; 100_000][..])] {
+        let mut entry = zip.start_entry(name, chrono::Utc::now()).await.unwrap();
+        entry.write(data).await.unwrap();
+        entry.close().await.unwrap();
+    }
+    let archive = zip.finish().await.unwrap();
+
+    let files = read_all(archive, 10).await.unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0], ("a.png".to_string(), b"first".to_vec()));
+    assert_eq!(files[1].0, "dir/b.jpg");
+    assert_eq!(files[1].1, vec![7u8; 100_000]);
+}
+
+#[tokio::test]
+async fn inflates_deflated_entries() {
+    let data = b"not really a picture ".repeat(1000);
+    let archive = deflated("c.gif", &data, u32::try_from(data.len()).unwrap());
+
+    let files = read_all(archive, 10).await.unwrap();
+    assert_eq!(files, vec![("c.gif".to_string(), data)]);
+}
+
+#[tokio::test]
+async fn rejects_entries_larger_than_declared() {
+    let bomb = vec![0u8; 10 * 1024 * 1024];
+    let archive = deflated("bomb.png", &bomb, 1024);
+
+    assert!(read_all(archive, 10).await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_too_many_entries() {
+    let mut zip = ZipWriter::new(Vec::new());
+    for i in 0..3 {
+        zip.start_entry(&format!("{i}.png"), chrono::Utc::now())
+            .await
+            .unwrap()
+            .close()
+            .await
+            .unwrap();
+    }
+    let archive = zip.finish().await.unwrap();
+
+    assert!(ZipReader::new(Cursor::new(archive), 2).await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_other_files() {
+    assert!(
+        ZipReader::new(Cursor::new(b"not a zip".to_vec()), 10)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn reads_info_zip_archives() {
+    for name in ["infozip.zip", "infozip-stored.zip", "infozip-streamed.zip"] {
+        let files = read_files(fixture(name)).await.unwrap();
+        assert_eq!(files, expected_files(), "{name}");
+    }
+}
+
+#[tokio::test]
+async fn reads_streamed_archives_with_macos_folder() {
+    // data descriptors after deflated entries and trailing padding, as
+    // streaming archivers like macOS' Archive Utility write them
+    let files = read_files(fixture("libarchive-streamed.zip"))
+        .await
+        .unwrap();
+
+    let (resource_fork, files): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|(name, _)| name.starts_with("__MACOSX/"));
+    assert_eq!(files, expected_files());
+    assert_eq!(resource_fork.len(), 1);
+}
+
+#[tokio::test]
+async fn reads_zip64_archives() {
+    let files = read_files(fixture("python-zip64.zip")).await.unwrap();
+
+    assert_eq!(files, expected_files());
+}
+
+#[tokio::test]
+async fn rejects_encrypted_entries() {
+    let mut zip = ZipReader::new(Cursor::new(fixture("infozip-encrypted.zip")), 10)
+        .await
+        .unwrap();
+
+    for entry in zip.entries().to_vec() {
+        let read = async {
+            let mut reader = zip.open(&entry).await?;
+            while reader.chunk().await?.is_some() {}
+            Ok::<_, std::io::Error>(())
+        };
+        assert!(read.await.is_err(), "{}", entry.name);
+    }
+}
+
+#[tokio::test]
+async fn rejects_unsupported_compression() {
+    assert!(read_all(fixture("infozip-bzip2.zip"), 10).await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_truncated_and_corrupted_archives() {
+    let archive = fixture("infozip.zip");
+
+    let truncated = archive[..archive.len() - 30].to_vec();
+    assert!(read_all(truncated, 10).await.is_err());
+
+    // a byte of the first file's data, right after its local header
+    let mut corrupted = archive;
+    let name_len = usize::from(u16::from_le_bytes([corrupted[26], corrupted[27]]));
+    let extra_len = usize::from(u16::from_le_bytes([corrupted[28], corrupted[29]]));
+    corrupted[30 + name_len + extra_len] ^= 0xff;
+    assert!(read_all(corrupted, 10).await.is_err());
+}
+
+#[tokio::test]
+async fn counts_entries_of_zip64_directories() {
+    // more entries than the classic end of central directory can hold
+    let count = usize::from(u16::MAX) + 10;
+    let mut zip = ZipWriter::new(Vec::new());
+    for i in 0..count {
+        zip.start_entry(&format!("{i}"), chrono::Utc::now())
+            .await
+            .unwrap()
+            .close()
+            .await
+            .unwrap();
+    }
+    let archive = zip.finish().await.unwrap();
+
+    let zip = ZipReader::new(Cursor::new(archive.clone()), count)
+        .await
+        .unwrap();
+    assert_eq!(zip.entries().len(), count);
+    assert!(
+        ZipReader::new(Cursor::new(archive), count - 1)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+#[ignore = "writes over 4 GiB to the temp dir"]
+async fn reads_archives_over_4_gib() {
+    let path = std::env::temp_dir().join("zip64-over-4gib.zip");
+    let file = tokio::fs::File::create(&path).await.unwrap();
+    let mut zip = ZipWriter::new(tokio::io::BufWriter::new(file));
+    let chunk = vec![0u8; 1024 * 1024];
+    for name in ["a.bin", "b.bin"] {
+        let mut entry = zip.start_entry(name, chrono::Utc::now()).await.unwrap();
+        for _ in 0..2100 {
+            entry.write(&chunk).await.unwrap();
+        }
+        entry.close().await.unwrap();
+    }
+    let mut entry = zip.start_entry("c.png", chrono::Utc::now()).await.unwrap();
+    entry.write(b"last").await.unwrap();
+    entry.close().await.unwrap();
+    zip.finish().await.unwrap();
+
+    let file = tokio::fs::File::open(&path).await.unwrap();
+    let mut zip = ZipReader::new(file, 10).await.unwrap();
+    let entry = zip.entries()[2].clone();
+    let mut reader = zip.open(&entry).await.unwrap();
+    assert_eq!(reader.chunk().await.unwrap(), Some(b"last".to_vec()));
+    assert_eq!(reader.chunk().await.unwrap(), None);
+    std::fs::remove_file(path).unwrap();
+}
@@ -1,4 +1,6 @@
+mod common;
 mod models;
 mod requests;
+mod support;
 mod tasks;
 mod workers;
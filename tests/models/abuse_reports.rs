@@ -0,0 +1,117 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::abuse_reports::ReportStatus,
+        abuse_reports::{Model, ReportParams},
+        images,
+    },
+};
+use loco_rs::{app::AppContext, model::ModelError, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+
+use crate::support::{self, USER1_PID};
+
+async fn report(ctx: &AppContext, image_id: i32, reason: &str) -> Model {
+    let params = ReportParams {
+        name: String::new(),
+        reason: format!(" {reason} "),
+        contact: " someone@example.com ".to_string(),
+    };
+
+    Model::create(&ctx.db, image_id, &params).await.unwrap()
+}
+
+fn ids(reports: &[(Model, images::Model)]) -> Vec<i32> {
+    reports.iter().map(|(report, _)| report.id).collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn creates_open_reports() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+
+    let created = report(ctx, image.id, "spam").await;
+
+    assert_eq!(created.reason, "spam");
+    assert_eq!(created.contact, "someone@example.com");
+    assert_eq!(created.status, ReportStatus::Open);
+    assert_eq!(created.resolved_at, None);
+    let found = Model::find_by_id(&ctx.db, created.id).await.unwrap();
+    assert_eq!(found.id, created.id);
+    assert!(matches!(
+        Model::find_by_id(&ctx.db, created.id + 1).await,
+        Err(ModelError::EntityNotFound)
+    ));
+}
+
+#[tokio::test]
+#[serial]
+async fn lists_by_status_oldest_first() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    let first = report(ctx, image.id, "spam").await;
+    let second = report(ctx, image.id, "copyright").await;
+    let third = report(ctx, image.id, "abuse").await;
+
+    let (open, pages) = Model::find_by_status(&ctx.db, ReportStatus::Open, 0, 2)
+        .await
+        .unwrap();
+    assert_eq!(ids(&open), vec![first.id, second.id]);
+    assert_eq!(open[0].1.id, image.id);
+    assert_eq!(pages.number_of_items, 3);
+    assert_eq!(pages.number_of_pages, 2);
+
+    let (open, _) = Model::find_by_status(&ctx.db, ReportStatus::Open, 1, 2)
+        .await
+        .unwrap();
+    assert_eq!(ids(&open), vec![third.id]);
+
+    let dismissed = second
+        .clone()
+        .into_active_model()
+        .resolve(&ctx.db, ReportStatus::Dismissed)
+        .await
+        .unwrap();
+    assert!(dismissed.resolved_at.is_some());
+    let (dismissed, _) = Model::find_by_status(&ctx.db, ReportStatus::Dismissed, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(ids(&dismissed), vec![second.id]);
+}
+
+#[tokio::test]
+#[serial]
+async fn resolves_open_reports_of_image() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    let other = support::create_image(ctx, Some(USER1_PID)).await;
+    let dismissed = report(ctx, image.id, "spam")
+        .await
+        .into_active_model()
+        .resolve(&ctx.db, ReportStatus::Dismissed)
+        .await
+        .unwrap();
+    let open = report(ctx, image.id, "copyright").await;
+    let kept = report(ctx, other.id, "abuse").await;
+
+    Model::resolve_by_image_id(&ctx.db, image.id, ReportStatus::TakenDown)
+        .await
+        .unwrap();
+
+    let open = Model::find_by_id(&ctx.db, open.id).await.unwrap();
+    assert_eq!(open.status, ReportStatus::TakenDown);
+    assert!(open.resolved_at.is_some());
+    // already closed ones and those of other images stay as they were
+    let dismissed = Model::find_by_id(&ctx.db, dismissed.id).await.unwrap();
+    assert_eq!(dismissed.status, ReportStatus::Dismissed);
+    let kept = Model::find_by_id(&ctx.db, kept.id).await.unwrap();
+    assert_eq!(kept.status, ReportStatus::Open);
+}
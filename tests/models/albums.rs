@@ -0,0 +1,159 @@
+use AetherPix::{
+    app::App,
+    models::albums::{CreateParams, Model},
+};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID};
+
+async fn create_album(ctx: &AppContext) -> Model {
+    let params = CreateParams {
+        name: " Trip ".to_string(),
+        description: None,
+        public: None,
+    };
+
+    Model::create(&ctx.db, Uuid::parse_str(USER1_PID).unwrap(), &params)
+        .await
+        .unwrap()
+}
+
+async fn image_ids(ctx: &AppContext, album: &Model) -> Vec<i32> {
+    album
+        .images(&ctx.db, false)
+        .await
+        .unwrap()
+        .iter()
+        .map(|image| image.id)
+        .collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn can_create() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+
+    let album = create_album(&boot.app_context).await;
+
+    assert_eq!(album.name, "Trip");
+    assert!(!album.public);
+    assert_eq!(album.cover_image_id, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn appends_images_in_order() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let album = create_album(ctx).await;
+    let a = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let b = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let c = support::create_image(ctx, Some(USER1_PID)).await.id;
+
+    let added = album.add_images(&ctx.db, &[b, a, b]).await.unwrap();
+    assert_eq!(added, vec![b, a]);
+
+    // already there ones are skipped, new ones go last
+    let added = album.add_images(&ctx.db, &[a, c]).await.unwrap();
+    assert_eq!(added, vec![c]);
+    assert_eq!(image_ids(ctx, &album).await, vec![b, a, c]);
+}
+
+#[tokio::test]
+#[serial]
+async fn reorders_listed_images_first() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let album = create_album(ctx).await;
+    let a = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let b = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let c = support::create_image(ctx, Some(USER1_PID)).await.id;
+    album.add_images(&ctx.db, &[a, b, c]).await.unwrap();
+
+    album.reorder(&ctx.db, &[c, a]).await.unwrap();
+    assert_eq!(image_ids(ctx, &album).await, vec![c, a, b]);
+
+    // unknown ids are ignored
+    album.reorder(&ctx.db, &[b, 9999]).await.unwrap();
+    assert_eq!(image_ids(ctx, &album).await, vec![b, c, a]);
+}
+
+#[tokio::test]
+#[serial]
+async fn lists_public_images_only() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let album = create_album(ctx).await;
+    let shown = support::create_image(ctx, Some(USER1_PID)).await;
+    let hidden = support::create_image(ctx, Some(USER1_PID)).await;
+    hidden
+        .clone()
+        .into_active_model()
+        .set_public(&ctx.db, false)
+        .await
+        .unwrap();
+    album
+        .add_images(&ctx.db, &[hidden.id, shown.id])
+        .await
+        .unwrap();
+
+    let public: Vec<i32> = album
+        .images(&ctx.db, true)
+        .await
+        .unwrap()
+        .iter()
+        .map(|image| image.id)
+        .collect();
+
+    assert_eq!(public, vec![shown.id]);
+    assert_eq!(image_ids(ctx, &album).await, vec![hidden.id, shown.id]);
+}
+
+#[tokio::test]
+#[serial]
+async fn removing_cover_image_clears_cover() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let album = create_album(ctx).await;
+    let a = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let b = support::create_image(ctx, Some(USER1_PID)).await.id;
+    album.add_images(&ctx.db, &[a, b]).await.unwrap();
+
+    let album = album
+        .into_active_model()
+        .set_cover(&ctx.db, Some(b))
+        .await
+        .unwrap();
+    assert_eq!(album.cover_image_id, Some(b));
+
+    // the cover stays while another image goes
+    album.remove_images(&ctx.db, &[a]).await.unwrap();
+    let album = Model::find_by_pid_and_user_pid(
+        &ctx.db,
+        &album.pid.to_string(),
+        Uuid::parse_str(USER1_PID).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(album.cover_image_id, Some(b));
+    assert!(!album.contains_image(&ctx.db, a).await.unwrap());
+
+    album.remove_images(&ctx.db, &[b]).await.unwrap();
+    let album = Model::find_by_pid_and_user_pid(
+        &ctx.db,
+        &album.pid.to_string(),
+        Uuid::parse_str(USER1_PID).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(album.cover_image_id, None);
+    assert!(image_ids(ctx, &album).await.is_empty());
+}
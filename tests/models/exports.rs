@@ -0,0 +1,139 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::{
+            exports::{ExportStatus, ExportVariant},
+            images::Location,
+        },
+        exports::{ExportParams, Model, StoredExport},
+    },
+};
+use chrono::{TimeDelta, Utc};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{USER1_PID, USER2_PID};
+
+async fn create_export(ctx: &AppContext, user_pid: &str) -> Model {
+    Model::create(
+        &ctx.db,
+        Uuid::parse_str(user_pid).unwrap(),
+        &ExportParams::default(),
+    )
+    .await
+    .unwrap()
+}
+
+fn stored(expires_at: chrono::DateTime<Utc>) -> StoredExport {
+    StoredExport {
+        location: Location::R2,
+        file_key: "exports/a.zip".to_string(),
+        size: 100,
+        images: 2,
+        missing: 1,
+        expires_at: expires_at.into(),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn creates_queued_exports() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+
+    let export = create_export(&boot.app_context, USER1_PID).await;
+
+    assert_eq!(export.status, ExportStatus::Queued);
+    assert_eq!(export.variant, ExportVariant::Original);
+    assert_eq!(export.stored(), None);
+}
+
+#[tokio::test]
+#[serial]
+async fn finds_pending_exports_of_user() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let queued = create_export(ctx, USER1_PID).await;
+    let running = create_export(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .start(&ctx.db)
+        .await
+        .unwrap();
+    create_export(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .fail(&ctx.db, "boom".to_string())
+        .await
+        .unwrap();
+    create_export(ctx, USER2_PID).await;
+
+    let mut pending: Vec<i32> =
+        Model::find_pending_by_user_pid(&ctx.db, Uuid::parse_str(USER1_PID).unwrap())
+            .await
+            .unwrap()
+            .iter()
+            .map(|export| export.id)
+            .collect();
+    pending.sort_unstable();
+
+    assert_eq!(pending, vec![queued.id, running.id]);
+}
+
+#[tokio::test]
+#[serial]
+async fn goes_stale_without_progress() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let export = create_export(ctx, USER1_PID).await;
+
+    assert!(!export.is_stale((Utc::now() - TimeDelta::hours(1)).into()));
+    assert!(export.is_stale((Utc::now() + TimeDelta::hours(1)).into()));
+
+    // finished ones never are
+    let export = export
+        .into_active_model()
+        .fail(&ctx.db, "boom".to_string())
+        .await
+        .unwrap();
+    assert!(!export.is_stale((Utc::now() + TimeDelta::hours(1)).into()));
+}
+
+#[tokio::test]
+#[serial]
+async fn expires_ready_exports_once_link_ran_out() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let valid = create_export(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .finish(&ctx.db, stored(Utc::now() + TimeDelta::hours(1)))
+        .await
+        .unwrap();
+    let ran_out = create_export(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .finish(&ctx.db, stored(Utc::now() - TimeDelta::minutes(1)))
+        .await
+        .unwrap();
+
+    assert_eq!(valid.stored(), Some((Location::R2, "exports/a.zip")));
+    assert_eq!(ran_out.stored(), None);
+    let expired: Vec<i32> = Model::find_expired(&ctx.db, 10)
+        .await
+        .unwrap()
+        .iter()
+        .map(|export| export.id)
+        .collect();
+    assert_eq!(expired, vec![ran_out.id]);
+
+    let ran_out = ran_out.into_active_model().expire(&ctx.db).await.unwrap();
+    assert_eq!(ran_out.status, ExportStatus::Expired);
+    assert_eq!(ran_out.file_key, None);
+    assert!(Model::find_expired(&ctx.db, 10).await.unwrap().is_empty());
+}
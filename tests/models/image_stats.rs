@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use AetherPix::{
+    app::App,
+    models::image_stats::{Counts, Model, StatsScope},
+};
+use chrono::NaiveDate;
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID, USER2_PID};
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 7, day).unwrap()
+}
+
+fn counts(views: i64, bytes: i64) -> Counts {
+    Counts { views, bytes }
+}
+
+#[tokio::test]
+#[serial]
+async fn adds_counts_to_stored_ones() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+    let image = support::create_image(&boot.app_context, Some(USER1_PID))
+        .await
+        .id;
+
+    let batch = HashMap::from([
+        ((image, day(1), String::new()), counts(2, 200)),
+        ((image, day(1), "example.com".to_string()), counts(1, 100)),
+        ((image, day(2), "example.com".to_string()), counts(3, 300)),
+        // deleted meanwhile
+        ((9999, day(1), String::new()), counts(5, 500)),
+    ]);
+    Model::add_batch(db, &batch).await.unwrap();
+    let batch = HashMap::from([
+        ((image, day(1), String::new()), counts(1, 50)),
+        ((image, day(2), "other.org".to_string()), counts(1, 10)),
+    ]);
+    Model::add_batch(db, &batch).await.unwrap();
+
+    let daily = Model::daily(db, StatsScope::Image(image), day(1), day(31))
+        .await
+        .unwrap();
+    assert_eq!(daily, vec![(day(1), 4, 350), (day(2), 4, 310)]);
+    let daily = Model::daily(db, StatsScope::Image(9999), day(1), day(31))
+        .await
+        .unwrap();
+    assert!(daily.is_empty());
+
+    // direct requests have no referrer
+    let referrers = Model::top_referrers(db, StatsScope::Image(image), day(1), day(31), 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        referrers,
+        vec![("example.com".to_string(), 4), ("other.org".to_string(), 1)]
+    );
+    let referrers = Model::top_referrers(db, StatsScope::Image(image), day(2), day(2), 1)
+        .await
+        .unwrap();
+    assert_eq!(referrers, vec![("example.com".to_string(), 3)]);
+}
+
+#[tokio::test]
+#[serial]
+async fn sums_over_images_of_user() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let first = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let second = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let other = support::create_image(ctx, Some(USER2_PID)).await.id;
+
+    let batch = HashMap::from([
+        ((first, day(1), String::new()), counts(1, 10)),
+        ((second, day(1), String::new()), counts(2, 20)),
+        ((other, day(1), String::new()), counts(4, 40)),
+    ]);
+    Model::add_batch(&ctx.db, &batch).await.unwrap();
+
+    let user = StatsScope::User(Uuid::parse_str(USER1_PID).unwrap());
+    let daily = Model::daily(&ctx.db, user, day(1), day(1)).await.unwrap();
+    assert_eq!(daily, vec![(day(1), 3, 30)]);
+}
+
+#[tokio::test]
+#[serial]
+async fn adds_batches_past_the_bind_limit() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+    let image = support::create_image(&boot.app_context, Some(USER1_PID))
+        .await
+        .id;
+
+    // 5 binds per row, past the 32766 SQLite allows in one statement
+    let batch: HashMap<_, _> = (0..7000)
+        .map(|i| ((image, day(1), format!("site{i}.com")), counts(1, 10)))
+        .collect();
+    Model::add_batch(db, &batch).await.unwrap();
+
+    let daily = Model::daily(db, StatsScope::Image(image), day(1), day(1))
+        .await
+        .unwrap();
+    assert_eq!(daily, vec![(day(1), 7000, 70000)]);
+}
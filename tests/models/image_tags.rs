@@ -0,0 +1,52 @@
+use AetherPix::{
+    app::App,
+    models::{image_tags, tags},
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID};
+
+#[tokio::test]
+#[serial]
+async fn attaches_and_detaches_tags() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let owner = Uuid::parse_str(USER1_PID).unwrap();
+    let first = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let second = support::create_image(ctx, Some(USER1_PID)).await.id;
+    let names = ["dogs".to_string(), "cats".to_string()];
+    let ids: Vec<i32> = tags::Model::find_or_create(&ctx.db, owner, &names)
+        .await
+        .unwrap()
+        .iter()
+        .map(|t| t.id)
+        .collect();
+
+    image_tags::Model::attach(&ctx.db, first, &ids).await.unwrap();
+    // linking twice is a no-op
+    image_tags::Model::attach(&ctx.db, first, &ids).await.unwrap();
+    image_tags::Model::attach(&ctx.db, second, &ids[..1])
+        .await
+        .unwrap();
+
+    let names = tags::Model::names_by_image_ids(&ctx.db, &[first, second])
+        .await
+        .unwrap();
+    assert_eq!(names[&first], vec!["cats", "dogs"]);
+    assert_eq!(names[&second], vec!["dogs"]);
+    let counts = tags::Model::find_by_user_pid_with_count(&ctx.db, owner)
+        .await
+        .unwrap();
+    assert_eq!(counts, vec![("cats".to_string(), 1), ("dogs".to_string(), 2)]);
+
+    image_tags::Model::detach(&ctx.db, first, &ids[..1])
+        .await
+        .unwrap();
+    let names = tags::Model::names_by_image_ids(&ctx.db, &[first])
+        .await
+        .unwrap();
+    assert_eq!(names[&first], vec!["cats"]);
+}
@@ -0,0 +1,141 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::imports::{ImportStatus, ImportStructure},
+        imports::{ImportProgress, Model},
+    },
+};
+use chrono::{TimeDelta, Utc};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{USER1_PID, USER2_PID};
+
+async fn create_import(ctx: &AppContext, user_pid: &str) -> Model {
+    Model::create(
+        &ctx.db,
+        Uuid::parse_str(user_pid).unwrap(),
+        "photos.zip",
+        ImportStructure::Albums,
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn keeps_progress_of_imports() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+
+    let import = create_import(ctx, USER1_PID).await;
+    assert_eq!(import.status, ImportStatus::Queued);
+    assert_eq!(import.structure, ImportStructure::Albums);
+    assert_eq!(import.file_name, "photos.zip");
+
+    let import = import.into_active_model().start(&ctx.db, 3).await.unwrap();
+    assert_eq!((import.status, import.total), (ImportStatus::Running, 3));
+
+    let progress = ImportProgress {
+        imported: 1,
+        skipped: 1,
+        failed: 1,
+    };
+    let import = import
+        .into_active_model()
+        .progress(&ctx.db, progress)
+        .await
+        .unwrap();
+    assert_eq!((import.imported, import.skipped, import.failed), (1, 1, 1));
+
+    let import = import.into_active_model().finish(&ctx.db).await.unwrap();
+    assert_eq!(import.status, ImportStatus::Done);
+    assert!(import.finished_at.is_some());
+    assert_eq!(import.error, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn finds_imports_of_user_only() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let import = create_import(ctx, USER1_PID).await;
+
+    let user1 = Uuid::parse_str(USER1_PID).unwrap();
+    let user2 = Uuid::parse_str(USER2_PID).unwrap();
+    assert!(
+        Model::find_by_id_and_user_pid(&ctx.db, import.id, user1)
+            .await
+            .is_ok()
+    );
+    assert!(
+        Model::find_by_id_and_user_pid(&ctx.db, import.id, user2)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn finds_pending_imports_of_user() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let queued = create_import(ctx, USER1_PID).await;
+    let running = create_import(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .start(&ctx.db, 1)
+        .await
+        .unwrap();
+    create_import(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .fail(&ctx.db, "boom".to_string())
+        .await
+        .unwrap();
+    create_import(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .finish(&ctx.db)
+        .await
+        .unwrap();
+    create_import(ctx, USER2_PID).await;
+
+    let mut pending: Vec<i32> =
+        Model::find_pending_by_user_pid(&ctx.db, Uuid::parse_str(USER1_PID).unwrap())
+            .await
+            .unwrap()
+            .iter()
+            .map(|import| import.id)
+            .collect();
+    pending.sort_unstable();
+
+    assert_eq!(pending, vec![queued.id, running.id]);
+}
+
+#[tokio::test]
+#[serial]
+async fn goes_stale_without_progress() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let import = create_import(ctx, USER1_PID).await;
+
+    assert!(!import.is_stale((Utc::now() - TimeDelta::hours(1)).into()));
+    assert!(import.is_stale((Utc::now() + TimeDelta::hours(1)).into()));
+
+    // failed ones never are, and keep their error
+    let import = import
+        .into_active_model()
+        .fail(&ctx.db, "boom".to_string())
+        .await
+        .unwrap();
+    assert!(!import.is_stale((Utc::now() + TimeDelta::hours(1)).into()));
+    assert_eq!(import.status, ImportStatus::Failed);
+    assert_eq!(import.error.as_deref(), Some("boom"));
+}
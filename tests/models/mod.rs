@@ -2,4 +2,13 @@ mod users;
 
 mod settings;
 mod images;
-mod tmps;
\ No newline at end of file
+mod tmps;
+mod albums;
+mod tags;
+mod image_tags;
+mod image_stats;
+mod abuse_reports;
+mod regenerations;
+mod exports;
+mod imports;
+mod tus_uploads;
@@ -0,0 +1,123 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::{images::Location, regenerations::RegenerationStatus},
+        regenerations::{Model, RegenerateParams},
+    },
+};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::USER1_PID;
+
+async fn create(ctx: &AppContext, quality: u8) -> Model {
+    let params = RegenerateParams {
+        user_pid: Some(Uuid::parse_str(USER1_PID).unwrap()),
+        location: Some(Location::Local),
+        created_from: None,
+        created_to: None,
+        quality,
+        delay_ms: 200,
+    };
+
+    Model::create(&ctx.db, &params).await.unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn creates_queued_runs() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+
+    let regeneration = create(ctx, 60).await;
+
+    assert_eq!(regeneration.status, RegenerationStatus::Queued);
+    assert_eq!(regeneration.quality, 60);
+    assert_eq!(regeneration.delay_ms, 200);
+    assert_eq!(
+        (
+            regeneration.total,
+            regeneration.encoded,
+            regeneration.skipped,
+            regeneration.failed
+        ),
+        (0, 0, 0, 0)
+    );
+    let scope = regeneration.scope();
+    assert_eq!(scope.user_pid, Some(Uuid::parse_str(USER1_PID).unwrap()));
+    assert_eq!(scope.location, Some(Location::Local));
+    assert_eq!(scope.created_from, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn lists_recent_runs_newest_first() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+    let first = create(ctx, 60).await;
+    let second = create(ctx, 70).await;
+    let third = create(ctx, 80).await;
+
+    let recent: Vec<i32> = Model::find_recent(&ctx.db, 2)
+        .await
+        .unwrap()
+        .iter()
+        .map(|regeneration| regeneration.id)
+        .collect();
+
+    assert_eq!(recent, vec![third.id, second.id]);
+    assert_eq!(
+        Model::find_by_id(&ctx.db, first.id).await.unwrap().quality,
+        60
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn tracks_progress_to_the_end() {
+    let boot = boot_test::<App>().await.unwrap();
+    let ctx = &boot.app_context;
+
+    let regeneration = create(ctx, 80)
+        .await
+        .into_active_model()
+        .start(&ctx.db, 12)
+        .await
+        .unwrap();
+    assert_eq!(regeneration.status, RegenerationStatus::Running);
+    assert_eq!(regeneration.total, 12);
+
+    let regeneration = regeneration
+        .into_active_model()
+        .set_progress(&ctx.db, 5, 2, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            regeneration.encoded,
+            regeneration.skipped,
+            regeneration.failed
+        ),
+        (5, 2, 1)
+    );
+
+    let finished = regeneration
+        .clone()
+        .into_active_model()
+        .finish(&ctx.db, None)
+        .await
+        .unwrap();
+    assert_eq!(finished.status, RegenerationStatus::Finished);
+    assert!(finished.finished_at.is_some());
+    assert_eq!(finished.error, None);
+
+    let failed = regeneration
+        .into_active_model()
+        .finish(&ctx.db, Some("storage is down".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(failed.status, RegenerationStatus::Failed);
+    assert_eq!(failed.error.as_deref(), Some("storage is down"));
+}
@@ -0,0 +1,63 @@
+use AetherPix::{
+    app::App,
+    models::tags::{Model, TagsParams},
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{USER1_PID, USER2_PID};
+
+fn params(tags: &[&str]) -> TagsParams {
+    TagsParams {
+        tags: tags.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[test]
+fn normalizes_names() {
+    let names = params(&[" Cats ", "cats", "DOGS", "猫"]).normalized().unwrap();
+
+    assert_eq!(names, vec!["cats", "dogs", "猫"]);
+}
+
+#[test]
+fn rejects_invalid_names() {
+    assert!(params(&["  "]).normalized().is_err());
+    assert!(params(&[&"a".repeat(33)]).normalized().is_err());
+    assert!(params(&[&"猫".repeat(32)]).normalized().is_ok());
+
+    let many: Vec<String> = (0..21).map(|i| format!("tag{i}")).collect();
+    let many: Vec<&str> = many.iter().map(String::as_str).collect();
+    assert!(params(&many).normalized().is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn creates_missing_tags_per_user() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+    let user1 = Uuid::parse_str(USER1_PID).unwrap();
+    let user2 = Uuid::parse_str(USER2_PID).unwrap();
+
+    let cats = Model::find_or_create(db, user1, &["cats".to_string()])
+        .await
+        .unwrap();
+    let tags = Model::find_or_create(db, user1, &["cats".to_string(), "dogs".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(tags.len(), 2);
+    assert!(tags.iter().any(|t| t.id == cats[0].id));
+
+    // the same name is another tag for another user
+    let other = Model::find_or_create(db, user2, &["cats".to_string()])
+        .await
+        .unwrap();
+    assert_ne!(other[0].id, cats[0].id);
+
+    let found = Model::find_by_names(db, user2, &["cats".to_string(), "dogs".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+}
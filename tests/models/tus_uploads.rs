@@ -0,0 +1,84 @@
+use AetherPix::{
+    app::App,
+    models::tus_uploads::{Model, NewTusUpload},
+};
+use chrono::{TimeDelta, Utc};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::USER1_PID;
+
+async fn create_upload(ctx: &AppContext, length: u64) -> Model {
+    Model::create(
+        &ctx.db,
+        NewTusUpload {
+            user_pid: Some(Uuid::parse_str(USER1_PID).unwrap()),
+            raw_name: "cat.png".to_string(),
+            length,
+            quality: 80,
+            public: false,
+            expires_in: Some(3600),
+            max_views: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn keeps_offset_of_uploads() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+
+    let upload = create_upload(ctx, 100).await;
+    assert_eq!((upload.offset(), upload.length()), (0, 100));
+    assert_eq!(upload.expires_in, Some(3600));
+    assert_eq!(
+        Model::find_by_uuid(&ctx.db, upload.uuid).await.unwrap().id,
+        upload.id
+    );
+
+    let upload = upload
+        .into_active_model()
+        .set_offset(&ctx.db, 40)
+        .await
+        .unwrap();
+    let found = Model::find_by_uuid(&ctx.db, upload.uuid).await.unwrap();
+    assert_eq!((found.offset(), found.length()), (40, 100));
+
+    assert!(Model::find_by_uuid(&ctx.db, Uuid::now_v7()).await.is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn finds_uploads_left_without_data() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    create_upload(ctx, 100).await;
+    let mut left = create_upload(ctx, 100).await.into_active_model();
+    left.updated_at = ActiveValue::Set((Utc::now() - TimeDelta::hours(2)).into());
+    let left = left.update(&ctx.db).await.unwrap();
+
+    let stale: Vec<i32> = Model::find_stale(&ctx.db, (Utc::now() - TimeDelta::hours(1)).into(), 10)
+        .await
+        .unwrap()
+        .iter()
+        .map(|upload| upload.id)
+        .collect();
+    assert_eq!(stale, vec![left.id]);
+
+    // data coming in keeps it fresh
+    left.into_active_model()
+        .set_offset(&ctx.db, 10)
+        .await
+        .unwrap();
+    let stale = Model::find_stale(&ctx.db, (Utc::now() - TimeDelta::hours(1)).into(), 10)
+        .await
+        .unwrap();
+    assert!(stale.is_empty());
+}
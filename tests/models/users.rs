@@ -1,10 +1,9 @@
-use aether_pix::{
+use AetherPix::{
     app::App,
     models::users::{self, Model, RegisterParams},
 };
-use chrono::{offset::Local, Duration};
 use insta::assert_debug_snapshot;
-use loco_rs::testing::prelude::*;
+use loco_rs::{model::Authenticable, testing::prelude::*};
 use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
 use serial_test::serial;
 
@@ -27,7 +26,7 @@ async fn test_can_validate_model() {
         .expect("Failed to boot test application");
 
     let invalid_user = users::ActiveModel {
-        name: ActiveValue::set("1".to_string()),
+        username: ActiveValue::set("1".to_string()),
         email: ActiveValue::set("invalid-email".to_string()),
         ..Default::default()
     };
@@ -48,8 +47,8 @@ async fn can_create_with_password() {
 
     let params = RegisterParams {
         email: "test@framework.com".to_string(),
-        password: "1234".to_string(),
-        name: "framework".to_string(),
+        password: "12341234".to_string(),
+        username: "framework".to_string(),
     };
 
     let res = Model::create_with_password(&boot.app_context.db, &params).await;
@@ -76,8 +75,8 @@ async fn handle_create_with_password_with_duplicate() {
         &boot.app_context.db,
         &RegisterParams {
             email: "user1@example.com".to_string(),
-            password: "1234".to_string(),
-            name: "framework".to_string(),
+            password: "12341234".to_string(),
+            username: "user1".to_string(),
         },
     )
     .await;
@@ -255,7 +254,7 @@ async fn can_verified() {
 
 #[tokio::test]
 #[serial]
-async fn can_reset_password() {
+async fn can_set_disabled() {
     configure_insta!();
 
     let boot = boot_test::<App>()
@@ -269,92 +268,71 @@ async fn can_reset_password() {
         .await
         .expect("Failed to find user by PID");
 
-    assert!(
-        user.verify_password("12341234"),
-        "Password verification failed for original password"
-    );
+    assert!(!user.is_disabled(), "Expected user to be enabled");
 
-    let result = user
-        .clone()
+    let user = user
         .into_active_model()
-        .reset_password(&boot.app_context.db, "new-password")
-        .await;
+        .set_disabled(&boot.app_context.db, true)
+        .await
+        .expect("Failed to disable user");
 
-    assert!(result.is_ok(), "Failed to reset password");
+    assert!(user.is_disabled(), "Expected user to be disabled");
+    assert!(
+        <Model as Authenticable>::find_by_api_key(&boot.app_context.db, &user.api_key)
+            .await
+            .is_err(),
+        "Expected API key of a disabled user to be rejected"
+    );
 
-    let user = Model::find_by_pid(&boot.app_context.db, "11111111-1111-1111-1111-111111111111")
+    let user = user
+        .into_active_model()
+        .set_disabled(&boot.app_context.db, false)
         .await
-        .expect("Failed to find user by PID after password reset");
+        .expect("Failed to enable user");
 
     assert!(
-        user.verify_password("new-password"),
-        "Password verification failed for new password"
+        <Model as Authenticable>::find_by_api_key(&boot.app_context.db, &user.api_key)
+            .await
+            .is_ok(),
+        "Expected API key of an enabled user to be accepted"
     );
 }
 
 #[tokio::test]
 #[serial]
-async fn magic_link() {
-    let boot = boot_test::<App>().await.unwrap();
-    seed::<App>(&boot.app_context).await.unwrap();
+async fn can_reset_password() {
+    configure_insta!();
+
+    let boot = boot_test::<App>()
+        .await
+        .expect("Failed to boot test application");
+    seed::<App>(&boot.app_context)
+        .await
+        .expect("Failed to seed database");
 
     let user = Model::find_by_pid(&boot.app_context.db, "11111111-1111-1111-1111-111111111111")
         .await
-        .unwrap();
+        .expect("Failed to find user by PID");
 
     assert!(
-        user.magic_link_token.is_none(),
-        "Magic link token should be initially unset"
-    );
-    assert!(
-        user.magic_link_expiration.is_none(),
-        "Magic link expiration should be initially unset"
+        user.verify_password("12341234"),
+        "Password verification failed for original password"
     );
 
-    let create_result = user
+    let result = user
+        .clone()
         .into_active_model()
-        .create_magic_link(&boot.app_context.db)
+        .reset_password(&boot.app_context.db, "new-password")
         .await;
 
-    assert!(
-        create_result.is_ok(),
-        "Failed to create magic link: {:?}",
-        create_result.unwrap_err()
-    );
-
-    let updated_user =
-        Model::find_by_pid(&boot.app_context.db, "11111111-1111-1111-1111-111111111111")
-            .await
-            .expect("Failed to refetch user after magic link creation");
-
-    assert!(
-        updated_user.magic_link_token.is_some(),
-        "Magic link token should be set after creation"
-    );
-
-    let magic_link_token = updated_user.magic_link_token.unwrap();
-    assert_eq!(
-        magic_link_token.len(),
-        users::MAGIC_LINK_LENGTH as usize,
-        "Magic link token length does not match expected length"
-    );
-
-    assert!(
-        updated_user.magic_link_expiration.is_some(),
-        "Magic link expiration should be set after creation"
-    );
-
-    let now = Local::now();
-    let should_expired_at = now + Duration::minutes(users::MAGIC_LINK_EXPIRATION_MIN.into());
-    let actual_expiration = updated_user.magic_link_expiration.unwrap();
+    assert!(result.is_ok(), "Failed to reset password");
 
-    assert!(
-        actual_expiration >= now,
-        "Magic link expiration should be in the future or now"
-    );
+    let user = Model::find_by_pid(&boot.app_context.db, "11111111-1111-1111-1111-111111111111")
+        .await
+        .expect("Failed to find user by PID after password reset");
 
     assert!(
-        actual_expiration <= should_expired_at,
-        "Magic link expiration exceeds expected maximum expiration time"
+        user.verify_password("new-password"),
+        "Password verification failed for new password"
     );
 }
@@ -0,0 +1,202 @@
+use AetherPix::{
+    app::App,
+    models::{_entities::users::UserRole, users},
+};
+use loco_rs::{app::AppContext, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serde_json::Value;
+use serial_test::serial;
+
+use crate::support::{self, USER1_PID, USER2_PID};
+
+async fn make_admin(ctx: &AppContext, pid: &str) {
+    users::Model::find_by_pid(&ctx.db, pid)
+        .await
+        .unwrap()
+        .into_active_model()
+        .set_role(&ctx.db, UserRole::Admin)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn only_admins_can_manage_users() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        let response = request.get("/api/admin/users").await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .get("/api/admin/users")
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 403);
+
+        let response = request
+            .post(&format!("/api/admin/users/{USER2_PID}/disable"))
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 403);
+        let user = users::Model::find_by_pid(&ctx.db, USER2_PID).await.unwrap();
+        assert!(!user.is_disabled());
+
+        make_admin(&ctx, USER1_PID).await;
+        let response = request
+            .get("/api/admin/users")
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["total"], 2);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_disable_and_promote_users() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        make_admin(&ctx, USER1_PID).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        let response = request
+            .post(&format!("/api/admin/users/{USER2_PID}/disable"))
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert!(!body["disabledAt"].is_null());
+        let user = users::Model::find_by_pid(&ctx.db, USER2_PID).await.unwrap();
+        assert!(user.is_disabled());
+
+        let response = request
+            .post(&format!("/api/admin/users/{USER2_PID}/enable"))
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert!(body["disabledAt"].is_null());
+
+        let response = request
+            .put(&format!("/api/admin/users/{USER2_PID}/role"))
+            .add_header(name.clone(), value.clone())
+            .json(&serde_json::json!({ "role": "admin" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["role"], "admin");
+
+        let response = request
+            .get("/api/admin/users/00000000-0000-0000-0000-000000000000")
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn admins_cannot_change_themselves() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        make_admin(&ctx, USER1_PID).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        let response = request
+            .post(&format!("/api/admin/users/{USER1_PID}/disable"))
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .put(&format!("/api/admin/users/{USER1_PID}/role"))
+            .add_header(name.clone(), value.clone())
+            .json(&serde_json::json!({ "role": "user" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .delete(&format!("/api/admin/users/{USER1_PID}"))
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let admin = users::Model::find_by_pid(&ctx.db, USER1_PID).await.unwrap();
+        assert_eq!(admin.role, UserRole::Admin);
+        assert!(!admin.is_disabled());
+
+        // others can still be removed
+        let response = request
+            .delete(&format!("/api/admin/users/{USER2_PID}"))
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(users::Model::find_by_pid(&ctx.db, USER2_PID).await.is_err());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_take_down_reported_images() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        make_admin(&ctx, USER1_PID).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+        let s3 = support::fake_storage(&ctx).await;
+        let image = support::create_image(&ctx, Some(USER2_PID)).await;
+        support::store_image(&s3, &image);
+
+        let response = request
+            .post("/api/image/report")
+            .json(&serde_json::json!({
+                "name": image.file_name,
+                "reason": "not mine",
+                "contact": "owner@example.com",
+            }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .get("/api/admin/reports")
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["reports"][0]["status"], "open");
+        assert_eq!(body["reports"][0]["image"]["id"], image.id);
+
+        let response = request
+            .post(&format!("/api/admin/images/{}/takedown", image.id))
+            .add_header(name.clone(), value.clone())
+            .json(&serde_json::json!({ "reason": "copyright" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["takedownReason"], "copyright");
+
+        let response = request
+            .get("/api/admin/reports?status=taken_down")
+            .add_header(name.clone(), value.clone())
+            .await;
+        let body: Value = response.json();
+        assert_eq!(body["total"], 1);
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+        assert_eq!(response.status_code(), 451);
+
+        let response = request
+            .post(&format!("/api/admin/images/{}/restore", image.id))
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+        assert_eq!(response.status_code(), 200);
+    })
+    .await;
+}
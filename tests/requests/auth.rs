@@ -1,7 +1,8 @@
-use aether_pix::{app::App, models::users};
+use AetherPix::{app::App, models::users};
 use insta::{assert_debug_snapshot, with_settings};
 use loco_rs::testing::prelude::*;
 use rstest::rstest;
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
 use serial_test::serial;
 
 use super::prepare_data;
@@ -25,7 +26,7 @@ async fn can_register() {
     request::<App, _, _>(|request, ctx| async move {
         let email = "test@loco.com";
         let payload = serde_json::json!({
-            "name": "loco",
+            "username": "loco",
             "email": email,
             "password": "12341234"
         });
@@ -67,7 +68,7 @@ async fn can_login_with_verify(#[case] test_name: &str, #[case] password: &str)
     request::<App, _, _>(|request, ctx| async move {
         let email = "test@loco.com";
         let register_payload = serde_json::json!({
-            "name": "loco",
+            "username": "loco",
             "email": email,
             "password": "12341234"
         });
@@ -96,7 +97,7 @@ async fn can_login_with_verify(#[case] test_name: &str, #[case] password: &str)
         let response = request
             .post("/api/auth/login")
             .json(&serde_json::json!({
-                "email": email,
+                "username": "loco",
                 "password": password
             }))
             .await;
@@ -123,35 +124,34 @@ async fn can_login_with_verify(#[case] test_name: &str, #[case] password: &str)
 
 #[tokio::test]
 #[serial]
-async fn login_with_un_existing_email() {
+async fn login_with_un_existing_username() {
     configure_insta!();
 
     request::<App, _, _>(|request, _ctx| async move {
-      
         let login_response = request
             .post("/api/auth/login")
             .json(&serde_json::json!({
-                "email": "un_existing@loco.rs",
-                "password":  "1234"
+                "username": "un_existing",
+                "password":  "12341234"
             }))
             .await;
 
         assert_eq!(login_response.status_code(), 401, "Login request should return 401");
-        login_response.assert_json(&serde_json::json!({"error": "unauthorized", "description": "You do not have permission to access this resource"}));
+        login_response.assert_json(&serde_json::json!({"error": "401 Unauthorized", "description": "用户名不存在"}));
     })
     .await;
 }
 
 #[tokio::test]
 #[serial]
-async fn can_login_without_verify() {
+async fn cannot_login_without_verify() {
     configure_insta!();
 
     request::<App, _, _>(|request, _ctx| async move {
         let email = "test@loco.com";
         let password = "12341234";
         let register_payload = serde_json::json!({
-            "name": "loco",
+            "username": "loco",
             "email": email,
             "password": password
         });
@@ -172,22 +172,17 @@ async fn can_login_without_verify() {
         let login_response = request
             .post("/api/auth/login")
             .json(&serde_json::json!({
-                "email": email,
+                "username": "loco",
                 "password": password
             }))
             .await;
 
         assert_eq!(
             login_response.status_code(),
-            200,
-            "Login request should succeed"
+            401,
+            "Login request should be rejected until the email is verified"
         );
-
-        with_settings!({
-            filters => cleanup_user_model()
-        }, {
-            assert_debug_snapshot!(login_response.text());
-        });
+        assert!(login_response.maybe_cookie("auth_token").is_none());
     })
     .await;
 }
@@ -261,7 +256,7 @@ async fn can_reset_password() {
         let login_response = request
             .post("/api/auth/login")
             .json(&serde_json::json!({
-                "email": user.email,
+                "username": user.username,
                 "password": new_password
             }))
             .await;
@@ -312,98 +307,6 @@ async fn can_get_current_user() {
     .await;
 }
 
-#[tokio::test]
-#[serial]
-async fn can_auth_with_magic_link() {
-    configure_insta!();
-    request::<App, _, _>(|request, ctx| async move {
-        seed::<App>(&ctx).await.unwrap();
-
-        let payload = serde_json::json!({
-            "email": "user1@example.com",
-        });
-        let response = request.post("/api/auth/magic-link").json(&payload).await;
-        assert_eq!(
-            response.status_code(),
-            200,
-            "Magic link request should succeed"
-        );
-
-        let deliveries = ctx.mailer.unwrap().deliveries();
-        assert_eq!(deliveries.count, 1, "Exactly one email should be sent");
-
-        // let redact_token = format!("[a-zA-Z0-9]{{{}}}", users::MAGIC_LINK_LENGTH);
-        // with_settings!({
-        //      filters => {
-        //          let mut combined_filters = cleanup_email().clone();
-        //         combined_filters.extend(vec![(r"(\\r\\n|=\\r\\n)", ""), (redact_token.as_str(), "[REDACT_TOKEN]") ]);
-        //         combined_filters
-        //     }
-        // }, {
-        //     assert_debug_snapshot!(deliveries.messages);
-        // });
-
-        let user = users::Model::find_by_email(&ctx.db, "user1@example.com")
-            .await
-            .expect("User should be found");
-
-        let magic_link_token = user
-            .magic_link_token
-            .expect("Magic link token should be generated");
-        let magic_link_response = request
-            .get(&format!("/api/auth/magic-link/{magic_link_token}"))
-            .await;
-        assert_eq!(
-            magic_link_response.status_code(),
-            200,
-            "Magic link authentication should succeed"
-        );
-
-        with_settings!({
-            filters => cleanup_user_model()
-        }, {
-            assert_debug_snapshot!(magic_link_response.text());
-        });
-    })
-    .await;
-}
-
-#[tokio::test]
-#[serial]
-async fn can_reject_invalid_email() {
-    configure_insta!();
-    request::<App, _, _>(|request, _ctx| async move {
-        let invalid_email = "user1@temp-mail.com";
-        let payload = serde_json::json!({
-            "email": invalid_email,
-        });
-        let response = request.post("/api/auth/magic-link").json(&payload).await;
-        assert_eq!(
-            response.status_code(),
-            400,
-            "Expected request with invalid email '{invalid_email}' to be blocked, but it was allowed."
-        );
-    })
-    .await;
-}
-
-#[tokio::test]
-#[serial]
-async fn can_reject_invalid_magic_link_token() {
-    configure_insta!();
-    request::<App, _, _>(|request, ctx| async move {
-        seed::<App>(&ctx).await.unwrap();
-
-        let magic_link_response = request.get("/api/auth/magic-link/invalid-token").await;
-        assert_eq!(
-            magic_link_response.status_code(),
-            401,
-            "Magic link authentication should be rejected"
-        );
-    })
-    .await;
-}
-
 #[tokio::test]
 #[serial]
 async fn can_resend_verification_email() {
@@ -412,7 +315,7 @@ async fn can_resend_verification_email() {
     request::<App, _, _>(|request, ctx| async move {
         let email = "test@loco.com";
         let payload = serde_json::json!({
-            "name": "loco",
+            "username": "loco",
             "email": email,
             "password": "12341234"
         });
@@ -465,7 +368,7 @@ async fn cannot_resend_email_if_already_verified() {
     request::<App, _, _>(|request, ctx| async move {
         let email = "verified@loco.com";
         let payload = serde_json::json!({
-            "name": "verified",
+            "username": "verified",
             "email": email,
             "password": "12341234"
         });
@@ -500,3 +403,40 @@ async fn cannot_resend_email_if_already_verified() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn login_limits_failed_attempts_only() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let user = users::Model::find_by_email(&ctx.db, "user1@example.com")
+            .await
+            .unwrap();
+        let mut user = user.into_active_model();
+        // its own name, the limits are kept across tests
+        user.username = ActiveValue::Set("limited".to_string());
+        let user = user.update(&ctx.db).await.unwrap();
+        user.into_active_model().verified(&ctx.db).await.unwrap();
+        let login = |username: &'static str, password: &'static str| {
+            request.post("/api/auth/login").json(&serde_json::json!({
+                "username": username,
+                "password": password,
+            }))
+        };
+
+        // logging in doesn't use up attempts
+        for _ in 0..12 {
+            assert_eq!(login("limited", "12341234").await.status_code(), 200);
+        }
+
+        // failures count however the name is written
+        for _ in 0..5 {
+            assert_eq!(login("limited", "wrong").await.status_code(), 400);
+            assert_eq!(login("Limited", "12341234").await.status_code(), 401);
+        }
+        let response = login("limited", "12341234").await;
+        assert_eq!(response.status_code(), 429);
+        assert!(response.headers().contains_key("retry-after"));
+    })
+    .await;
+}
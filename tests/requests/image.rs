@@ -0,0 +1,432 @@
+use AetherPix::{
+    app::App,
+    controllers::upload::UploadResult,
+    models::{albums, images},
+};
+use loco_rs::app::AppContext;
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID, USER2_PID};
+
+/// Saves an anonymous upload, returning it with its delete token.
+async fn anonymous_upload(ctx: &AppContext) -> (images::Model, String) {
+    let uuid = Uuid::now_v7();
+    let delete_token = images::Model::new_delete_token(uuid);
+    let r = UploadResult {
+        url: format!("http://localhost/i/{uuid}.avif"),
+        file_name: format!("{uuid}.avif"),
+        is_public: true,
+        user_id: None,
+        uuid,
+        raw_name: "cat.png".to_string(),
+        size: 1024,
+        delete_token: Some(delete_token.clone()),
+        expires_at: None,
+        max_views: None,
+    };
+    let image = images::Model::save_local_with_result(&ctx.db, &r)
+        .await
+        .unwrap();
+
+    (image, delete_token)
+}
+
+#[tokio::test]
+#[serial]
+async fn bulk_delete_reports_each_item() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        let first = support::create_image(&ctx, Some(USER1_PID)).await;
+        let second = support::create_image(&ctx, Some(USER1_PID)).await;
+        let other = support::create_image(&ctx, Some(USER2_PID)).await;
+        for image in [&first, &second, &other] {
+            support::store_image(&s3, image);
+        }
+
+        let response = request
+            .post("/api/image/bulk")
+            .add_header(key, value)
+            .json(&serde_json::json!({
+                "ids": [first.id, other.id, second.id, 9999],
+                "action": "delete",
+            }))
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        response.assert_json(&serde_json::json!({
+            "results": [
+                {"id": first.id, "ok": true},
+                {"id": other.id, "ok": false, "error": "Image not found"},
+                {"id": second.id, "ok": true},
+                {"id": 9999, "ok": false, "error": "Image not found"},
+            ]
+        }));
+        for image in [&first, &second] {
+            assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_err());
+            assert!(!support::has_objects(&s3, image));
+        }
+        assert!(images::Model::find_by_id(&ctx.db, other.id).await.is_ok());
+        assert!(support::has_objects(&s3, &other));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn bulk_changes_visibility() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+        assert!(image.public);
+
+        let response = request
+            .post("/api/image/bulk")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({"ids": [image.id], "action": "make_private"}))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert!(!image.public);
+
+        let response = request
+            .post("/api/image/bulk")
+            .add_header(key, value)
+            .json(&serde_json::json!({"ids": [image.id], "action": "make_public"}))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert!(image.public);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn bulk_adds_own_images_to_album() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let owner = Uuid::parse_str(USER1_PID).unwrap();
+        let album = albums::Model::create(
+            &ctx.db,
+            owner,
+            &albums::CreateParams {
+                name: "Trip".to_string(),
+                description: None,
+                public: None,
+            },
+        )
+        .await
+        .unwrap();
+        let first = support::create_image(&ctx, Some(USER1_PID)).await;
+        let second = support::create_image(&ctx, Some(USER1_PID)).await;
+        let other = support::create_image(&ctx, Some(USER2_PID)).await;
+
+        let response = request
+            .post("/api/image/bulk")
+            .add_header(key, value)
+            .json(&serde_json::json!({
+                "ids": [second.id, other.id, first.id],
+                "action": "add_to_album",
+                "album": album.pid.to_string(),
+            }))
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        response.assert_json(&serde_json::json!({
+            "results": [
+                {"id": second.id, "ok": true},
+                {"id": other.id, "ok": false, "error": "Image not found"},
+                {"id": first.id, "ok": true},
+            ]
+        }));
+        let ids: Vec<i32> = album
+            .images(&ctx.db, false)
+            .await
+            .unwrap()
+            .iter()
+            .map(|image| image.id)
+            .collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn bulk_rejects_more_than_100_items() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+        let ids: Vec<i32> = std::iter::once(image.id).chain(10_000..10_100).collect();
+
+        let response = request
+            .post("/api/image/bulk")
+            .add_header(key, value)
+            .json(&serde_json::json!({"ids": ids, "action": "delete"}))
+            .await;
+
+        assert_eq!(response.status_code(), 400);
+        assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_ok());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn bulk_requires_login() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+
+        let response = request
+            .post("/api/image/bulk")
+            .json(&serde_json::json!({"ids": [image.id], "action": "delete"}))
+            .await;
+
+        assert_eq!(response.status_code(), 401);
+        assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_ok());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_update_metadata() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+
+        let response = request
+            .patch(&format!("/api/image/{}", image.id))
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({
+                "name": "Holiday",
+                "description": " At the beach ",
+                "altText": "A cat on the sand",
+                "public": false,
+            }))
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        response.assert_json(&serde_json::json!({
+            "id": image.id,
+            "name": "Holiday.png",
+            "description": "At the beach",
+            "altText": "A cat on the sand",
+            "public": false,
+        }));
+
+        // fields left out are kept, empty ones are cleared
+        let response = request
+            .patch(&format!("/api/image/{}", image.id))
+            .add_header(key, value)
+            .json(&serde_json::json!({"altText": ""}))
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert_eq!(image.raw_name, "Holiday.png");
+        assert_eq!(image.description.as_deref(), Some("At the beach"));
+        assert_eq!(image.alt_text, None);
+        assert!(!image.public);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rename_keeps_the_original_key() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let uuid = Uuid::now_v7();
+        let r = UploadResult {
+            url: format!("http://localhost/i/{uuid}.avif"),
+            file_name: format!("{uuid}.avif"),
+            is_public: true,
+            user_id: Some(Uuid::parse_str(USER1_PID).unwrap()),
+            uuid,
+            raw_name: "scan".to_string(),
+            size: 1024,
+            delete_token: None,
+            expires_at: None,
+            max_views: None,
+        };
+        let image = images::Model::save_local_with_result(&ctx.db, &r)
+            .await
+            .unwrap();
+        let original_key = image.original_key();
+
+        // the upload had no extension to keep, so the new one is taken
+        let response = request
+            .patch(&format!("/api/image/{}", image.id))
+            .add_header(key, value)
+            .json(&serde_json::json!({"name": "scan.png"}))
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert_eq!(image.raw_name, "scan.png");
+        assert_eq!(image.original_key(), original_key);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn cannot_update_metadata_of_others() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER2_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+
+        let response = request
+            .patch(&format!("/api/image/{}", image.id))
+            .add_header(key, value)
+            .json(&serde_json::json!({"public": false}))
+            .await;
+
+        assert_eq!(response.status_code(), 404);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert!(image.public);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_empty_name() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+
+        let response = request
+            .patch(&format!("/api/image/{}", image.id))
+            .add_header(key, value)
+            .json(&serde_json::json!({"name": ""}))
+            .await;
+
+        assert_eq!(response.status_code(), 400);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert_eq!(image.raw_name, "cat.png");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_tag_and_untag_images() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+
+        let response = request
+            .post(&format!("/api/image/{}/tags", image.id))
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({"tags": [" Cats ", "dogs", "CATS"]}))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        response.assert_json(&serde_json::json!(["cats", "dogs"]));
+
+        let response = request
+            .delete(&format!("/api/image/{}/tags", image.id))
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({"tags": ["dogs", "birds"]}))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        response.assert_json(&serde_json::json!(["cats"]));
+
+        let response = request.get("/api/tag/list").add_header(key, value).await;
+        assert_eq!(response.status_code(), 200);
+        response.assert_json(&serde_json::json!({
+            "tags": [
+                {"name": "cats", "count": 1},
+                {"name": "dogs", "count": 0},
+            ]
+        }));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn cannot_tag_images_of_others() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER2_PID).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+
+        let response = request
+            .post(&format!("/api/image/{}/tags", image.id))
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({"tags": ["cats"]}))
+            .await;
+        assert_eq!(response.status_code(), 404);
+
+        let response = request
+            .post(&format!("/api/image/{}/tags", image.id))
+            .add_header(key, value)
+            .json(&serde_json::json!({"tags": [" "]}))
+            .await;
+        assert_eq!(response.status_code(), 400);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_delete_anonymous_upload_by_token() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (image, token) = anonymous_upload(&ctx).await;
+        let (other, other_token) = anonymous_upload(&ctx).await;
+        support::store_image(&s3, &image);
+        support::store_image(&s3, &other);
+        let (uuid, _) = token.split_once('.').unwrap();
+        let (_, other_secret) = other_token.split_once('.').unwrap();
+
+        // a token only fits its own image
+        for wrong in [
+            format!("{uuid}.{other_secret}"),
+            uuid.to_string(),
+            "not-a-token".to_string(),
+        ] {
+            let response = request
+                .post("/api/image/delete-by-token")
+                .json(&serde_json::json!({"token": wrong}))
+                .await;
+            assert_eq!(response.status_code(), 404, "{wrong}");
+        }
+        assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_ok());
+
+        let response = request
+            .post("/api/image/delete-by-token")
+            .json(&serde_json::json!({"token": format!(" {token} ")}))
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_err());
+        assert!(!support::has_objects(&s3, &image));
+        assert!(images::Model::find_by_id(&ctx.db, other.id).await.is_ok());
+        assert!(support::has_objects(&s3, &other));
+
+        let response = request
+            .post("/api/image/delete-by-token")
+            .json(&serde_json::json!({"token": token}))
+            .await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
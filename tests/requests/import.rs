@@ -0,0 +1,157 @@
+use AetherPix::{
+    app::App,
+    common::settings::SettingsService,
+    models::{
+        _entities::imports::{ImportStatus, ImportStructure},
+        imports,
+    },
+    workers::importer::STALE_HOURS,
+};
+use axum_test::multipart::{MultipartForm, Part};
+use chrono::{TimeDelta, Utc};
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+use serde_json::Value;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID, USER2_PID};
+
+fn archive_form(archive: Vec<u8>, file_name: &str) -> MultipartForm {
+    MultipartForm::new().add_part(
+        "file",
+        Part::bytes(archive)
+            .file_name(file_name)
+            .mime_type("application/zip"),
+    )
+}
+
+/// An archive of one image, padded past the default body limit of 2 MB.
+async fn large_archive() -> Vec<u8> {
+    let padding = vec![0; 3 * 1024 * 1024];
+    support::zip(&[("trip/cat.png", &support::png()), ("padding.bin", &padding)]).await
+}
+
+#[tokio::test]
+#[serial]
+async fn can_import_archives_past_body_limit() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let _s3 = support::fake_storage(&ctx).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+        let archive = large_archive().await;
+
+        let response = request
+            .post("/api/import?quality=80")
+            .multipart(archive_form(archive.clone(), "photos.zip"))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .post("/api/import?quality=80")
+            .add_header(name.clone(), value.clone())
+            .multipart(archive_form(support::png(), "cat.png"))
+            .await;
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("请上传ZIP文件"));
+
+        let response = request
+            .post("/api/import?quality=80&structure=tags")
+            .add_header(name.clone(), value.clone())
+            .multipart(archive_form(archive, "photos.zip"))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let body: Value = response.json();
+        assert_eq!(body["fileName"], "photos.zip");
+        assert_eq!(body["structure"], "tags");
+        let id = body["id"].as_i64().unwrap();
+
+        let response = request
+            .get(&format!("/api/import/{id}"))
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["status"], "done");
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["imported"], 1);
+
+        // nor can others see it
+        let (name, value) = support::auth_header(&ctx, USER2_PID).await;
+        let response = request
+            .get(&format!("/api/import/{id}"))
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn refuses_archives_over_import_size() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        SettingsService::set(&ctx.db, "zip_import_max_size_mb", "1")
+            .await
+            .unwrap();
+        let response = request
+            .post("/api/import?quality=80")
+            .add_header(name, value)
+            .multipart(archive_form(large_archive().await, "photos.zip"))
+            .await;
+        SettingsService::set(&ctx.db, "zip_import_max_size_mb", "")
+            .await
+            .unwrap();
+
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("File too large"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn stale_imports_do_not_block_new_ones() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let _s3 = support::fake_storage(&ctx).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+        let archive = support::zip(&[("cat.png", &support::png())]).await;
+        let pending = imports::Model::create(
+            &ctx.db,
+            Uuid::parse_str(USER1_PID).unwrap(),
+            "photos.zip",
+            ImportStructure::Flat,
+        )
+        .await
+        .unwrap();
+
+        let response = request
+            .post("/api/import?quality=80")
+            .add_header(name.clone(), value.clone())
+            .multipart(archive_form(archive.clone(), "photos.zip"))
+            .await;
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("已有导入正在进行中"));
+
+        // as if its job was lost to a restart
+        let mut stale = pending.into_active_model();
+        stale.updated_at =
+            ActiveValue::Set((Utc::now() - TimeDelta::hours(STALE_HOURS + 1)).into());
+        let stale = stale.update(&ctx.db).await.unwrap();
+
+        let response = request
+            .post("/api/import?quality=80")
+            .add_header(name, value)
+            .multipart(archive_form(archive, "photos.zip"))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let stale = imports::Model::find_by_id(&ctx.db, stale.id).await.unwrap();
+        assert_eq!(stale.status, ImportStatus::Failed);
+        assert_eq!(stale.error.as_deref(), Some("Timed out"));
+    })
+    .await;
+}
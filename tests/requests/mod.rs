@@ -1,2 +1,9 @@
+mod admin;
 mod auth;
+mod image;
+mod import;
 mod prepare_data;
+mod profile;
+mod tus;
+mod upload;
+mod view;
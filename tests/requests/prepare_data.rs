@@ -1,9 +1,10 @@
-use aether_pix::{models::users, views::auth::LoginResponse};
+use AetherPix::{models::users, views::auth::LoginResponse};
 use axum::http::{HeaderName, HeaderValue};
-use loco_rs::{app::AppContext, TestServer};
+use loco_rs::{TestServer, app::AppContext};
 
 const USER_EMAIL: &str = "test@loco.com";
-const USER_PASSWORD: &str = "1234";
+const USER_NAME: &str = "loco";
+const USER_PASSWORD: &str = "12341234";
 
 pub struct LoggedInUser {
     pub user: users::Model,
@@ -12,7 +13,7 @@ pub struct LoggedInUser {
 
 pub async fn init_user_login(request: &TestServer, ctx: &AppContext) -> LoggedInUser {
     let register_payload = serde_json::json!({
-        "name": "loco",
+        "username": USER_NAME,
         "email": USER_EMAIL,
         "password": USER_PASSWORD
     });
@@ -26,27 +27,25 @@ pub async fn init_user_login(request: &TestServer, ctx: &AppContext) -> LoggedIn
         .await
         .unwrap();
 
-    let verify_payload = serde_json::json!({
-        "token": user.email_verification_token,
-    });
-
-    request.post("/api/auth/verify").json(&verify_payload).await;
+    let token = user.email_verification_token.unwrap();
+    request.get(&format!("/api/auth/verify/{token}")).await;
 
     let response = request
         .post("/api/auth/login")
         .json(&serde_json::json!({
-            "email": USER_EMAIL,
+            "username": USER_NAME,
             "password": USER_PASSWORD
         }))
         .await;
 
     let login_response: LoginResponse = serde_json::from_str(&response.text()).unwrap();
+    assert_eq!(login_response.username, USER_NAME);
 
     LoggedInUser {
         user: users::Model::find_by_email(&ctx.db, USER_EMAIL)
             .await
             .unwrap(),
-        token: login_response.token,
+        token: response.cookie("auth_token").value().to_string(),
     }
 }
 
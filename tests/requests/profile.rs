@@ -0,0 +1,100 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::exports::ExportStatus,
+        exports::{self, ExportParams},
+    },
+    workers::downloader::STALE_HOURS,
+};
+use chrono::{TimeDelta, Utc};
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+use serde_json::Value;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID};
+
+#[tokio::test]
+#[serial]
+async fn can_export_images() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let _s3 = support::fake_storage(&ctx).await;
+        support::create_image(&ctx, Some(USER1_PID)).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        let response = request
+            .post("/api/profile/export")
+            .json(&serde_json::json!({}))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .get("/api/profile/export")
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 404);
+
+        let response = request
+            .post("/api/profile/export")
+            .add_header(name.clone(), value.clone())
+            .json(&serde_json::json!({ "variant": "avif" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["variant"], "avif");
+
+        let response = request
+            .get("/api/profile/export")
+            .add_header(name, value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: Value = response.json();
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["images"], 1);
+        assert!(body["url"].as_str().is_some());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn stale_exports_do_not_block_new_ones() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let _s3 = support::fake_storage(&ctx).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+        let pending = exports::Model::create(
+            &ctx.db,
+            Uuid::parse_str(USER1_PID).unwrap(),
+            &ExportParams::default(),
+        )
+        .await
+        .unwrap();
+
+        let response = request
+            .post("/api/profile/export")
+            .add_header(name.clone(), value.clone())
+            .json(&serde_json::json!({}))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        // as if its job was lost to a restart
+        let mut stale = pending.into_active_model();
+        stale.updated_at =
+            ActiveValue::Set((Utc::now() - TimeDelta::hours(STALE_HOURS + 1)).into());
+        let stale = stale.update(&ctx.db).await.unwrap();
+
+        let response = request
+            .post("/api/profile/export")
+            .add_header(name, value)
+            .json(&serde_json::json!({}))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let stale = exports::Model::find_by_id(&ctx.db, stale.id).await.unwrap();
+        assert_eq!(stale.status, ExportStatus::Failed);
+        assert_eq!(stale.error.as_deref(), Some("Timed out"));
+    })
+    .await;
+}
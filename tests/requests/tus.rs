@@ -0,0 +1,224 @@
+use AetherPix::{app::App, models::images};
+use axum::{
+    body::Bytes,
+    http::{HeaderName, HeaderValue, Method},
+};
+use axum_test::{TestRequest, TestServer};
+use loco_rs::testing::prelude::*;
+use sea_orm::EntityTrait;
+use serial_test::serial;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID};
+
+const TUS_RESUMABLE: (HeaderName, HeaderValue) = (
+    HeaderName::from_static("tus-resumable"),
+    HeaderValue::from_static("1.0.0"),
+);
+
+fn header<'a>(response: &'a axum_test::TestResponse, name: &str) -> &'a str {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+fn patch(request: &TestServer, location: &str, offset: usize, data: &[u8]) -> TestRequest {
+    request
+        .patch(location)
+        .add_header(TUS_RESUMABLE.0, TUS_RESUMABLE.1)
+        .add_header("upload-offset", offset.to_string())
+        .content_type("application/offset+octet-stream")
+        .bytes(Bytes::copy_from_slice(data))
+}
+
+/// Sends a raw `PATCH` of `data` to `addr`, declaring `length` bytes so the
+/// server keeps waiting for the rest.
+async fn start_patch(
+    addr: std::net::SocketAddr,
+    path: &str,
+    auth: &HeaderValue,
+    length: usize,
+    data: &[u8],
+) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let head = format!(
+        "PATCH {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+         Authorization: {}\r\nTus-Resumable: 1.0.0\r\nUpload-Offset: 0\r\n\
+         Content-Type: application/offset+octet-stream\r\nContent-Length: {length}\r\n\r\n",
+        auth.to_str().unwrap()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(data).await.unwrap();
+
+    stream
+}
+
+/// Status code of a raw response, read to its end.
+async fn read_status(stream: &mut TcpStream) -> u16 {
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    response
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn options_are_served_on_every_base() {
+    request::<App, _, _>(|request, _ctx| async move {
+        for base in ["/api/tus", "/api/tus/jwt", "/api/tus/token"] {
+            let response = request.method(Method::OPTIONS, base).await;
+
+            assert_eq!(response.status_code(), 204, "{base}");
+            assert_eq!(header(&response, "tus-version"), "1.0.0");
+            assert_eq!(header(&response, "tus-extension"), "creation");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_upload_in_several_patches() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let _s3 = support::fake_storage(&ctx).await;
+        let (name, value) = support::auth_header(&ctx, USER1_PID).await;
+        let png = support::png();
+        let half = png.len() / 2;
+
+        // errors carry the protocol version too
+        let response = request
+            .post("/api/tus/jwt?quality=80")
+            .add_header(TUS_RESUMABLE.0, TUS_RESUMABLE.1)
+            .add_header("upload-length", png.len().to_string())
+            .await;
+        assert_eq!(response.status_code(), 401);
+        assert_eq!(header(&response, "tus-resumable"), "1.0.0");
+
+        let response = request
+            .post("/api/tus/jwt?quality=80")
+            .add_header(name.clone(), value.clone())
+            .add_header(TUS_RESUMABLE.0, TUS_RESUMABLE.1)
+            .add_header("upload-length", png.len().to_string())
+            // "cat.png"
+            .add_header("upload-metadata", "filename Y2F0LnBuZw==")
+            .await;
+        assert_eq!(response.status_code(), 201, "{}", response.text());
+        assert_eq!(header(&response, "tus-resumable"), "1.0.0");
+        let location = header(&response, "location").to_string();
+        assert!(location.starts_with("/api/tus/jwt/"));
+
+        let response = request
+            .method(Method::HEAD, &location)
+            .add_header(name.clone(), value.clone())
+            .add_header(TUS_RESUMABLE.0, TUS_RESUMABLE.1)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(header(&response, "upload-offset"), "0");
+        assert_eq!(header(&response, "upload-length"), png.len().to_string());
+
+        let response = patch(&request, &location, 0, &png[..half])
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 204, "{}", response.text());
+        assert_eq!(header(&response, "upload-offset"), half.to_string());
+
+        // resent from the start, as by a client that missed the response
+        let response = patch(&request, &location, 0, &png[..half])
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 409);
+        assert_eq!(header(&response, "tus-resumable"), "1.0.0");
+
+        let response = request
+            .method(Method::HEAD, &location)
+            .add_header(name.clone(), value.clone())
+            .add_header(TUS_RESUMABLE.0, TUS_RESUMABLE.1)
+            .await;
+        assert_eq!(header(&response, "upload-offset"), half.to_string());
+
+        let response = patch(&request, &location, half, &png[half..])
+            .add_header(name.clone(), value.clone())
+            .await;
+        assert_eq!(response.status_code(), 204, "{}", response.text());
+        assert_eq!(header(&response, "upload-offset"), png.len().to_string());
+        assert!(header(&response, "image-url").starts_with("http://localhost/i/"));
+
+        let image = images::Entity::find().one(&ctx.db).await.unwrap().unwrap();
+        assert_eq!(image.raw_name, "cat.png");
+        assert_eq!(image.user_pid, Some(Uuid::parse_str(USER1_PID).unwrap()));
+
+        // gone once complete
+        let response = request
+            .method(Method::HEAD, &location)
+            .add_header(name, value)
+            .add_header(TUS_RESUMABLE.0, TUS_RESUMABLE.1)
+            .await;
+        assert_eq!(response.status_code(), 404);
+        assert_eq!(header(&response, "tus-resumable"), "1.0.0");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn refuses_patches_while_one_is_in_progress() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let _s3 = support::fake_storage(&boot.app_context).await;
+    let (name, value) = support::auth_header(&boot.app_context, USER1_PID).await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = boot.router.unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    let client = reqwest::Client::new();
+    let png = support::png();
+
+    let response = client
+        .post(format!("http://{addr}/api/tus/jwt?quality=80"))
+        .header(name.clone(), value.clone())
+        .header("tus-resumable", "1.0.0")
+        .header("upload-length", png.len())
+        .header("upload-metadata", "filename Y2F0LnBuZw==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+
+    let mut held = start_patch(addr, &location, &value, png.len(), &png[..10]).await;
+    // answered with 409 until the held request has taken the lock
+    let mut status = 0;
+    for _ in 0..50 {
+        let response = client
+            .patch(format!("http://{addr}{location}"))
+            .header(name.clone(), value.clone())
+            .header("tus-resumable", "1.0.0")
+            .header("upload-offset", "1")
+            .header("content-type", "application/offset+octet-stream")
+            .body("x")
+            .send()
+            .await
+            .unwrap();
+        status = response.status().as_u16();
+        if status != 409 {
+            assert_eq!(response.headers()["tus-resumable"], "1.0.0");
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, 423);
+
+    held.write_all(&png[10..]).await.unwrap();
+    assert_eq!(read_status(&mut held).await, 204);
+}
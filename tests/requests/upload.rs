@@ -0,0 +1,455 @@
+use AetherPix::{
+    app::App,
+    common::{quota::Quota, settings::SettingsService},
+    controllers::upload::{UploadResult, save_within_quota},
+    models::{images, tmps, users},
+};
+use axum::{Router, http::StatusCode, routing::get};
+use axum_test::multipart::{MultipartForm, Part};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use loco_rs::{Error, app::AppContext, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, IntoActiveModel};
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, AVIF_BUCKET, ORIGIN_BUCKET, PREVIEW_BUCKET, R2_BUCKET, USER1_PID};
+
+async fn set_quota(ctx: &AppContext, storage_quota: Option<i64>, image_quota: Option<i32>) {
+    let mut user = users::Model::find_by_pid(&ctx.db, USER1_PID)
+        .await
+        .unwrap()
+        .into_active_model();
+    user.storage_quota = ActiveValue::Set(storage_quota);
+    user.image_quota = ActiveValue::Set(image_quota);
+    user.update(&ctx.db).await.unwrap();
+}
+
+/// Serves [`support::png`] on a random loopback port, with and without an
+/// extension in its path.
+async fn serve_png() -> std::net::SocketAddr {
+    let png = || async { ([("content-type", "image/png")], support::png()) };
+    let app = Router::new()
+        .route("/cat.png", get(png))
+        .route("/photo", get(png));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    addr
+}
+
+async fn user_images(ctx: &AppContext) -> usize {
+    images::Model::usage_by_user_pid(&ctx.db, Uuid::parse_str(USER1_PID).unwrap())
+        .await
+        .unwrap()
+        .images as usize
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_encodes_derivatives() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let form = MultipartForm::new().add_part(
+            "file",
+            Part::bytes(support::png())
+                .file_name("cat.png")
+                .mime_type("image/png"),
+        );
+
+        let response = request
+            .post("/api/upload/jwt?quality=80")
+            .add_header(key, value)
+            .multipart(form)
+            .await;
+
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let image = images::Entity::find().one(&ctx.db).await.unwrap().unwrap();
+        let preview = s3.get(PREVIEW_BUCKET, &image.file_name).unwrap();
+        let avif = s3.get(AVIF_BUCKET, &image.file_name).unwrap();
+        let original = s3.get(ORIGIN_BUCKET, &image.original_key()).unwrap();
+        assert_eq!(preview.content_type, "image/avif");
+        assert_eq!(avif.content_type, "image/avif");
+        assert_eq!(original.content_type, "image/png");
+        assert_eq!(original.body, support::png());
+        assert_eq!(
+            image.size,
+            (support::png().len() + preview.body.len() + avif.body.len()) as i64
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_rejects_files_over_the_size_limit() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let form = MultipartForm::new().add_part(
+            "file",
+            Part::bytes(vec![0; 1024 * 1024 + 1])
+                .file_name("big.png")
+                .mime_type("image/png"),
+        );
+
+        SettingsService::set(&ctx.db, "upload_max_size_mb", "1")
+            .await
+            .unwrap();
+        let response = request
+            .post("/api/upload/jwt?quality=80")
+            .add_header(key, value)
+            .multipart(form)
+            .await;
+        SettingsService::set(&ctx.db, "upload_max_size_mb", "50")
+            .await
+            .unwrap();
+
+        assert_eq!(response.status_code(), 400, "{}", response.text());
+        assert!(response.text().contains("File too large"));
+        assert_eq!(user_images(&ctx).await, 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn confirm_saves_presigned_upload() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        let response = request
+            .post("/api/presign/jwt")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({
+                "fileName": "cat.png",
+                "contentType": "image/png",
+                "size": 100,
+            }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: serde_json::Value = response.json();
+        let file_name = body["fileName"].as_str().unwrap().to_string();
+        assert!(body["uploadUrl"].as_str().unwrap().contains(&file_name));
+        s3.put(R2_BUCKET, &file_name, support::png());
+
+        let response = request
+            .post("/api/confirm/jwt?quality=80")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({
+                "fileName": file_name,
+                "size": 100,
+                "isPublic": false,
+            }))
+            .await;
+
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        assert!(response.json::<serde_json::Value>()["url"].is_null());
+        let image = images::Entity::find().one(&ctx.db).await.unwrap().unwrap();
+        assert_eq!(image.user_pid, Some(Uuid::parse_str(USER1_PID).unwrap()));
+        assert!(!image.public);
+        // counts the derivatives too
+        assert!(image.size > support::png().len() as i64);
+        assert!(!s3.contains(R2_BUCKET, &file_name));
+        assert!(tmps::Entity::find().all(&ctx.db).await.unwrap().is_empty());
+
+        // a presigned upload is confirmed once
+        let response = request
+            .post("/api/confirm/jwt?quality=80")
+            .add_header(key, value)
+            .json(&serde_json::json!({
+                "fileName": file_name,
+                "size": 100,
+                "isPublic": false,
+            }))
+            .await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn confirm_checks_the_stored_size() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        set_quota(&ctx, Some(1000), None).await;
+
+        let response = request
+            .post("/api/presign/jwt")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({
+                "fileName": "cat.png",
+                "contentType": "image/png",
+                "size": 10,
+            }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let body: serde_json::Value = response.json();
+        let file_name = body["fileName"].as_str().unwrap().to_string();
+        // more than claimed, and more than fits
+        s3.put(R2_BUCKET, &file_name, vec![0u8; 2000]);
+
+        let response = request
+            .post("/api/confirm/jwt?quality=80")
+            .add_header(key, value)
+            .json(&serde_json::json!({
+                "fileName": file_name,
+                "size": 10,
+                "isPublic": true,
+            }))
+            .await;
+
+        assert_eq!(response.status_code(), 413);
+        assert_eq!(user_images(&ctx).await, 0);
+        assert!(!s3.contains(R2_BUCKET, &file_name));
+        assert!(tmps::Entity::find().all(&ctx.db).await.unwrap().is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn presign_rejects_claimed_size_over_quota() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        set_quota(&ctx, Some(1000), None).await;
+
+        let response = request
+            .post("/api/presign/jwt")
+            .add_header(key, value)
+            .json(&serde_json::json!({
+                "fileName": "cat.png",
+                "contentType": "image/png",
+                "size": 2000,
+            }))
+            .await;
+
+        assert_eq!(response.status_code(), 413);
+        assert!(tmps::Entity::find().all(&ctx.db).await.unwrap().is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn unimplemented_confirm_routes_answer_an_error() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let response = request
+            .post("/api/confirm")
+            .json(&serde_json::json!({
+                "fileName": "cat.png",
+                "contentType": "image/png",
+                "size": 100,
+            }))
+            .await;
+        assert_eq!(response.status_code(), 501);
+        let response = request.post("/api/confirm/token").await;
+        assert_eq!(response.status_code(), 501);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn save_checks_usage_again() {
+    request::<App, _, _>(|_request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        set_quota(&ctx, None, Some(1)).await;
+        let quota =
+            Quota::for_user(&users::Model::find_by_pid(&ctx.db, USER1_PID).await.unwrap()).await;
+        // an upload that passed the first check, while another one landed
+        support::create_image(&ctx, Some(USER1_PID)).await;
+        let uuid = Uuid::now_v7();
+        let r = UploadResult {
+            url: format!("http://localhost/i/{uuid}.avif"),
+            file_name: format!("{uuid}.avif"),
+            is_public: true,
+            user_id: Some(Uuid::parse_str(USER1_PID).unwrap()),
+            uuid,
+            raw_name: "cat.png".to_string(),
+            size: 10,
+            delete_token: None,
+            expires_at: None,
+            max_views: None,
+        };
+
+        let result = save_within_quota(&ctx, &r, &quota).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::CustomError(StatusCode::PAYLOAD_TOO_LARGE, _))
+        ));
+        assert_eq!(user_images(&ctx).await, 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_from_url_rehosts_image() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let addr = serve_png().await;
+        let url = format!("http://{addr}/cat.png");
+
+        let response = request
+            .post("/api/upload/url/jwt?quality=80")
+            .json(&serde_json::json!({ "url": url }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        // loopback is refused unless allowed
+        let response = request
+            .post("/api/upload/url/jwt?quality=80")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({ "url": url }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("不允许访问该地址"));
+        assert_eq!(user_images(&ctx).await, 0);
+
+        SettingsService::set(&ctx.db, "url_upload_allowed_ips", "127.0.0.1")
+            .await
+            .unwrap();
+        let response = request
+            .post("/api/upload/url/jwt?quality=80")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({ "url": url }))
+            .await;
+        let photo = request
+            .post("/api/upload/url/jwt?quality=80&public=false")
+            .add_header(key, value)
+            .json(&serde_json::json!({ "url": format!("http://{addr}/photo") }))
+            .await;
+        SettingsService::set(&ctx.db, "url_upload_allowed_ips", "")
+            .await
+            .unwrap();
+
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        assert_eq!(photo.status_code(), 200, "{}", photo.text());
+        assert!(photo.json::<serde_json::Value>()["url"].is_null());
+        let images = images::Entity::find().all(&ctx.db).await.unwrap();
+        let mut names: Vec<&str> = images.iter().map(|image| image.raw_name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["cat.png", "photo.png"]);
+        for image in &images {
+            assert_eq!(image.user_pid, Some(Uuid::parse_str(USER1_PID).unwrap()));
+            assert!(s3.contains(AVIF_BUCKET, &image.file_name));
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_base64_decodes_payloads() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let encoded = BASE64_STANDARD.encode(support::png());
+        // wrapped like clipboard tools do
+        let (head, tail) = encoded.split_at(40);
+        let data_uri = format!("data:image/png;base64,{head}\n{tail}");
+
+        let response = request
+            .post("/api/upload/base64/jwt?quality=80")
+            .add_header(key.clone(), value.clone())
+            .json(&serde_json::json!({ "data": data_uri }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+
+        let response = request
+            .post("/api/upload/base64/jwt?quality=80")
+            .add_header(key, value)
+            .json(&serde_json::json!({ "data": encoded, "fileName": "cat.png" }))
+            .await;
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+
+        let images = images::Entity::find().all(&ctx.db).await.unwrap();
+        let mut names: Vec<&str> = images.iter().map(|image| image.raw_name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["cat.png", "image.png"]);
+        for image in &images {
+            assert_eq!(image.user_pid, Some(Uuid::parse_str(USER1_PID).unwrap()));
+            assert!(s3.contains(AVIF_BUCKET, &image.file_name));
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_base64_rejects_bad_payloads() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        support::fake_storage(&ctx).await;
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        for (data, error) in [
+            ("data:image/png,not-base64", "Data URI is not base64"),
+            ("data:image/png;base64", "Invalid data URI"),
+            ("!!! not base64 !!!", "Invalid base64 data"),
+        ] {
+            let response = request
+                .post("/api/upload/base64/jwt?quality=80")
+                .add_header(key.clone(), value.clone())
+                .json(&serde_json::json!({ "data": data }))
+                .await;
+            assert_eq!(response.status_code(), 400, "{data}");
+            assert!(response.text().contains(error), "{data}");
+        }
+        assert_eq!(user_images(&ctx).await, 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn anonymous_base64_upload_needs_everyone_allowed() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        support::fake_storage(&ctx).await;
+        let payload = serde_json::json!({
+            "data": BASE64_STANDARD.encode(support::png()),
+            "fileName": "cat.png",
+        });
+
+        SettingsService::set(&ctx.db, "allow_everyone_upload", "false")
+            .await
+            .unwrap();
+        let refused = request
+            .post("/api/upload/base64?quality=80")
+            .json(&payload)
+            .await;
+        SettingsService::set(&ctx.db, "allow_everyone_upload", "true")
+            .await
+            .unwrap();
+        let response = request
+            .post("/api/upload/base64?quality=80")
+            .json(&payload)
+            .await;
+
+        assert_eq!(refused.status_code(), 401, "{}", refused.text());
+        assert_eq!(response.status_code(), 200, "{}", response.text());
+        let body: serde_json::Value = response.json();
+        assert!(body["deleteToken"].as_str().is_some());
+        let image = images::Entity::find().one(&ctx.db).await.unwrap().unwrap();
+        assert_eq!(image.user_pid, None);
+    })
+    .await;
+}
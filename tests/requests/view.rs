@@ -0,0 +1,454 @@
+use AetherPix::{
+    app::App,
+    models::{image_tags, images, tags},
+};
+use loco_rs::{TestServer, app::AppContext, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID, USER2_PID};
+
+async fn tag(ctx: &AppContext, image_id: i32, names: &[&str]) {
+    let names: Vec<String> = names.iter().map(|n| (*n).to_string()).collect();
+    let owner = Uuid::parse_str(USER1_PID).unwrap();
+    let tags = tags::Model::find_or_create(&ctx.db, owner, &names)
+        .await
+        .unwrap();
+    let tag_ids: Vec<i32> = tags.iter().map(|t| t.id).collect();
+    image_tags::Model::attach(&ctx.db, image_id, &tag_ids)
+        .await
+        .unwrap();
+}
+
+async fn list(request: &TestServer, ctx: &AppContext, query: &str) -> serde_json::Value {
+    let (key, value) = support::auth_header(ctx, USER1_PID).await;
+    let response = request
+        .get(&format!("/api/view/list?limit=20&{query}"))
+        .add_header(key, value)
+        .await;
+    assert_eq!(response.status_code(), 200, "{}", response.text());
+
+    response.json()
+}
+
+/// Ids of the listed images, in listing order.
+fn ids(body: &serde_json::Value) -> Vec<i32> {
+    body["images"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|image| i32::try_from(image["id"].as_i64().unwrap()).unwrap())
+        .collect()
+}
+
+async fn list_ids(request: &TestServer, ctx: &AppContext, query: &str) -> Vec<i32> {
+    let mut ids = ids(&list(request, ctx, query).await);
+    ids.sort_unstable();
+    ids
+}
+
+async fn create_image(ctx: &AppContext, raw_name: &str, day: &str, public: bool) -> i32 {
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    let created_at = chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+        .and_utc();
+    let mut image = image.into_active_model();
+    image.raw_name = ActiveValue::Set(raw_name.to_string());
+    image.created_at = ActiveValue::Set(created_at.into());
+    image.public = ActiveValue::Set(public);
+
+    image.update(&ctx.db).await.unwrap().id
+}
+
+#[tokio::test]
+#[serial]
+async fn filters_by_tags() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let cat = support::create_image(&ctx, Some(USER1_PID)).await.id;
+        let both = support::create_image(&ctx, Some(USER1_PID)).await.id;
+        let untagged = support::create_image(&ctx, Some(USER1_PID)).await.id;
+        support::create_image(&ctx, Some(USER2_PID)).await;
+        tag(&ctx, cat, &["cats"]).await;
+        tag(&ctx, both, &["cats", "dogs"]).await;
+
+        assert_eq!(
+            list_ids(&request, &ctx, "").await,
+            vec![cat, both, untagged]
+        );
+        assert_eq!(list_ids(&request, &ctx, "tags=Cats").await, vec![cat, both]);
+        assert_eq!(
+            list_ids(&request, &ctx, "tags=cats,dogs").await,
+            vec![cat, both]
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "tags=cats,dogs&tag_mode=all").await,
+            vec![both]
+        );
+        // an unknown tag can't be on every image
+        assert!(
+            list_ids(&request, &ctx, "tags=cats,birds&tag_mode=all")
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "tags=cats,birds").await,
+            vec![cat, both]
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn searches_and_filters() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let beach = create_image(&ctx, "Beach Day.PNG", "2026-07-01", true).await;
+        let sunset = create_image(&ctx, "sunset.jpg", "2026-07-02", false).await;
+        let city = create_image(&ctx, "city.png", "2026-08-15", true).await;
+
+        assert_eq!(list_ids(&request, &ctx, "q=beach").await, vec![beach]);
+        assert_eq!(
+            list_ids(&request, &ctx, "q=%20C%20").await,
+            vec![beach, city]
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "format=.PNG").await,
+            vec![beach, city]
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "visibility=private").await,
+            vec![sunset]
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "visibility=public").await,
+            vec![beach, city]
+        );
+        // both days are included
+        assert_eq!(
+            list_ids(&request, &ctx, "from=2026-07-01&to=2026-07-02").await,
+            vec![beach, sunset]
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "from=2026-07-02").await,
+            vec![sunset, city]
+        );
+        assert_eq!(
+            list_ids(&request, &ctx, "q=c&format=png&to=2026-07-31").await,
+            vec![beach]
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_invalid_filters() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        for query in ["from=2026-13-01", "to=yesterday", "format=p%25g"] {
+            let response = request
+                .get(&format!("/api/view/list?limit=20&{query}"))
+                .add_header(key.clone(), value.clone())
+                .await;
+            assert_eq!(response.status_code(), 400, "{query}");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn sorts_by_date_and_name() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let b = create_image(&ctx, "b.png", "2026-07-01", true).await;
+        let c = create_image(&ctx, "C.png", "2026-07-02", true).await;
+        let a = create_image(&ctx, "a.png", "2026-07-03", true).await;
+
+        assert_eq!(ids(&list(&request, &ctx, "").await), vec![a, c, b]);
+        assert_eq!(
+            ids(&list(&request, &ctx, "sort=newest").await),
+            vec![a, c, b]
+        );
+        assert_eq!(
+            ids(&list(&request, &ctx, "sort=oldest").await),
+            vec![b, c, a]
+        );
+        assert_eq!(
+            ids(&list(&request, &ctx, "page=0&sort=name_asc").await),
+            vec![a, b, c]
+        );
+        assert_eq!(
+            ids(&list(&request, &ctx, "page=0&sort=name_desc").await),
+            vec![c, b, a]
+        );
+
+        // name order has no cursor
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let response = request
+            .get("/api/view/list?limit=20&sort=name_asc")
+            .add_header(key, value)
+            .await;
+        assert_eq!(response.status_code(), 400);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn pages_by_cursor() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        // images sharing a timestamp are ordered by id
+        let first = create_image(&ctx, "a.png", "2026-07-01", true).await;
+        let second = create_image(&ctx, "b.png", "2026-07-02", true).await;
+        let third = create_image(&ctx, "c.png", "2026-07-02", true).await;
+        let fourth = create_image(&ctx, "d.png", "2026-07-02", true).await;
+        let fifth = create_image(&ctx, "e.png", "2026-07-03", true).await;
+
+        for (sort, expected) in [
+            ("newest", vec![fifth, fourth, third, second, first]),
+            ("oldest", vec![first, second, third, fourth, fifth]),
+        ] {
+            let mut seen = Vec::new();
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut url = format!("/api/view/list?limit=2&sort={sort}&with_total=true");
+                if let Some(cursor) = &cursor {
+                    url.push_str(&format!("&cursor={cursor}"));
+                }
+                let response = request
+                    .get(&url)
+                    .add_header(key.clone(), value.clone())
+                    .await;
+                assert_eq!(response.status_code(), 200);
+                let body: serde_json::Value = response.json();
+                assert_eq!(body["total"], 5);
+                assert!(body.get("pages").is_none());
+                seen.extend(ids(&body));
+
+                match body["nextCursor"].as_str() {
+                    Some(next) => cursor = Some(next.to_string()),
+                    None => break,
+                }
+            }
+            assert_eq!(seen, expected, "{sort}");
+        }
+
+        // new uploads don't shift a cursor walk
+        let response = request
+            .get("/api/view/list?limit=2")
+            .add_header(key.clone(), value.clone())
+            .await;
+        let body: serde_json::Value = response.json();
+        let next = body["nextCursor"].as_str().unwrap().to_string();
+        create_image(&ctx, "f.png", "2026-07-04", true).await;
+        let response = request
+            .get(&format!("/api/view/list?limit=2&cursor={next}"))
+            .add_header(key, value)
+            .await;
+        let body: serde_json::Value = response.json();
+        assert_eq!(ids(&body), vec![third, second]);
+        assert!(body.get("total").is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn pages_by_offset() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let first = create_image(&ctx, "a.png", "2026-07-01", true).await;
+        create_image(&ctx, "b.png", "2026-07-02", true).await;
+        create_image(&ctx, "c.png", "2026-07-03", true).await;
+
+        let response = request
+            .get("/api/view/list?limit=2&page=1&sort=name_desc")
+            .add_header(key, value)
+            .await;
+
+        assert_eq!(response.status_code(), 200);
+        let body: serde_json::Value = response.json();
+        assert_eq!(ids(&body), vec![first]);
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["pages"], 2);
+        assert!(body["nextCursor"].is_null());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_page_with_cursor() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        for day in ["2026-07-01", "2026-07-02", "2026-07-03"] {
+            create_image(&ctx, "a.png", day, true).await;
+        }
+        let body: serde_json::Value = request
+            .get("/api/view/list?limit=2")
+            .add_header(key.clone(), value.clone())
+            .await
+            .json();
+        let next = body["nextCursor"].as_str().unwrap();
+
+        let response = request
+            .get(&format!("/api/view/list?limit=2&page=0&cursor={next}"))
+            .add_header(key, value)
+            .await;
+
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("Use either page or cursor"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_invalid_cursor() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+
+        for cursor in ["abc", "zzzzzzzzzzzzzzzzzzzzzzzz"] {
+            let response = request
+                .get(&format!("/api/view/list?limit=2&cursor={cursor}"))
+                .add_header(key.clone(), value.clone())
+                .await;
+            assert_eq!(response.status_code(), 400, "{cursor}");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn caches_only_images_that_stay() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let in_an_hour = Some(chrono::Utc::now() + chrono::TimeDelta::hours(1));
+
+        let public = support::create_image(&ctx, Some(USER1_PID)).await;
+        let expiring = support::create_image(&ctx, None).await;
+        let expiring = support::set_expiry(&ctx, expiring, in_an_hour, None, 0).await;
+        let limited = support::create_image(&ctx, None).await;
+        let limited = support::set_expiry(&ctx, limited, None, Some(5), 0).await;
+
+        for (image, cache_control) in [
+            (&public, "public, max-age=2592000, immutable"),
+            (&expiring, "private, no-store"),
+            (&limited, "private, no-store"),
+        ] {
+            support::store_image(&s3, image);
+            let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+            assert_eq!(response.status_code(), 200);
+            assert_eq!(response.header("cache-control"), cache_control);
+
+            // a revalidation answers the same
+            let response = request
+                .get(&format!("/api/view/{}", image.file_name))
+                .add_header(
+                    axum::http::header::IF_NONE_MATCH,
+                    axum::http::HeaderValue::from_static("\"etag\""),
+                )
+                .await;
+            assert_eq!(response.status_code(), 304);
+            assert_eq!(response.header("cache-control"), cache_control);
+        }
+
+        // the preview is for its owner only
+        let (key, value) = support::auth_header(&ctx, USER1_PID).await;
+        let response = request
+            .get(&format!("/api/view/preview/{}", public.file_name))
+            .add_header(key, value)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.header("cache-control"), "private, no-cache");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn counts_only_served_views() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let image = support::create_image(&ctx, None).await;
+        let image = support::set_expiry(&ctx, image, None, Some(1), 0).await;
+
+        // nothing stored yet
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+        assert_eq!(response.status_code(), 404);
+
+        support::store_image(&s3, &image);
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+        assert_eq!(response.status_code(), 200);
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+        assert_eq!(response.status_code(), 404);
+        let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+        assert_eq!(image.views, 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn does_not_serve_private_images() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+        support::store_image(&s3, &image);
+        image
+            .clone()
+            .into_active_model()
+            .set_public(&ctx.db, false)
+            .await
+            .unwrap();
+
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn does_not_cache_taken_down_images() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+        let s3 = support::fake_storage(&ctx).await;
+        let image = support::create_image(&ctx, Some(USER1_PID)).await;
+        support::store_image(&s3, &image);
+        image
+            .clone()
+            .into_active_model()
+            .set_taken_down(&ctx.db, Some("copyright".to_string()))
+            .await
+            .unwrap();
+
+        let response = request.get(&format!("/api/view/{}", image.file_name)).await;
+
+        assert_eq!(response.status_code(), 451);
+        assert_eq!(response.header("cache-control"), "private, no-store");
+        response.assert_json(&serde_json::json!({
+            "error": "Unavailable For Legal Reasons",
+            "description": "Image was taken down",
+        }));
+    })
+    .await;
+}
@@ -0,0 +1,170 @@
+//! Helpers shared by the model, request and worker tests.
+
+pub mod s3;
+
+use AetherPix::{
+    common::{client, settings::SettingsService, zip::ZipWriter},
+    controllers::upload::TempUpload,
+    models::{_entities::images::Location, images, users},
+    workers::thumbnail,
+};
+use axum::http::{HeaderName, HeaderValue};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker};
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+use uuid::Uuid;
+
+pub use s3::FakeS3;
+
+pub const USER1_PID: &str = "11111111-1111-1111-1111-111111111111";
+pub const USER2_PID: &str = "22222222-2222-2222-2222-222222222222";
+
+pub const ORIGIN_BUCKET: &str = "origin";
+pub const PREVIEW_BUCKET: &str = "preview";
+pub const AVIF_BUCKET: &str = "avif";
+pub const R2_BUCKET: &str = "r2";
+
+/// Points both storage clients at a fresh [`FakeS3`]. Meant to run after
+/// seeding, which would overwrite the settings.
+pub async fn fake_storage(ctx: &AppContext) -> FakeS3 {
+    let s3 = FakeS3::start().await;
+    let endpoint = s3.endpoint();
+    let settings = [
+        ("aws_endpoint_url", endpoint.as_str()),
+        ("aws_access_key_id", "test"),
+        ("aws_secret_access_key", "test"),
+        ("aws_region", "garage"),
+        ("origin_bucket_name", ORIGIN_BUCKET),
+        ("preview_bucket_name", PREVIEW_BUCKET),
+        ("avif_bucket_name", AVIF_BUCKET),
+        ("r2_endpoint_url", endpoint.as_str()),
+        ("r2_access_key_id", "test"),
+        ("r2_secret_access_key", "test"),
+        ("r2_bucket_name", R2_BUCKET),
+        ("local_base_url", "http://localhost/i"),
+        ("r2_base_url", "http://r2.localhost"),
+    ];
+    for (key, value) in settings {
+        SettingsService::set(&ctx.db, key, value).await.unwrap();
+    }
+    client::reload().await.unwrap();
+
+    s3
+}
+
+/// Inserts an image row owned by `user_pid`, nothing is stored for it.
+pub async fn create_image(ctx: &AppContext, user_pid: Option<&str>) -> images::Model {
+    let uuid = Uuid::now_v7();
+    images::ActiveModel {
+        url: ActiveValue::Set(format!("http://localhost/i/{uuid}.avif")),
+        user_pid: ActiveValue::Set(user_pid.map(|pid| Uuid::parse_str(pid).unwrap())),
+        public: ActiveValue::Set(true),
+        raw_name: ActiveValue::Set("cat.png".to_string()),
+        original_ext: ActiveValue::Set("png".to_string()),
+        file_name: ActiveValue::Set(format!("{uuid}.avif")),
+        uuid: ActiveValue::Set(uuid),
+        location: ActiveValue::Set(Location::Local),
+        size: ActiveValue::Set(1024),
+        views: ActiveValue::Set(0),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await
+    .unwrap()
+}
+
+/// Sets when `image` expires and how many of its views are left.
+pub async fn set_expiry(
+    ctx: &AppContext,
+    image: images::Model,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    max_views: Option<i32>,
+    views: i32,
+) -> images::Model {
+    let mut image = image.into_active_model();
+    image.expires_at = ActiveValue::Set(expires_at.map(Into::into));
+    image.max_views = ActiveValue::Set(max_views);
+    image.views = ActiveValue::Set(views);
+
+    image.update(&ctx.db).await.unwrap()
+}
+
+/// Stores every object of `image` where its location says.
+pub fn store_image(s3: &FakeS3, image: &images::Model) {
+    match image.location {
+        Location::Local => {
+            s3.put(ORIGIN_BUCKET, &image.original_key(), "original");
+            s3.put(PREVIEW_BUCKET, &image.file_name, "preview");
+            s3.put(AVIF_BUCKET, &image.file_name, "avif");
+        }
+        Location::R2 => {
+            s3.put(R2_BUCKET, &image.file_name, "avif");
+            s3.put(
+                R2_BUCKET,
+                &client::r2_original_key(&image.original_key()),
+                "original",
+            );
+        }
+    }
+}
+
+/// Whether any object of `image` is still stored, wherever it lives.
+pub fn has_objects(s3: &FakeS3, image: &images::Model) -> bool {
+    let original_key = image.original_key();
+    s3.contains(ORIGIN_BUCKET, &original_key)
+        || s3.contains(PREVIEW_BUCKET, &image.file_name)
+        || s3.contains(AVIF_BUCKET, &image.file_name)
+        || s3.contains(R2_BUCKET, &image.file_name)
+        || s3.contains(R2_BUCKET, &client::r2_original_key(&original_key))
+}
+
+/// Bearer header of a JWT for the user with `pid`.
+pub async fn auth_header(ctx: &AppContext, pid: &str) -> (HeaderName, HeaderValue) {
+    let user = users::Model::find_by_pid(&ctx.db, pid).await.unwrap();
+    let jwt = ctx.config.get_jwt_config().unwrap();
+    let token = user.generate_jwt(&jwt.secret, jwt.expiration).unwrap();
+
+    (
+        HeaderName::from_static("authorization"),
+        HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+    )
+}
+
+/// A small PNG the thumbnail worker can encode.
+pub fn png() -> Vec<u8> {
+    let image = image::RgbImage::from_fn(16, 16, |x, y| {
+        image::Rgb([(x * 16) as u8, (y * 16) as u8, 128])
+    });
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+
+    png.into_inner()
+}
+
+/// Uploads [`png`] for `user_pid` the way the upload routes do, encoding it
+/// right away.
+pub async fn upload_png(ctx: &AppContext, user_pid: Option<&str>) -> images::Model {
+    let mut upload = TempUpload::create("cat.png").await.unwrap();
+    upload.write(&png()).await.unwrap();
+    let (mut r, args) = upload.finish(ctx, 80, None).await.unwrap();
+    r.user_id = user_pid.map(|pid| Uuid::parse_str(pid).unwrap());
+    let image = images::Model::save_local_with_result(&ctx.db, &r)
+        .await
+        .unwrap();
+    let path = args.tmp_file_guard.0.clone();
+    thumbnail::Worker::build(ctx).perform(args).await.unwrap();
+    let _ = tokio::fs::remove_file(path).await;
+
+    images::Model::find_by_id(&ctx.db, image.id).await.unwrap()
+}
+
+/// A ZIP archive of the given files, stored uncompressed.
+pub async fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Vec::new());
+    for (name, data) in files {
+        let mut entry = zip.start_entry(name, chrono::Utc::now()).await.unwrap();
+        entry.write(data).await.unwrap();
+        entry.close().await.unwrap();
+    }
+
+    zip.finish().await.unwrap()
+}
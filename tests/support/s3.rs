@@ -0,0 +1,264 @@
+//! An in-memory stand-in for the S3 API, just what the app calls: objects
+//! are put, read, listed and deleted by path-style requests.
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use percent_encoding::percent_decode_str;
+
+#[derive(Debug, Clone)]
+pub struct Object {
+    pub body: Bytes,
+    pub content_type: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+type Objects = Arc<Mutex<BTreeMap<(String, String), Object>>>;
+
+#[derive(Clone)]
+pub struct FakeS3 {
+    pub addr: SocketAddr,
+    objects: Objects,
+    failing: Arc<AtomicBool>,
+}
+
+impl FakeS3 {
+    /// Serves the API on a random loopback port.
+    pub async fn start() -> Self {
+        let objects = Objects::default();
+        let failing = Arc::new(AtomicBool::new(false));
+        let state = (objects.clone(), failing.clone());
+        let app = Router::new().fallback(
+            move |method: Method, uri: Uri, headers: HeaderMap, body: Bytes| {
+                let (objects, failing) = state.clone();
+                async move { handle(&objects, &failing, &method, &uri, &headers, body) }
+            },
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        Self {
+            addr,
+            objects,
+            failing,
+        }
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn put(&self, bucket: &str, key: &str, body: impl Into<Bytes>) {
+        self.put_at(bucket, key, body, Utc::now());
+    }
+
+    pub fn put_at(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: impl Into<Bytes>,
+        last_modified: DateTime<Utc>,
+    ) {
+        self.objects.lock().unwrap().insert(
+            (bucket.to_string(), key.to_string()),
+            Object {
+                body: body.into(),
+                content_type: "application/octet-stream".to_string(),
+                last_modified,
+            },
+        );
+    }
+
+    pub fn get(&self, bucket: &str, key: &str) -> Option<Object> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(&(bucket.to_string(), key.to_string()))
+            .cloned()
+    }
+
+    pub fn contains(&self, bucket: &str, key: &str) -> bool {
+        self.get(bucket, key).is_some()
+    }
+
+    pub fn remove(&self, bucket: &str, key: &str) {
+        self.objects
+            .lock()
+            .unwrap()
+            .remove(&(bucket.to_string(), key.to_string()));
+    }
+
+    /// Makes every request answer 500 until turned off again.
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::SeqCst);
+    }
+}
+
+fn handle(
+    objects: &Objects,
+    failing: &AtomicBool,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> Response {
+    if failing.load(Ordering::SeqCst) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "InternalError");
+    }
+
+    let path = percent_decode_str(uri.path().trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .into_owned();
+    let (bucket, key) = match path.split_once('/') {
+        Some((bucket, key)) if !key.is_empty() => (bucket.to_string(), key.to_string()),
+        _ => (path.trim_end_matches('/').to_string(), String::new()),
+    };
+
+    if key.is_empty() {
+        return match *method {
+            Method::HEAD => StatusCode::OK.into_response(),
+            Method::GET => list(objects, &bucket, uri.query().unwrap_or_default()),
+            _ => error(StatusCode::NOT_IMPLEMENTED, "NotImplemented"),
+        };
+    }
+
+    let mut objects = objects.lock().unwrap();
+    let id = (bucket, key);
+    match *method {
+        Method::PUT => {
+            let content_type = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            let body = if is_aws_chunked(headers) {
+                decode_aws_chunked(&body)
+            } else {
+                body
+            };
+            objects.insert(
+                id,
+                Object {
+                    body,
+                    content_type,
+                    last_modified: Utc::now(),
+                },
+            );
+            ([(header::ETAG, "\"etag\"")], StatusCode::OK).into_response()
+        }
+        Method::GET | Method::HEAD => match objects.get(&id) {
+            Some(object) => {
+                let headers = [
+                    (header::CONTENT_TYPE, object.content_type.clone()),
+                    (header::CONTENT_LENGTH, object.body.len().to_string()),
+                    (header::ETAG, "\"etag\"".to_string()),
+                    (
+                        header::LAST_MODIFIED,
+                        object
+                            .last_modified
+                            .format("%a, %d %b %Y %H:%M:%S GMT")
+                            .to_string(),
+                    ),
+                ];
+                if *method == Method::HEAD {
+                    (headers, StatusCode::OK).into_response()
+                } else {
+                    (headers, object.body.clone()).into_response()
+                }
+            }
+            None if *method == Method::HEAD => StatusCode::NOT_FOUND.into_response(),
+            None => error(StatusCode::NOT_FOUND, "NoSuchKey"),
+        },
+        Method::DELETE => {
+            objects.remove(&id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => error(StatusCode::NOT_IMPLEMENTED, "NotImplemented"),
+    }
+}
+
+/// ListObjectsV2, every matching key on a single page.
+fn list(objects: &Objects, bucket: &str, query: &str) -> Response {
+    let prefix = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("prefix="))
+        .map(|p| percent_decode_str(p).decode_utf8_lossy().into_owned())
+        .unwrap_or_default();
+    let objects = objects.lock().unwrap();
+    let contents: Vec<String> = objects
+        .iter()
+        .filter(|((b, key), _)| b == bucket && key.starts_with(&prefix))
+        .map(|((_, key), object)| {
+            format!(
+                "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>\"etag\"</ETag>\
+                 <Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                key,
+                object.last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                object.body.len()
+            )
+        })
+        .collect();
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <ListBucketResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Name>{}</Name><Prefix>{}</Prefix><KeyCount>{}</KeyCount><MaxKeys>1000</MaxKeys>\
+         <IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+        bucket,
+        prefix,
+        contents.len(),
+        contents.concat()
+    );
+
+    ([(header::CONTENT_TYPE, "application/xml")], body).into_response()
+}
+
+fn error(status: StatusCode, code: &str) -> Response {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>{}</Code><Message>{}</Message></Error>",
+        code, code
+    );
+
+    (status, [(header::CONTENT_TYPE, "application/xml")], body).into_response()
+}
+
+fn is_aws_chunked(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("aws-chunked"))
+}
+
+/// Strips the chunk framing and trailers of an `aws-chunked` body.
+fn decode_aws_chunked(body: &[u8]) -> Bytes {
+    let mut data = Vec::new();
+    let mut rest = body;
+    while let Some(end) = rest.windows(2).position(|w| w == b"\r\n") {
+        let line = String::from_utf8_lossy(&rest[..end]);
+        let size = line.split(';').next().unwrap_or_default();
+        let Ok(size) = usize::from_str_radix(size.trim(), 16) else {
+            break;
+        };
+        if size == 0 {
+            break;
+        }
+        let start = end + 2;
+        data.extend_from_slice(&rest[start..start + size]);
+        rest = &rest[(start + size + 2).min(rest.len())..];
+    }
+
+    data.into()
+}
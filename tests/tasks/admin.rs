@@ -9,9 +9,30 @@ use serial_test::serial;
 async fn test_can_run_admin() {
     let boot = boot_test::<App>().await.unwrap();
 
+    let vars = task::Vars::from_cli_args(vec![
+        ("username".to_string(), "admin".to_string()),
+        ("password".to_string(), "12341234".to_string()),
+        ("email".to_string(), "admin@example.com".to_string()),
+    ]);
     assert!(
-        run_task::<App>(&boot.app_context, Some(&"admin".to_string()), &task::Vars::default())
+        run_task::<App>(&boot.app_context, Some(&"admin".to_string()), &vars)
             .await
             .is_ok()
     );
 }
+
+#[tokio::test]
+#[serial]
+async fn test_admin_requires_username() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    assert!(
+        run_task::<App>(
+            &boot.app_context,
+            Some(&"admin".to_string()),
+            &task::Vars::default()
+        )
+        .await
+        .is_err()
+    );
+}
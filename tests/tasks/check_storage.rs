@@ -0,0 +1,112 @@
+use AetherPix::{app::App, models::images};
+use loco_rs::{app::AppContext, task, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+
+use loco_rs::boot::run_task;
+use serial_test::serial;
+
+use crate::support::{self, AVIF_BUCKET, ORIGIN_BUCKET, PREVIEW_BUCKET, R2_BUCKET, USER1_PID};
+
+async fn check_storage(ctx: &AppContext, fix: Option<&str>) {
+    let vars = task::Vars::from_cli_args(
+        fix.map(|fix| ("fix".to_string(), fix.to_string()))
+            .into_iter()
+            .collect(),
+    );
+    run_task::<App>(ctx, Some(&"check_storage".to_string()), &vars)
+        .await
+        .unwrap();
+}
+
+/// An image uploaded long enough ago for missing objects to count.
+async fn create_old_image(ctx: &AppContext) -> images::Model {
+    make_old(ctx, support::create_image(ctx, Some(USER1_PID)).await).await
+}
+
+async fn make_old(ctx: &AppContext, image: images::Model) -> images::Model {
+    let mut image = image.into_active_model();
+    image.created_at = ActiveValue::Set((chrono::Utc::now() - chrono::Duration::days(1)).into());
+
+    image.update(&ctx.db).await.unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_can_run_check_storage() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    assert!(
+        run_task::<App>(
+            &boot.app_context,
+            Some(&"check_storage".to_string()),
+            &task::Vars::default()
+        )
+        .await
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deletes_old_orphans_only() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let image = create_old_image(ctx).await;
+    support::store_image(&s3, &image);
+    let old = chrono::Utc::now() - chrono::Duration::days(2);
+    s3.put_at(ORIGIN_BUCKET, "originals/gone.png", "original", old);
+    s3.put_at(PREVIEW_BUCKET, "gone.avif", "preview", old);
+    s3.put_at(AVIF_BUCKET, "gone.avif", "avif", old);
+    s3.put_at(R2_BUCKET, "gone.avif", "avif", old);
+    // may still be uploading
+    s3.put(PREVIEW_BUCKET, "new.avif", "preview");
+
+    check_storage(ctx, None).await;
+    assert!(s3.contains(AVIF_BUCKET, "gone.avif"));
+
+    check_storage(ctx, Some("delete_orphans")).await;
+
+    assert!(!s3.contains(ORIGIN_BUCKET, "originals/gone.png"));
+    assert!(!s3.contains(PREVIEW_BUCKET, "gone.avif"));
+    assert!(!s3.contains(AVIF_BUCKET, "gone.avif"));
+    assert!(!s3.contains(R2_BUCKET, "gone.avif"));
+    assert!(s3.contains(PREVIEW_BUCKET, "new.avif"));
+    assert!(s3.contains(ORIGIN_BUCKET, &image.original_key()));
+    assert!(s3.contains(PREVIEW_BUCKET, &image.file_name));
+    assert!(s3.contains(AVIF_BUCKET, &image.file_name));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_requeues_or_marks_images_missing_objects() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let complete = create_old_image(ctx).await;
+    support::store_image(&s3, &complete);
+    // the derivatives are gone but the original is kept
+    let encodable = make_old(ctx, support::upload_png(ctx, Some(USER1_PID)).await).await;
+    s3.remove(PREVIEW_BUCKET, &encodable.file_name);
+    s3.remove(AVIF_BUCKET, &encodable.file_name);
+    let lost = create_old_image(ctx).await;
+    let recent = support::create_image(ctx, Some(USER1_PID)).await;
+
+    check_storage(ctx, Some("requeue,mark_broken")).await;
+
+    let find = |id| images::Model::find_by_id(&ctx.db, id);
+    assert!(!find(complete.id).await.unwrap().is_broken());
+    let encoded = find(encodable.id).await.unwrap();
+    assert!(!encoded.is_broken());
+    let preview = s3.get(PREVIEW_BUCKET, &encodable.file_name).unwrap().body;
+    let avif = s3.get(AVIF_BUCKET, &encodable.file_name).unwrap().body;
+    // nothing stored was replaced, so nothing is taken off
+    assert_eq!(
+        encoded.size,
+        encodable.size + (preview.len() + avif.len()) as i64
+    );
+    assert!(find(lost.id).await.unwrap().is_broken());
+    assert!(!find(recent.id).await.unwrap().is_broken());
+}
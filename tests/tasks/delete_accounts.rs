@@ -0,0 +1,50 @@
+use AetherPix::{app::App, models::users};
+use chrono::{Duration, Utc};
+use loco_rs::{task, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+
+use loco_rs::boot::run_task;
+use serial_test::serial;
+
+use crate::support::{USER1_PID, USER2_PID};
+
+#[tokio::test]
+#[serial]
+async fn test_can_run_delete_accounts() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    assert!(
+        run_task::<App>(
+            &boot.app_context,
+            Some(&"delete_accounts".to_string()),
+            &task::Vars::default()
+        )
+        .await
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deletes_accounts_past_grace_period() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let mut user = users::Model::find_by_pid(&ctx.db, USER1_PID)
+        .await
+        .unwrap()
+        .into_active_model();
+    user.deletes_at = ActiveValue::Set(Some((Utc::now() - Duration::minutes(1)).into()));
+    user.update(&ctx.db).await.unwrap();
+
+    run_task::<App>(
+        ctx,
+        Some(&"delete_accounts".to_string()),
+        &task::Vars::default(),
+    )
+    .await
+    .unwrap();
+
+    assert!(users::Model::find_by_pid(&ctx.db, USER1_PID).await.is_err());
+    assert!(users::Model::find_by_pid(&ctx.db, USER2_PID).await.is_ok());
+}
@@ -1,3 +1,7 @@
-
-
-pub mod admin;
\ No newline at end of file
+pub mod admin;
+pub mod check_storage;
+pub mod delete_accounts;
+pub mod purge_expired;
+pub mod regenerate;
+pub mod relocate;
+pub mod tier_images;
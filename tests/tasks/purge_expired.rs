@@ -0,0 +1,40 @@
+use AetherPix::{app::App, models::images};
+use chrono::{TimeDelta, Utc};
+use loco_rs::{boot::run_task, task, testing::prelude::*};
+use serial_test::serial;
+
+use crate::support;
+
+#[tokio::test]
+#[serial]
+async fn test_can_run_purge_expired() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let image = support::create_image(ctx, None).await;
+    let image = support::set_expiry(
+        ctx,
+        image,
+        Some(Utc::now() - TimeDelta::seconds(1)),
+        None,
+        0,
+    )
+    .await;
+    support::store_image(&s3, &image);
+    let live = support::create_image(ctx, None).await;
+
+    assert!(
+        run_task::<App>(
+            ctx,
+            Some(&"purge_expired".to_string()),
+            &task::Vars::default()
+        )
+        .await
+        .is_ok()
+    );
+
+    assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_err());
+    assert!(!support::has_objects(&s3, &image));
+    assert!(images::Model::find_by_id(&ctx.db, live.id).await.is_ok());
+}
@@ -0,0 +1,79 @@
+use AetherPix::{
+    app::App,
+    models::{_entities::regenerations::RegenerationStatus, regenerations},
+};
+use loco_rs::{task, testing::prelude::*};
+
+use loco_rs::boot::run_task;
+use serial_test::serial;
+
+use crate::support::{self, AVIF_BUCKET, USER1_PID};
+
+#[tokio::test]
+#[serial]
+async fn test_can_run_regenerate_derivatives() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    assert!(
+        run_task::<App>(
+            &boot.app_context,
+            Some(&"regenerate_derivatives".to_string()),
+            &task::Vars::default()
+        )
+        .await
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_regenerates_matching_images() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let image = support::upload_png(ctx, Some(USER1_PID)).await;
+    let avif = s3.get(AVIF_BUCKET, &image.file_name).unwrap().body;
+
+    let vars = task::Vars::from_cli_args(vec![
+        ("user".to_string(), USER1_PID.to_string()),
+        ("location".to_string(), "local".to_string()),
+        ("quality".to_string(), "50".to_string()),
+    ]);
+    run_task::<App>(ctx, Some(&"regenerate_derivatives".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let regeneration = regenerations::Model::find_recent(&ctx.db, 1)
+        .await
+        .unwrap()
+        .remove(0);
+    assert_eq!(regeneration.status, RegenerationStatus::Finished);
+    assert_eq!(regeneration.quality, 50);
+    assert_eq!((regeneration.total, regeneration.encoded), (1, 1));
+    assert_ne!(s3.get(AVIF_BUCKET, &image.file_name).unwrap().body, avif);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_regenerate_rejects_invalid_arguments() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    for (name, value) in [
+        ("quality", "0"),
+        ("location", "moon"),
+        ("from", "yesterday"),
+    ] {
+        let vars = task::Vars::from_cli_args(vec![(name.to_string(), value.to_string())]);
+        assert!(
+            run_task::<App>(
+                &boot.app_context,
+                Some(&"regenerate_derivatives".to_string()),
+                &vars
+            )
+            .await
+            .is_err(),
+            "{name}:{value}"
+        );
+    }
+}
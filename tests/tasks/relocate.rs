@@ -0,0 +1,71 @@
+use AetherPix::{
+    app::App,
+    models::{_entities::images::Location, images},
+};
+use loco_rs::{task, testing::prelude::*};
+
+use loco_rs::boot::run_task;
+use serial_test::serial;
+
+use crate::support::{self, R2_BUCKET, USER1_PID};
+
+#[tokio::test]
+#[serial]
+async fn test_can_run_relocate_images() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    let vars = task::Vars::from_cli_args(vec![("to".to_string(), "r2".to_string())]);
+    assert!(
+        run_task::<App>(
+            &boot.app_context,
+            Some(&"relocate_images".to_string()),
+            &vars
+        )
+        .await
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_relocate_images_requires_location() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    assert!(
+        run_task::<App>(
+            &boot.app_context,
+            Some(&"relocate_images".to_string()),
+            &task::Vars::default()
+        )
+        .await
+        .is_err()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_relocates_given_images() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let moved = support::create_image(ctx, Some(USER1_PID)).await;
+    let kept = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &moved);
+    support::store_image(&s3, &kept);
+
+    let vars = task::Vars::from_cli_args(vec![
+        ("to".to_string(), "r2".to_string()),
+        ("ids".to_string(), moved.id.to_string()),
+    ]);
+    run_task::<App>(ctx, Some(&"relocate_images".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let moved = images::Model::find_by_id(&ctx.db, moved.id).await.unwrap();
+    assert_eq!(moved.location, Location::R2);
+    assert!(s3.contains(R2_BUCKET, &moved.file_name));
+    let kept = images::Model::find_by_id(&ctx.db, kept.id).await.unwrap();
+    assert_eq!(kept.location, Location::Local);
+    assert!(!s3.contains(R2_BUCKET, &kept.file_name));
+}
@@ -0,0 +1,91 @@
+use AetherPix::{
+    app::App,
+    models::users,
+    workers::account_deleter::{Worker, WorkerArgs},
+};
+use chrono::{Duration, Utc};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, IntoActiveModel};
+use serial_test::serial;
+
+use crate::support::{self, USER1_PID, USER2_PID};
+
+/// Schedules deleting the user, already due.
+async fn make_due(ctx: &AppContext, pid: &str) -> users::Model {
+    let user = users::Model::find_by_pid(&ctx.db, pid)
+        .await
+        .unwrap()
+        .into_active_model()
+        .schedule_deletion(&ctx.db, Duration::days(1))
+        .await
+        .unwrap();
+    let mut user = user.into_active_model();
+    user.deletes_at = ActiveValue::Set(Some((Utc::now() - Duration::minutes(1)).into()));
+
+    user.update(&ctx.db).await.unwrap()
+}
+
+/// Makes deleting the user fail, through an sqlite trigger.
+async fn keep_user(ctx: &AppContext, user: &users::Model, kept: bool) {
+    let sql = if kept {
+        format!(
+            "CREATE TRIGGER keep_user BEFORE DELETE ON users WHEN OLD.id = {} \
+             BEGIN SELECT RAISE(ABORT, 'kept'); END",
+            user.id
+        )
+    } else {
+        "DROP TRIGGER IF EXISTS keep_user".to_string()
+    };
+    ctx.db.execute_unprepared(&sql).await.unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deletes_due_accounts() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    make_due(ctx, USER1_PID).await;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &image);
+    // not due yet
+    users::Model::find_by_pid(&ctx.db, USER2_PID)
+        .await
+        .unwrap()
+        .into_active_model()
+        .schedule_deletion(&ctx.db, Duration::days(1))
+        .await
+        .unwrap();
+
+    Worker::build(ctx).perform(WorkerArgs {}).await.unwrap();
+
+    assert!(users::Model::find_by_pid(&ctx.db, USER1_PID).await.is_err());
+    assert!(!support::has_objects(&s3, &image));
+    assert!(users::Model::find_by_pid(&ctx.db, USER2_PID).await.is_ok());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_puts_off_failed_deletions() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let failing = make_due(ctx, USER1_PID).await;
+    make_due(ctx, USER2_PID).await;
+
+    keep_user(ctx, &failing, true).await;
+    let result = Worker::build(ctx).perform(WorkerArgs {}).await;
+    keep_user(ctx, &failing, false).await;
+
+    // the one after it still went
+    assert!(result.is_ok());
+    assert!(users::Model::find_by_pid(&ctx.db, USER2_PID).await.is_err());
+    let kept = users::Model::find_by_pid(&ctx.db, USER1_PID).await.unwrap();
+    assert!(kept.deletes_at.unwrap() > Utc::now());
+    assert_eq!(kept.deletion_token, failing.deletion_token);
+
+    // left alone until then
+    Worker::build(ctx).perform(WorkerArgs {}).await.unwrap();
+    assert!(users::Model::find_by_pid(&ctx.db, USER1_PID).await.is_ok());
+}
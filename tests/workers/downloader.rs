@@ -0,0 +1,120 @@
+use std::io::Cursor;
+
+use AetherPix::{
+    app::App,
+    common::zip::ZipReader,
+    models::{
+        _entities::{exports::ExportStatus, images::Location},
+        exports::{self, ExportParams},
+    },
+    workers::downloader::{DownloadWorker, DownloadWorkerArgs},
+};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, R2_BUCKET, USER1_PID};
+
+async fn create_export(ctx: &AppContext) -> exports::Model {
+    exports::Model::create(
+        &ctx.db,
+        Uuid::parse_str(USER1_PID).unwrap(),
+        &ExportParams::default(),
+    )
+    .await
+    .unwrap()
+}
+
+async fn export(ctx: &AppContext, export: &exports::Model) -> exports::Model {
+    DownloadWorker::build(ctx)
+        .perform(DownloadWorkerArgs {
+            export_id: export.id,
+        })
+        .await
+        .unwrap();
+
+    exports::Model::find_by_id(&ctx.db, export.id)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_stores_archive_of_user_images() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let stored = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &stored);
+    support::create_image(ctx, Some(USER1_PID)).await;
+
+    let export = export(ctx, &create_export(ctx).await).await;
+
+    assert_eq!(export.status, ExportStatus::Ready);
+    assert_eq!((export.images, export.missing), (2, 1));
+    assert!(export.expires_at.is_some());
+    let (location, key) = export.stored().unwrap();
+    assert_eq!(location, Location::R2);
+    let archive = s3.get(R2_BUCKET, key).unwrap();
+    assert_eq!(
+        export.size,
+        Some(i64::try_from(archive.body.len()).unwrap())
+    );
+
+    let zip = ZipReader::new(Cursor::new(archive.body.to_vec()), 10)
+        .await
+        .unwrap();
+    let names: Vec<&str> = zip
+        .entries()
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    let original = format!("images/{}", stored.original_key());
+    assert_eq!(names, vec![original.as_str(), "manifest.json"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_fails_export_when_storage_errors() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    support::create_image(ctx, Some(USER1_PID)).await;
+
+    s3.set_failing(true);
+    let export = export(ctx, &create_export(ctx).await).await;
+    s3.set_failing(false);
+
+    assert_eq!(export.status, ExportStatus::Failed);
+    assert!(export.error.is_some());
+    assert!(export.finished_at.is_some());
+    assert_eq!(export.stored(), None);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_skips_exports_no_longer_queued() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let failed = create_export(ctx)
+        .await
+        .into_active_model()
+        .fail(&ctx.db, "Timed out".to_string())
+        .await
+        .unwrap();
+
+    let export = export(ctx, &failed).await;
+
+    assert_eq!(export.status, ExportStatus::Failed);
+    assert_eq!(export.error.as_deref(), Some("Timed out"));
+    // a missing one is no error either
+    assert!(
+        DownloadWorker::perform_later(ctx, DownloadWorkerArgs { export_id: 9999 })
+            .await
+            .is_ok()
+    );
+}
@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use AetherPix::{
+    app::App,
+    common::settings::SettingsService,
+    controllers::upload::{TempFileGuard, TempUpload},
+    models::{
+        _entities::imports::{ImportStatus, ImportStructure},
+        albums, images, imports,
+    },
+    workers::importer::{Worker, WorkerArgs},
+};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::{EntityTrait, IntoActiveModel};
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, USER1_PID};
+
+async fn create_import(ctx: &AppContext, user_pid: &str) -> imports::Model {
+    imports::Model::create(
+        &ctx.db,
+        Uuid::parse_str(user_pid).unwrap(),
+        "photos.zip",
+        ImportStructure::Albums,
+    )
+    .await
+    .unwrap()
+}
+
+/// Runs the import of `archive`, received into the temp dir like an upload.
+async fn import(ctx: &AppContext, import: &imports::Model, archive: &[u8]) -> imports::Model {
+    let mut upload = TempUpload::create("photos.zip").await.unwrap();
+    upload.write(archive).await.unwrap();
+    let tmp_file_guard = upload.keep().await.unwrap();
+    let path = tmp_file_guard.0.clone();
+
+    Worker::build(ctx)
+        .perform(WorkerArgs {
+            import_id: import.id,
+            tmp_file_guard,
+            quality: 80,
+            public: true,
+        })
+        .await
+        .unwrap();
+    // the guard deletes it in a task the test may not wait for
+    let _ = tokio::fs::remove_file(path).await;
+
+    imports::Model::find_by_id(&ctx.db, import.id)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_run_importer_worker() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    // Execute the worker ensuring that it operates in 'ForegroundBlocking' mode, which prevents the addition of your worker to the background
+    assert!(
+        Worker::perform_later(
+            &boot.app_context,
+            WorkerArgs {
+                import_id: 1,
+                tmp_file_guard: TempFileGuard(PathBuf::from("tmp_upload/missing.zip")),
+                quality: 80,
+                public: true,
+            }
+        )
+        .await
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_imports_images_into_albums_of_their_folders() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    support::fake_storage(ctx).await;
+    let png = support::png();
+    let archive = support::zip(&[
+        ("cat.png", &png),
+        ("trip/", b""),
+        ("trip/beach.png", &png),
+        ("__MACOSX/trip/._beach.png", b"resource fork"),
+        ("notes.txt", b"hello\n"),
+    ])
+    .await;
+
+    let import = import(ctx, &create_import(ctx, USER1_PID).await, &archive).await;
+
+    assert_eq!(import.status, ImportStatus::Done, "{:?}", import.error);
+    assert_eq!(
+        (import.total, import.imported, import.skipped, import.failed),
+        (2, 2, 0, 0)
+    );
+    assert!(import.finished_at.is_some());
+    let images = images::Entity::find().all(&ctx.db).await.unwrap();
+    let mut names: Vec<&str> = images.iter().map(|image| image.raw_name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["beach.png", "cat.png"]);
+
+    let albums = albums::Model::find_by_user_pid(&ctx.db, import.user_pid)
+        .await
+        .unwrap();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].name, "trip");
+    for image in images {
+        let contained = albums[0].contains_image(&ctx.db, image.id).await.unwrap();
+        assert_eq!(contained, image.raw_name == "beach.png");
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_skips_images_over_upload_size() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    support::fake_storage(ctx).await;
+    let big = vec![0; 1024 * 1024 + 1];
+    let archive = support::zip(&[("cat.png", &support::png()), ("big.png", &big)]).await;
+
+    SettingsService::set(&ctx.db, "upload_max_size_mb", "1")
+        .await
+        .unwrap();
+    let import = import(ctx, &create_import(ctx, USER1_PID).await, &archive).await;
+    SettingsService::set(&ctx.db, "upload_max_size_mb", "50")
+        .await
+        .unwrap();
+
+    assert_eq!(import.status, ImportStatus::Done, "{:?}", import.error);
+    assert_eq!(
+        (import.total, import.imported, import.skipped, import.failed),
+        (2, 1, 1, 0)
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_fails_import_of_broken_archive() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+
+    let import = import(ctx, &create_import(ctx, USER1_PID).await, b"not a zip").await;
+
+    assert_eq!(import.status, ImportStatus::Failed);
+    assert!(import.error.is_some());
+    assert!(import.finished_at.is_some());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_fails_import_of_missing_user() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let archive = support::zip(&[("cat.png", &support::png())]).await;
+    let gone = create_import(ctx, "33333333-3333-3333-3333-333333333333").await;
+
+    let import = import(ctx, &gone, &archive).await;
+
+    assert_eq!(import.status, ImportStatus::Failed);
+    assert!(import.error.is_some());
+    assert_eq!(import.imported, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_skips_imports_no_longer_queued() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let archive = support::zip(&[("cat.png", &support::png())]).await;
+    let failed = create_import(ctx, USER1_PID)
+        .await
+        .into_active_model()
+        .fail(&ctx.db, "Timed out".to_string())
+        .await
+        .unwrap();
+
+    let import = import(ctx, &failed, &archive).await;
+
+    assert_eq!(import.status, ImportStatus::Failed);
+    assert_eq!(import.error.as_deref(), Some("Timed out"));
+    assert_eq!(import.imported, 0);
+}
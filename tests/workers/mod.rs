@@ -1,3 +1,11 @@
 
 
-pub mod thumbnail;
\ No newline at end of file
+pub mod account_deleter;
+pub mod downloader;
+pub mod importer;
+pub mod purger;
+pub mod regenerator;
+pub mod relocator;
+pub mod remover;
+pub mod thumbnail;
+pub mod tiering;
@@ -0,0 +1,130 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::{exports::ExportStatus, images::Location},
+        exports::{self, ExportParams, StoredExport},
+        images,
+    },
+    workers::purger::{Worker, WorkerArgs},
+};
+use chrono::{TimeDelta, Utc};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::IntoActiveModel;
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, R2_BUCKET, USER1_PID};
+
+/// A ready export whose link ran out, its archive stored in R2.
+async fn create_expired_export(ctx: &AppContext, s3: &support::FakeS3) -> exports::Model {
+    let export = exports::Model::create(
+        &ctx.db,
+        Uuid::parse_str(USER1_PID).unwrap(),
+        &ExportParams::default(),
+    )
+    .await
+    .unwrap();
+    let file_key = format!("exports/{USER1_PID}/{}.zip", export.id);
+    s3.put(R2_BUCKET, &file_key, "zip");
+
+    export
+        .into_active_model()
+        .finish(
+            &ctx.db,
+            StoredExport {
+                location: Location::R2,
+                file_key,
+                size: 3,
+                images: 0,
+                missing: 0,
+                expires_at: (Utc::now() - TimeDelta::minutes(1)).into(),
+            },
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_purges_expired_images() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+
+    let past = Some(Utc::now() - TimeDelta::minutes(1));
+    let future = Some(Utc::now() + TimeDelta::hours(1));
+    let (mut expired, mut kept) = (Vec::new(), Vec::new());
+    for (gone, expires_at, max_views, views) in [
+        (true, past, None, 0),
+        (true, None, Some(3), 3),
+        (true, future, Some(1), 1),
+        (false, future, None, 0),
+        (false, None, Some(3), 2),
+        (false, None, None, 100),
+    ] {
+        let image = support::create_image(ctx, Some(USER1_PID)).await;
+        let image = support::set_expiry(ctx, image, expires_at, max_views, views).await;
+        support::store_image(&s3, &image);
+        if gone {
+            expired.push(image);
+        } else {
+            kept.push(image);
+        }
+    }
+
+    Worker::build(ctx).perform(WorkerArgs {}).await.unwrap();
+
+    for image in &expired {
+        assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_err());
+        assert!(!support::has_objects(&s3, image));
+    }
+    for image in &kept {
+        assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_ok());
+        assert!(support::has_objects(&s3, image));
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_purges_expired_exports() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let export = create_expired_export(ctx, &s3).await;
+    let key = export.file_key.clone().unwrap();
+
+    Worker::build(ctx).perform(WorkerArgs {}).await.unwrap();
+
+    let export = exports::Model::find_by_id(&ctx.db, export.id)
+        .await
+        .unwrap();
+    assert_eq!(export.status, ExportStatus::Expired);
+    assert!(!s3.contains(R2_BUCKET, &key));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_expires_exports_whose_archive_cannot_be_deleted() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let first = create_expired_export(ctx, &s3).await;
+    let second = create_expired_export(ctx, &s3).await;
+
+    s3.set_failing(true);
+    let result = Worker::build(ctx).perform(WorkerArgs {}).await;
+    s3.set_failing(false);
+
+    assert!(result.is_ok());
+    for export in [first, second] {
+        let export = exports::Model::find_by_id(&ctx.db, export.id)
+            .await
+            .unwrap();
+        assert_eq!(export.status, ExportStatus::Expired);
+        // left for `check_storage` to find
+        assert!(s3.contains(R2_BUCKET, &format!("exports/{USER1_PID}/{}.zip", export.id)));
+    }
+}
@@ -0,0 +1,99 @@
+use AetherPix::{
+    app::App,
+    models::{
+        _entities::regenerations::RegenerationStatus,
+        images,
+        regenerations::{self, RegenerateParams},
+    },
+    workers::regenerator::{Worker, WorkerArgs},
+};
+use loco_rs::{bgworker::BackgroundWorker, testing::prelude::*};
+use serial_test::serial;
+use uuid::Uuid;
+
+use crate::support::{self, AVIF_BUCKET, ORIGIN_BUCKET, PREVIEW_BUCKET, USER1_PID, USER2_PID};
+
+#[tokio::test]
+#[serial]
+async fn test_run_regenerator_worker() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    // Execute the worker ensuring that it operates in 'ForegroundBlocking' mode, which prevents the addition of your worker to the background
+    assert!(
+        Worker::perform_later(&boot.app_context, WorkerArgs { regeneration_id: 1 })
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_encodes_images_of_the_run() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let encoded = support::upload_png(ctx, Some(USER1_PID)).await;
+    assert!(s3.contains(ORIGIN_BUCKET, &encoded.original_key()));
+    let old_avif = s3.get(AVIF_BUCKET, &encoded.file_name).unwrap().body;
+    // uploaded before originals were kept
+    let skipped = support::create_image(ctx, Some(USER1_PID)).await;
+    s3.put(PREVIEW_BUCKET, &skipped.file_name, "preview");
+    s3.put(AVIF_BUCKET, &skipped.file_name, "avif");
+    let other = support::upload_png(ctx, Some(USER2_PID)).await;
+    let other_avif = s3.get(AVIF_BUCKET, &other.file_name).unwrap().body;
+
+    let regeneration = regenerations::Model::create(
+        &ctx.db,
+        &RegenerateParams {
+            user_pid: Some(Uuid::parse_str(USER1_PID).unwrap()),
+            location: None,
+            created_from: None,
+            created_to: None,
+            quality: 60,
+            delay_ms: 0,
+        },
+    )
+    .await
+    .unwrap();
+    Worker::build(ctx)
+        .perform(WorkerArgs {
+            regeneration_id: regeneration.id,
+        })
+        .await
+        .unwrap();
+
+    let regeneration = regenerations::Model::find_by_id(&ctx.db, regeneration.id)
+        .await
+        .unwrap();
+    assert_eq!(regeneration.status, RegenerationStatus::Finished);
+    assert_eq!(
+        (
+            regeneration.total,
+            regeneration.encoded,
+            regeneration.skipped,
+            regeneration.failed
+        ),
+        (2, 1, 1, 0)
+    );
+
+    let preview = s3.get(PREVIEW_BUCKET, &encoded.file_name).unwrap().body;
+    let avif = s3.get(AVIF_BUCKET, &encoded.file_name).unwrap().body;
+    assert_ne!(avif, old_avif);
+    // the old derivatives no longer count
+    let image = images::Model::find_by_id(&ctx.db, encoded.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        image.size,
+        (support::png().len() + preview.len() + avif.len()) as i64
+    );
+    assert_eq!(
+        s3.get(AVIF_BUCKET, &skipped.file_name).unwrap().body,
+        "avif"
+    );
+    assert_eq!(
+        s3.get(AVIF_BUCKET, &other.file_name).unwrap().body,
+        other_avif
+    );
+}
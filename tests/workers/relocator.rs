@@ -0,0 +1,141 @@
+use AetherPix::{
+    app::App,
+    common::client,
+    models::{_entities::images::Location, images, pending_removals},
+    workers::relocator::{Worker, WorkerArgs},
+};
+use loco_rs::{bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+use crate::support::{self, AVIF_BUCKET, ORIGIN_BUCKET, PREVIEW_BUCKET, R2_BUCKET, USER1_PID};
+
+#[tokio::test]
+#[serial]
+async fn test_run_relocator_worker() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    // Execute the worker ensuring that it operates in 'ForegroundBlocking' mode, which prevents the addition of your worker to the background
+    assert!(
+        Worker::perform_later(
+            &boot.app_context,
+            WorkerArgs {
+                image_ids: vec![],
+                to: Location::R2,
+            }
+        )
+        .await
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_moves_images_there_and_back() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &image);
+    s3.put(ORIGIN_BUCKET, &image.original_key(), support::png());
+    let original_key = client::r2_original_key(&image.original_key());
+
+    Worker::build(ctx)
+        .perform(WorkerArgs {
+            image_ids: vec![image.id],
+            to: Location::R2,
+        })
+        .await
+        .unwrap();
+
+    let moved = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+    assert_eq!(moved.location, Location::R2);
+    assert_eq!(
+        moved.url,
+        format!("http://localhost/i/r2/view/{}", image.file_name)
+    );
+    assert_eq!(s3.get(R2_BUCKET, &image.file_name).unwrap().body, "avif");
+    assert!(s3.contains(R2_BUCKET, &original_key));
+    assert!(!s3.contains(ORIGIN_BUCKET, &image.original_key()));
+    assert!(!s3.contains(PREVIEW_BUCKET, &image.file_name));
+    assert!(!s3.contains(AVIF_BUCKET, &image.file_name));
+
+    Worker::build(ctx)
+        .perform(WorkerArgs {
+            image_ids: vec![image.id],
+            to: Location::Local,
+        })
+        .await
+        .unwrap();
+
+    let back = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+    assert_eq!(back.location, Location::Local);
+    assert_eq!(back.url, image.url);
+    assert!(!s3.contains(R2_BUCKET, &image.file_name));
+    assert!(!s3.contains(R2_BUCKET, &original_key));
+    assert_eq!(
+        s3.get(ORIGIN_BUCKET, &image.original_key()).unwrap().body,
+        support::png()
+    );
+    // encoded again from the original, not the copied AVIF
+    let preview = s3.get(PREVIEW_BUCKET, &image.file_name).unwrap();
+    let avif = s3.get(AVIF_BUCKET, &image.file_name).unwrap();
+    assert_ne!(preview.body, "avif");
+    assert_ne!(avif.body, "avif");
+    assert!(
+        pending_removals::Entity::find()
+            .all(&ctx.db)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_skips_images_already_there() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &image);
+
+    Worker::build(ctx)
+        .perform(WorkerArgs {
+            image_ids: vec![image.id, image.id + 1],
+            to: Location::Local,
+        })
+        .await
+        .unwrap();
+
+    let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+    assert_eq!(image.location, Location::Local);
+    assert!(support::has_objects(&s3, &image));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_leaves_image_when_copy_fails() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+
+    // nothing stored to copy
+    assert!(
+        Worker::build(ctx)
+            .perform(WorkerArgs {
+                image_ids: vec![image.id],
+                to: Location::R2,
+            })
+            .await
+            .is_err()
+    );
+
+    let image = images::Model::find_by_id(&ctx.db, image.id).await.unwrap();
+    assert_eq!(image.location, Location::Local);
+    assert!(!s3.contains(R2_BUCKET, &image.file_name));
+}
@@ -0,0 +1,91 @@
+use AetherPix::{
+    app::App,
+    controllers::image::remove_image,
+    models::{images, pending_removals},
+    workers::{
+        purger,
+        remover::{Worker, WorkerArgs},
+    },
+};
+use loco_rs::{bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, IntoActiveModel};
+use serial_test::serial;
+
+use crate::support::{self, USER1_PID};
+
+#[tokio::test]
+#[serial]
+async fn test_removes_objects_of_deleted_image() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &image);
+
+    remove_image(ctx, image.clone()).await.unwrap();
+
+    assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_err());
+    assert!(!support::has_objects(&s3, &image));
+    let pending = pending_removals::Entity::find().all(&ctx.db).await.unwrap();
+    assert!(pending.is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_keeps_failed_removal_for_the_purger() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+
+    let image = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &image);
+
+    s3.set_failing(true);
+    remove_image(ctx, image.clone()).await.unwrap();
+    s3.set_failing(false);
+
+    assert!(images::Model::find_by_id(&ctx.db, image.id).await.is_err());
+    assert!(support::has_objects(&s3, &image));
+    let pending = pending_removals::Entity::find().all(&ctx.db).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    let removal = pending[0].clone();
+    assert_eq!(removal.file_name, image.file_name);
+    assert_eq!(removal.original_key, image.original_key());
+    assert_eq!(removal.attempts, 1);
+    assert!(removal.error.is_some());
+    assert!(removal.retry_at > chrono::Utc::now());
+
+    // not due yet
+    purger::Worker::build(ctx)
+        .perform(purger::WorkerArgs {})
+        .await
+        .unwrap();
+    assert!(support::has_objects(&s3, &image));
+
+    let mut due = removal.into_active_model();
+    due.retry_at = ActiveValue::Set(chrono::Utc::now().into());
+    due.update(&ctx.db).await.unwrap();
+    purger::Worker::build(ctx)
+        .perform(purger::WorkerArgs {})
+        .await
+        .unwrap();
+
+    assert!(!support::has_objects(&s3, &image));
+    let pending = pending_removals::Entity::find().all(&ctx.db).await.unwrap();
+    assert!(pending.is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_skips_removal_already_done() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    assert!(
+        Worker::perform_later(&boot.app_context, WorkerArgs { removal_id: 42 })
+            .await
+            .is_ok()
+    );
+}
@@ -1,20 +1,26 @@
-use loco_rs::{bgworker::BackgroundWorker, testing::prelude::*};
 use AetherPix::{
     app::App,
+    controllers::upload::TempFileGuard,
     workers::thumbnail::{Worker, WorkerArgs},
 };
+use loco_rs::{bgworker::BackgroundWorker, testing::prelude::*};
 use serial_test::serial;
 
 #[tokio::test]
 #[serial]
-async fn test_run_thumbnail_worker() {
+async fn test_fails_on_missing_upload() {
     let boot = boot_test::<App>().await.unwrap();
 
-    // Execute the worker ensuring that it operates in 'ForegroundBlocking' mode, which prevents the addition of your worker to the background
+    let args = WorkerArgs {
+        tmp_file_guard: TempFileGuard("tmp_upload/missing.png".into()),
+        preview_key: "missing.avif".to_string(),
+        quality: 80,
+        original_key: None,
+    };
+
     assert!(
-        Worker::perform_later(&boot.app_context,WorkerArgs {})
+        Worker::perform_later(&boot.app_context, args)
             .await
-            .is_ok()
+            .is_err()
     );
-    // Include additional assert validations after the execution of the worker
 }
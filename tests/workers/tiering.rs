@@ -0,0 +1,108 @@
+use AetherPix::{
+    app::App,
+    common::settings::SettingsService,
+    models::{_entities::images::Location, images},
+    workers::tiering::{Worker, WorkerArgs},
+};
+use loco_rs::{app::AppContext, bgworker::BackgroundWorker, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, ActiveValue, IntoActiveModel};
+use serial_test::serial;
+
+use crate::support::{self, USER1_PID};
+
+/// Tiers images older than 30 days, `batch_size` at a time.
+async fn set_policy(ctx: &AppContext, after_days: &str, batch_size: &str) {
+    SettingsService::set(&ctx.db, "tiering_after_days", after_days)
+        .await
+        .unwrap();
+    SettingsService::set(&ctx.db, "tiering_batch_size", batch_size)
+        .await
+        .unwrap();
+}
+
+async fn create_old_image(ctx: &AppContext) -> images::Model {
+    let mut image = support::create_image(ctx, Some(USER1_PID))
+        .await
+        .into_active_model();
+    image.created_at = ActiveValue::Set((chrono::Utc::now() - chrono::Duration::days(60)).into());
+
+    image.update(&ctx.db).await.unwrap()
+}
+
+async fn location(ctx: &AppContext, image: &images::Model) -> Location {
+    images::Model::find_by_id(&ctx.db, image.id)
+        .await
+        .unwrap()
+        .location
+}
+
+#[tokio::test]
+#[serial]
+async fn test_run_tiering_worker() {
+    let boot = boot_test::<App>().await.unwrap();
+
+    // Execute the worker ensuring that it operates in 'ForegroundBlocking' mode, which prevents the addition of your worker to the background
+    assert!(
+        Worker::perform_later(&boot.app_context, WorkerArgs {})
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_moves_cold_images() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    set_policy(ctx, "30", "100").await;
+    let old = create_old_image(ctx).await;
+    let recent = support::create_image(ctx, Some(USER1_PID)).await;
+    support::store_image(&s3, &old);
+    support::store_image(&s3, &recent);
+
+    let result = Worker::build(ctx).perform(WorkerArgs {}).await;
+    set_policy(ctx, "0", "100").await;
+    result.unwrap();
+
+    assert_eq!(location(ctx, &old).await, Location::R2);
+    assert_eq!(location(ctx, &recent).await, Location::Local);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_skips_failed_and_broken_images() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+    let s3 = support::fake_storage(ctx).await;
+    set_policy(ctx, "30", "1").await;
+    // nothing stored, so it fails to move
+    let failing = create_old_image(ctx).await;
+    let broken = create_old_image(ctx).await;
+    let cold = create_old_image(ctx).await;
+    support::store_image(&s3, &broken);
+    support::store_image(&s3, &cold);
+    images::Model::set_broken(&ctx.db, &[broken.id], true)
+        .await
+        .unwrap();
+
+    let first = Worker::build(ctx).perform(WorkerArgs {}).await;
+    let failed = images::Model::find_by_id(&ctx.db, failing.id)
+        .await
+        .unwrap();
+    let second = Worker::build(ctx).perform(WorkerArgs {}).await;
+    set_policy(ctx, "0", "100").await;
+    first.unwrap();
+    second.unwrap();
+
+    assert_eq!(failed.location, Location::Local);
+    assert!(failed.tier_failed_at.is_some());
+    // the next run goes past both
+    assert_eq!(location(ctx, &failing).await, Location::Local);
+    assert_eq!(location(ctx, &broken).await, Location::Local);
+    assert_eq!(location(ctx, &cold).await, Location::R2);
+    let cold = images::Model::find_by_id(&ctx.db, cold.id).await.unwrap();
+    assert_eq!(cold.tier_failed_at, None);
+}